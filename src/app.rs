@@ -1,17 +1,76 @@
-use crate::{FractalType, FractalParams, FractalGenerator, TerminalRenderer, Config};
-use crossterm::event::{self, Event, KeyCode, KeyEvent};
+use crate::{Fractal, FractalType, FractalParams, FractalGenerator, FractalSample, TerminalRenderer, Config, Precision};
+use crate::bigfloat::BigFloat;
+use crate::expression::{self, ParseError};
+use crate::polynomial::Polynomial;
+use crate::buddhabrot::{DensityAccumulator, DEFAULT_NEBULABROT_LIMITS};
+use crate::history::{NavigationHistory, ViewState};
+use crate::bookmark::{self, Bookmark, Bookmarks};
+use crate::config::DEFAULT_CONFIG_PATH;
+use crate::command::{self, Command};
+use crate::export;
+use crate::keymap::{Action, Keymap};
+use crate::renderer::CellMode;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
 use ratatui::{
     backend::Backend,
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Style},
+    text::Line,
     widgets::{Block, Borders, Clear, Paragraph},
     Frame, Terminal,
 };
 use std::time::{Duration, Instant};
 use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
 use rand::Rng;
 use num_complex::Complex;
 
+// Time spent tracing Buddhabrot orbits per frame before the image is refreshed
+const DENSITY_FRAME_BUDGET: Duration = Duration::from_millis(40);
+const DENSITY_BATCH: usize = 2048;
+// Refinement stops once the density image has this many samples per pixel
+const DENSITY_MAX_SAMPLES_PER_PIXEL: f64 = 4096.0;
+// Command lines remembered for Up/Down in the `:` prompt
+const COMMAND_HISTORY_LIMIT: usize = 50;
+
+// Rectangle being marked for a box zoom, in pixels of the fractal image
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoxSelection {
+    /// First corner; `None` while a keyboard selection is still placing it
+    pub anchor: Option<(i32, i32)>,
+    /// Opposite corner, following the mouse or the arrow keys
+    pub cursor: (i32, i32),
+    /// Driven by the arrow keys rather than a left-button drag
+    pub keyboard: bool,
+}
+
+// State of the bookmarks popup
+#[derive(Debug, Clone, PartialEq)]
+pub struct BookmarkPopup {
+    pub selection: usize,
+    /// Name being typed, when adding or renaming
+    pub edit: Option<BookmarkEdit>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BookmarkEdit {
+    Add { name: String },
+    Rename { index: usize, name: String },
+}
+
+// The parameter-plane view Julia mode was entered from, restored when it is left
+#[derive(Debug, Clone)]
+pub struct ParameterPlaneView {
+    pub fractal_type: FractalType,
+    pub equation: String,
+    pub center_x: BigFloat,
+    pub center_y: BigFloat,
+    pub zoom_factor: BigFloat,
+    /// The Julia set switched to; leaving for any other fractal forgets this view
+    pub julia_type: FractalType,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum AppMode {
     Interactive,
@@ -23,6 +82,8 @@ pub enum AppMode {
 pub enum InputMode {
     Normal,
     Editing,
+    /// Typing a `:` command
+    Command,
 }
 
 pub struct App {
@@ -32,27 +93,61 @@ pub struct App {
     pub fractal_generator: FractalGenerator,
     pub renderer: TerminalRenderer,
     pub config: Config,
-    pub fractal_data: Vec<Vec<u32>>,
+    /// Key chords for every normal-mode action, from `config.controls`
+    pub keymap: Keymap,
+    pub fractal_data: Vec<Vec<FractalSample>>,
+    /// Buddhabrot/Nebulabrot image being refined a batch at a time between frames
+    pub density: Option<DensityAccumulator>,
     pub auto_generation_timer: Instant,
     pub auto_generation_phase: u32,
     pub auto_target_zoom: f64,
     pub auto_target_x: f64,
     pub auto_target_y: f64,
-    pub zoom_factor: f64,
-    pub center_x: f64,
-    pub center_y: f64,
+    pub zoom_factor: BigFloat,
+    pub center_x: BigFloat,
+    pub center_y: BigFloat,
     pub max_iterations: u32,
     pub current_equation: String,
+    pub equation_error: Option<ParseError>,
     pub current_fractal_type: FractalType,
+    pub parameter_plane_view: Option<ParameterPlaneView>,
     pub status_message: String,
     pub show_help: bool,
-    pub fractal_cache: HashMap<String, Vec<Vec<u32>>>,
+    pub fractal_cache: HashMap<String, Vec<Vec<FractalSample>>>,
     pub last_render_time: Instant,
     pub frame_count: u32,
     pub fps: f64,
     pub total_generation_time: Duration,
     pub generation_count: u32,
     pub fractal_display_area: Option<Rect>,
+    /// Parameter plane and its Julia set side by side, linked by a crosshair
+    pub split_view: bool,
+    /// Crosshair position in cells from the middle of the fractal pane
+    pub crosshair: (i32, i32),
+    /// Julia set for the point under the crosshair
+    pub julia_data: Vec<Vec<FractalSample>>,
+    pub julia_display_area: Option<Rect>,
+    /// Cell the right-button drag last panned from
+    pub pan_anchor: Option<(u16, u16)>,
+    /// Box zoom in progress, drawn over the fractal pane
+    pub box_selection: Option<BoxSelection>,
+    /// Views visited, for undo/redo and the history popup
+    pub history: NavigationHistory,
+    pub show_history: bool,
+    /// Entry highlighted in the history popup
+    pub history_selection: usize,
+    pub bookmarks: Bookmarks,
+    pub bookmarks_path: PathBuf,
+    /// Why the bookmarks file could not be read; saving is refused while set
+    /// so the file is not overwritten
+    pub bookmarks_error: Option<String>,
+    pub bookmark_popup: Option<BookmarkPopup>,
+    /// Text typed after the `:` prompt
+    pub command_line: String,
+    /// Commands entered so far, oldest first
+    pub command_history: Vec<String>,
+    /// Entry recalled with Up/Down; `None` while typing a new line
+    pub command_history_index: Option<usize>,
     pub last_terminal_size: Option<(u16, u16)>,
 }
 
@@ -64,10 +159,22 @@ impl Default for App {
 
 impl App {
     pub fn new() -> Self {
-        let config = Config::default();
-        let fractal_generator = FractalGenerator::new();
-        let renderer = TerminalRenderer::new();
-        
+        Self::with_config(Config::default(), DEFAULT_CONFIG_PATH)
+    }
+
+    /// An app started from a loaded config; bookmarks are kept beside `config_path`
+    pub fn with_config<P: AsRef<std::path::Path>>(config: Config, config_path: P) -> Self {
+        let fractal_generator = FractalGenerator::from_config(&config);
+        let renderer = TerminalRenderer::from_config(&config.display);
+        let (zoom_factor, center_x, center_y, max_iterations) = config.get_default_fractal_params();
+        let bookmarks_path = bookmark::bookmarks_path_for(config_path);
+        let keymap = Keymap::from_config(&config.controls).unwrap_or_default();
+        // An unreadable file leaves the shipped set in memory only
+        let (bookmarks, bookmarks_error) = match Bookmarks::load(&bookmarks_path) {
+            Ok(bookmarks) => (bookmarks, None),
+            Err(error) => (Bookmarks::default(), Some(error)),
+        };
+
         Self {
             should_quit: false,
             mode: AppMode::Interactive,
@@ -75,19 +182,26 @@ impl App {
             fractal_generator,
             renderer,
             config,
+            keymap,
             fractal_data: Vec::new(),
+            density: None,
             auto_generation_timer: Instant::now(),
             auto_generation_phase: 0,
             auto_target_zoom: 1.0,
             auto_target_x: -0.5,
             auto_target_y: 0.0,
-            zoom_factor: 1.0,
-            center_x: -0.5,
-            center_y: 0.0,
-            max_iterations: 256,
+            zoom_factor,
+            center_x,
+            center_y,
+            max_iterations,
             current_equation: "z^2 + c".to_string(),
+            equation_error: None,
             current_fractal_type: FractalType::Mandelbrot,
-            status_message: "Ready".to_string(),
+            parameter_plane_view: None,
+            status_message: match &bookmarks_error {
+                Some(error) => format!("Bookmarks will not be saved: {}", error),
+                None => "Ready".to_string(),
+            },
             show_help: false,
             fractal_cache: HashMap::new(),
             last_render_time: Instant::now(),
@@ -96,6 +210,22 @@ impl App {
             total_generation_time: Duration::new(0, 0),
             generation_count: 0,
             fractal_display_area: None,
+            split_view: false,
+            crosshair: (0, 0),
+            julia_data: Vec::new(),
+            julia_display_area: None,
+            pan_anchor: None,
+            box_selection: None,
+            history: NavigationHistory::default(),
+            show_history: false,
+            history_selection: 0,
+            bookmarks,
+            bookmarks_path,
+            bookmarks_error,
+            bookmark_popup: None,
+            command_line: String::new(),
+            command_history: Vec::new(),
+            command_history_index: None,
             last_terminal_size: None,
         }
     }
@@ -120,6 +250,9 @@ impl App {
                 Event::Key(key) => {
                     self.handle_key_event(key);
                 }
+                Event::Mouse(mouse) => {
+                    self.handle_mouse_event(mouse);
+                }
                 Event::Resize(width, height) => {
                     self.handle_resize_event(width, height);
                 }
@@ -129,11 +262,188 @@ impl App {
         Ok(())
     }
 
-    fn handle_key_event(&mut self, key: KeyEvent) {
+    pub fn handle_key_event(&mut self, key: KeyEvent) {
         match self.input_mode {
             InputMode::Normal => self.handle_normal_key_event(key),
             InputMode::Editing => self.handle_editing_key_event(key),
+            InputMode::Command => self.handle_command_key_event(key),
+        }
+    }
+
+    pub fn handle_mouse_event(&mut self, mouse: MouseEvent) {
+        if self.input_mode != InputMode::Normal || self.show_help {
+            return;
+        }
+
+        match mouse.kind {
+            // A left drag marks a box to zoom into; a left click without one recenters
+            MouseEventKind::Down(MouseButton::Left) => {
+                self.box_selection = self.cell_to_pixel(mouse.column, mouse.row).map(|pixel| BoxSelection {
+                    anchor: Some(pixel),
+                    cursor: pixel,
+                    keyboard: false,
+                });
+            },
+            MouseEventKind::Drag(MouseButton::Left) => {
+                let pixel = self.cell_to_pixel_clamped(mouse.column, mouse.row);
+                if let (Some(selection), Some(pixel)) = (self.box_selection.as_mut(), pixel) {
+                    selection.cursor = pixel;
+                }
+            },
+            MouseEventKind::Up(MouseButton::Left) => {
+                let Some(selection) = self.box_selection.take() else { return };
+                match selection.anchor {
+                    Some(anchor) if anchor != selection.cursor => self.zoom_to_box(anchor, selection.cursor),
+                    _ => {
+                        let (x, y) = self.pixel_to_view_offset(selection.cursor);
+                        self.pan_by(x, y);
+                        self.status_message = format!("Centered on ({:.6}, {:.6})", self.center_x, self.center_y);
+                    }
+                }
+            },
+            // One notch zooms as far as one press of the zoom keys
+            MouseEventKind::ScrollUp => self.zoom_at_cell(mouse.column, mouse.row, self.zoom_step()),
+            MouseEventKind::ScrollDown => self.zoom_at_cell(mouse.column, mouse.row, 1.0 / self.zoom_step()),
+            MouseEventKind::Down(MouseButton::Right) => {
+                self.pan_anchor = self.cell_to_view_offset(mouse.column, mouse.row).map(|_| (mouse.column, mouse.row));
+            },
+            MouseEventKind::Drag(MouseButton::Right) => {
+                let Some((anchor_column, anchor_row)) = self.pan_anchor else { return };
+                let (Some(from), Some(to)) = (
+                    self.cell_to_view_offset(anchor_column, anchor_row),
+                    self.cell_to_view_offset(mouse.column, mouse.row),
+                ) else {
+                    return;
+                };
+                // Drag the image along with the pointer
+                self.pan_anchor = Some((mouse.column, mouse.row));
+                self.pan_by(from.0 - to.0, from.1 - to.1);
+            },
+            MouseEventKind::Up(MouseButton::Right) => self.pan_anchor = None,
+            _ => {}
+        }
+    }
+
+    // Size of the image in cells; in braille mode each holds several samples
+    fn image_size(&self) -> (i32, i32) {
+        let (width, height) = self.renderer.cell_size(&self.fractal_data);
+        (width as i32, height as i32)
+    }
+
+    // The fractal image pixel drawn at a screen cell; None outside the rendered image
+    fn cell_to_pixel(&self, column: u16, row: u16) -> Option<(i32, i32)> {
+        let area = self.fractal_display_area?;
+        let inside_pane = column > area.x && column < (area.x + area.width).saturating_sub(1)
+            && row > area.y && row < (area.y + area.height).saturating_sub(1);
+        let (x, y) = self.cell_to_pixel_clamped(column, row)?;
+        (inside_pane && self.pixel_to_cell((x, y)) == Some((column, row))).then_some((x, y))
+    }
+
+    // Like `cell_to_pixel`, but cells outside the image map to the nearest edge pixel
+    fn cell_to_pixel_clamped(&self, column: u16, row: u16) -> Option<(i32, i32)> {
+        let area = self.fractal_display_area?;
+        let (width, height) = self.image_size();
+        if width == 0 || height == 0 {
+            return None;
+        }
+        let (origin_x, origin_y) = sample_origin(area, width as usize, height as usize);
+        Some(((column as i32 - origin_x).clamp(0, width - 1), (row as i32 - origin_y).clamp(0, height - 1)))
+    }
+
+    // The screen cell a fractal image pixel is drawn at, if it is on screen
+    fn pixel_to_cell(&self, (x, y): (i32, i32)) -> Option<(u16, u16)> {
+        let area = self.fractal_display_area?;
+        let (width, height) = self.image_size();
+        let (origin_x, origin_y) = sample_origin(area, width as usize, height as usize);
+        let (column, row) = (origin_x + x, origin_y + y);
+        let inside = column > area.x as i32 && column < (area.x + area.width) as i32 - 1
+            && row > area.y as i32 && row < (area.y + area.height) as i32 - 1;
+        inside.then_some((column as u16, row as u16))
+    }
+
+    // Where a pixel's point lies relative to the view center, in units of the zoom-1 view
+    fn pixel_to_view_offset(&self, (x, y): (i32, i32)) -> (f64, f64) {
+        // Same pixel grid as the generator: the view spans 4 units across each axis
+        let (width, height) = self.image_size();
+        (
+            (x as f64 - width as f64 / 2.0) * 4.0 / width as f64,
+            (y as f64 - height as f64 / 2.0) * 4.0 / height as f64,
+        )
+    }
+
+    fn cell_to_view_offset(&self, column: u16, row: u16) -> Option<(f64, f64)> {
+        self.cell_to_pixel(column, row).map(|pixel| self.pixel_to_view_offset(pixel))
+    }
+
+    // Zooms so the box between two corner pixels fills the display. The zoom is
+    // the same on both axes, so the box's shorter side is widened to keep the aspect ratio.
+    fn zoom_to_box(&mut self, (x0, y0): (i32, i32), (x1, y1): (i32, i32)) {
+        let (width, height) = self.image_size();
+        let box_width = (x1 - x0).abs() + 1;
+        let box_height = (y1 - y0).abs() + 1;
+        let scale = (box_width as f64 / width as f64).max(box_height as f64 / height as f64);
+        let center = (x0.min(x1) as f64 + (box_width - 1) as f64 / 2.0, y0.min(y1) as f64 + (box_height - 1) as f64 / 2.0);
+        let offset = (
+            (center.0 - width as f64 / 2.0) * 4.0 / width as f64,
+            (center.1 - height as f64 / 2.0) * 4.0 / height as f64,
+        );
+
+        let precision = self.coordinate_precision();
+        let zoom = self.zoom_factor.with_precision(precision);
+        self.center_x = &self.center_x.with_precision(precision) + &(&BigFloat::from(offset.0) / &zoom);
+        self.center_y = &self.center_y.with_precision(precision) + &(&BigFloat::from(offset.1) / &zoom);
+        self.zoom_factor = &zoom / &BigFloat::from(scale);
+        self.regenerate_fractal();
+        self.status_message = format!("Box zoom to {:.2}x at ({:.6}, {:.6})",
+            self.zoom_factor, self.center_x, self.center_y);
+    }
+
+    // Keys while a keyboard box selection is active: arrows move the corner,
+    // Enter or b marks it, Esc cancels
+    fn handle_box_key_event(&mut self, key: KeyEvent) {
+        let Some(mut selection) = self.box_selection else { return };
+        let (width, height) = self.image_size();
+        let step = if key.modifiers.contains(KeyModifiers::SHIFT) { 8 } else { 1 };
+        let (x, y) = selection.cursor;
+        match key.code {
+            KeyCode::Up => selection.cursor = (x, (y - step).max(0)),
+            KeyCode::Down => selection.cursor = (x, (y + step).min(height - 1)),
+            KeyCode::Left => selection.cursor = ((x - step).max(0), y),
+            KeyCode::Right => selection.cursor = ((x + step).min(width - 1), y),
+            _ if key.code == KeyCode::Enter || self.keymap.action_for(key) == Some(Action::BoxZoom) => match selection.anchor {
+                None => {
+                    selection.anchor = Some(selection.cursor);
+                    self.status_message = "Box Zoom: move to the opposite corner, Enter to zoom".to_string();
+                },
+                Some(anchor) => {
+                    self.box_selection = None;
+                    self.zoom_to_box(anchor, selection.cursor);
+                    return;
+                },
+            },
+            KeyCode::Esc => {
+                self.box_selection = None;
+                self.status_message = "Box Zoom cancelled".to_string();
+                return;
+            },
+            _ => {},
         }
+        self.box_selection = Some(selection);
+    }
+
+    // Zooms by `factor` while keeping the point under the cell where it is on screen
+    fn zoom_at_cell(&mut self, column: u16, row: u16, factor: f64) {
+        let Some((x, y)) = self.cell_to_view_offset(column, row) else { return };
+        let precision = self.coordinate_precision();
+        let zoom = self.zoom_factor.with_precision(precision);
+        // The point sits at offset / zoom before and must sit there at the new zoom too
+        let shift = 1.0 - 1.0 / factor;
+        self.center_x = &self.center_x.with_precision(precision) + &(&BigFloat::from(x * shift) / &zoom);
+        self.center_y = &self.center_y.with_precision(precision) + &(&BigFloat::from(y * shift) / &zoom);
+        self.zoom_factor = &zoom * &BigFloat::from(factor);
+        self.regenerate_fractal();
+        self.status_message = format!("Zoomed to {:.2}x at ({:.6}, {:.6})",
+            self.zoom_factor, self.center_x, self.center_y);
     }
 
     pub fn handle_resize_event(&mut self, width: u16, height: u16) {
@@ -145,6 +455,7 @@ impl App {
 
             // Clear the fractal display area so it gets recalculated
             self.fractal_display_area = None;
+            self.julia_display_area = None;
 
             // Clear cache since the fractal dimensions will change
             self.fractal_cache.clear();
@@ -155,71 +466,230 @@ impl App {
     }
 
     fn handle_normal_key_event(&mut self, key: KeyEvent) {
-        match key.code {
-            KeyCode::Char('q') | KeyCode::Esc => self.should_quit = true,
-            KeyCode::Char('h') | KeyCode::F(1) => self.show_help = !self.show_help,
-            KeyCode::Char('1') => {
+        if self.box_selection.is_some_and(|selection| selection.keyboard) {
+            self.handle_box_key_event(key);
+            return;
+        }
+        if self.show_history {
+            self.handle_history_key_event(key);
+            return;
+        }
+        if self.bookmark_popup.is_some() {
+            self.handle_bookmark_key_event(key);
+            return;
+        }
+
+        let Some(action) = self.keymap.action_for(key) else { return };
+        match action {
+            Action::Quit => self.should_quit = true,
+            Action::ToggleHelp => self.show_help = !self.show_help,
+            Action::CommandLine => {
+                self.input_mode = InputMode::Command;
+                self.command_line.clear();
+                self.command_history_index = None;
+            },
+            Action::InteractiveMode => {
                 self.mode = AppMode::Interactive;
                 self.status_message = "Switched to Interactive mode".to_string();
             },
-            KeyCode::Char('2') => {
+            Action::AutoMode => {
                 self.mode = AppMode::AutoGenerate;
                 self.auto_generation_phase = 0;
                 self.auto_generation_timer = Instant::now();
                 self.status_message = "Switched to Auto-Generate mode - Exploring fractal automatically".to_string();
             },
-            KeyCode::Char('3') => {
+            Action::EquationEditor => {
                 self.mode = AppMode::EquationEditor;
                 self.input_mode = InputMode::Editing;
                 self.status_message = "Equation Editor - Type new equation, Enter to apply, Esc to cancel".to_string();
             },
-            KeyCode::Char('r') => {
+            Action::NextFractal => {
+                let current = self.current_fractal_name();
+                if let Some(fractal) = self.fractal_generator.registry.next_after(&current) {
+                    self.select_fractal(fractal);
+                }
+            },
+            Action::JuliaMode => self.toggle_julia_mode(),
+            Action::Undo => {
+                let state = self.history.undo().cloned();
+                match state {
+                    Some(state) => self.restore_view(state, "Undo"),
+                    None => self.status_message = "Nothing to undo".to_string(),
+                }
+            },
+            Action::Redo => {
+                let state = self.history.redo().cloned();
+                match state {
+                    Some(state) => self.restore_view(state, "Redo"),
+                    None => self.status_message = "Nothing to redo".to_string(),
+                }
+            },
+            Action::History => {
+                self.show_history = true;
+                self.history_selection = self.history.position();
+            },
+            Action::Bookmarks => self.bookmark_popup = Some(BookmarkPopup { selection: 0, edit: None }),
+            Action::BoxZoom => {
+                let (width, height) = self.image_size();
+                if width > 0 && height > 0 {
+                    self.box_selection = Some(BoxSelection { anchor: None, cursor: (width / 2, height / 2), keyboard: true });
+                    self.status_message = "Box Zoom: arrows move, Enter marks a corner, Esc cancels".to_string();
+                }
+            },
+            Action::SplitView => {
+                self.split_view = !self.split_view;
+                // Both panes change size, so let the next draw lay them out again
+                self.fractal_display_area = None;
+                self.julia_display_area = None;
+                self.julia_data.clear();
+                self.status_message = format!("Split View: {}",
+                    if self.split_view { "ON - Shift+Arrows move the crosshair" } else { "OFF" });
+            },
+            Action::CrosshairUp => self.move_crosshair(0, -1),
+            Action::CrosshairDown => self.move_crosshair(0, 1),
+            Action::CrosshairLeft => self.move_crosshair(-1, 0),
+            Action::CrosshairRight => self.move_crosshair(1, 0),
+            Action::Regenerate => {
                 self.regenerate_fractal();
                 self.status_message = "Fractal regenerated".to_string();
             },
-            KeyCode::Char('+') | KeyCode::Char('=') => self.zoom_in(),
-            KeyCode::Char('-') | KeyCode::Char('_') => self.zoom_out(),
-            KeyCode::Up => self.pan_up(),
-            KeyCode::Down => self.pan_down(),
-            KeyCode::Left => self.pan_left(),
-            KeyCode::Right => self.pan_right(),
-            KeyCode::Char('i') => self.increase_iterations(),
-            KeyCode::Char('d') => self.decrease_iterations(),
-            KeyCode::Char('c') => {
+            Action::ZoomIn => self.zoom_in(),
+            Action::ZoomOut => self.zoom_out(),
+            Action::PanUp => self.pan_up(),
+            Action::PanDown => self.pan_down(),
+            Action::PanLeft => self.pan_left(),
+            Action::PanRight => self.pan_right(),
+            Action::MoreIterations => self.increase_iterations(),
+            Action::FewerIterations => self.decrease_iterations(),
+            Action::ResetCenter => {
                 // Reset to center
-                self.center_x = -0.5;
-                self.center_y = 0.0;
-                self.zoom_factor = 1.0;
+                self.center_x = self.config.fractal.default_center_x.clone();
+                self.center_y = self.config.fractal.default_center_y.clone();
+                self.zoom_factor = self.config.fractal.default_zoom.clone();
                 self.status_message = "Reset to center view".to_string();
                 self.regenerate_fractal();
             },
-            KeyCode::Char(' ') => {
-                // Spacebar to regenerate (alternative to 'r')
+            Action::SmoothColouring => {
+                let smooth_coloring = !self.fractal_generator.smooth_coloring;
+                self.fractal_generator.set_smooth_coloring(smooth_coloring);
+                self.fractal_cache.clear(); // Clear cache since sample values changed
+                self.status_message = format!("Smooth Colouring: {}",
+                    if smooth_coloring { "ON" } else { "OFF" });
                 self.regenerate_fractal();
-                self.status_message = "Fractal regenerated".to_string();
             },
-            KeyCode::F(2) => {
+            Action::DeepZoom => {
+                let deep_zoom = !self.fractal_generator.deep_zoom;
+                self.fractal_generator.set_deep_zoom(deep_zoom);
+                self.fractal_cache.clear(); // Clear cache since the engine changed
+                self.status_message = format!("Deep Zoom (perturbation): {}",
+                    if deep_zoom { "ON" } else { "OFF" });
+                self.regenerate_fractal();
+            },
+            Action::FloatPrecision => {
+                let precision = self.fractal_generator.precision.next();
+                self.fractal_generator.set_precision(precision);
+                self.fractal_cache.clear(); // Clear cache since the kernels changed
+                self.status_message = format!("Float Precision: {} (widened automatically when zoomed in)", precision);
+                self.regenerate_fractal();
+            },
+            Action::DistanceEstimation => {
+                let distance_estimation = !self.fractal_generator.distance_estimation;
+                self.fractal_generator.set_distance_estimation(distance_estimation);
+                self.renderer.set_distance_mode(distance_estimation);
+                self.fractal_cache.clear(); // Clear cache since samples now carry distances
+                self.status_message = format!("Distance Estimation: {}",
+                    if distance_estimation { "ON" } else { "OFF" });
+                self.regenerate_fractal();
+            },
+            Action::InteriorDetection => {
+                let enabled = !self.fractal_generator.periodicity_check;
+                self.fractal_generator.set_cardioid_check(enabled);
+                self.fractal_generator.set_periodicity_check(enabled);
+                self.fractal_cache.clear(); // Clear cache so timings reflect the new setting
+                self.status_message = format!("Interior Detection (cardioid/bulb + periodicity): {}",
+                    if enabled { "ON" } else { "OFF" });
+                self.regenerate_fractal();
+            },
+            Action::PeriodColouring => {
+                let period_coloring = !self.renderer.period_coloring;
+                self.renderer.set_period_coloring(period_coloring);
+                self.status_message = format!("Period Colouring: {}",
+                    if period_coloring { "ON" } else { "OFF" });
+            },
+            Action::NextCellMode => {
+                let cell_mode = self.renderer.cell_mode.next();
+                self.renderer.set_cell_mode(cell_mode);
+                self.config.display.cell_mode = cell_mode;
+                self.regenerate_fractal();
+                self.status_message = match self.renderer.effective_cell_mode() {
+                    CellMode::Braille => "Cell Mode: braille (2x4 dots per cell)".to_string(),
+                    CellMode::HalfBlock => "Cell Mode: half-block (2 pixels per cell)".to_string(),
+                    _ if cell_mode != CellMode::Text => format!("Cell Mode: {} (needs Unicode, showing text)", cell_mode),
+                    _ => "Cell Mode: text".to_string(),
+                };
+            },
+            Action::NextPalette => {
+                let names = self.renderer.palette_names();
+                let current = names.iter().position(|name| name == self.renderer.palette_name()).unwrap_or(0);
+                let next = names[(current + 1) % names.len()].clone();
+                self.status_message = match self.set_palette(&next) {
+                    Ok(()) => format!("Palette: {}", next),
+                    Err(error) => error,
+                };
+            },
+            Action::OrbitTrap => {
+                let trap_config = &mut self.config.fractal.orbit_trap;
+                trap_config.enabled = !trap_config.enabled;
+                self.status_message = if trap_config.enabled {
+                    format!("Orbit Trap: ON - {}", trap_config.trap)
+                } else {
+                    "Orbit Trap: OFF".to_string()
+                };
+                self.apply_orbit_trap();
+            },
+            Action::TrapShape => {
+                let trap = &mut self.config.fractal.orbit_trap.trap;
+                trap.shape = trap.shape.next();
+                self.status_message = format!("Orbit Trap shape: {}", trap);
+                self.apply_orbit_trap();
+            },
+            Action::PlaceTrap => {
+                // Drop the trap at the middle of the current view
+                let trap = &mut self.config.fractal.orbit_trap.trap;
+                trap.center_x = self.center_x.to_f64();
+                trap.center_y = self.center_y.to_f64();
+                self.status_message = format!("Orbit Trap placed: {}", trap);
+                self.apply_orbit_trap();
+            },
+            Action::TrapSmaller | Action::TrapLarger => {
+                let steps = if action == Action::TrapLarger { 1 } else { -1 };
+                let trap = &mut self.config.fractal.orbit_trap.trap;
+                trap.shape = trap.shape.adjust(steps);
+                self.status_message = format!("Orbit Trap: {}", trap);
+                self.apply_orbit_trap();
+            },
+            Action::BurningShip => {
                 // Quick preset: Burning Ship
                 self.current_fractal_type = FractalType::BurningShip;
                 self.current_equation = "Burning Ship".to_string();
                 self.status_message = "Switched to Burning Ship fractal".to_string();
                 self.regenerate_fractal();
             },
-            KeyCode::F(3) => {
+            Action::JuliaSet => {
                 // Quick preset: Julia Set
                 self.current_fractal_type = FractalType::Julia { c: Complex::new(-0.7269, 0.1889) };
                 self.current_equation = "Julia: c = -0.7269 + 0.1889i".to_string();
                 self.status_message = "Switched to Julia Set fractal".to_string();
                 self.regenerate_fractal();
             },
-            KeyCode::F(4) => {
+            Action::Tricorn => {
                 // Quick preset: Tricorn
                 self.current_fractal_type = FractalType::Tricorn;
                 self.current_equation = "Tricorn".to_string();
                 self.status_message = "Switched to Tricorn fractal".to_string();
                 self.regenerate_fractal();
             },
-            KeyCode::F(5) => {
+            Action::PerformanceMode => {
                 // Toggle performance mode
                 self.fractal_generator.performance_mode = !self.fractal_generator.performance_mode;
                 let mode_str = if self.fractal_generator.performance_mode { "ON" } else { "OFF" };
@@ -227,7 +697,7 @@ impl App {
                 self.fractal_cache.clear(); // Clear cache when changing performance mode
                 self.regenerate_fractal();
             },
-            KeyCode::F(6) => {
+            Action::AdaptiveSampling => {
                 // Toggle adaptive sampling
                 self.fractal_generator.use_adaptive_sampling = !self.fractal_generator.use_adaptive_sampling;
                 let mode_str = if self.fractal_generator.use_adaptive_sampling { "ON" } else { "OFF" };
@@ -235,13 +705,13 @@ impl App {
                 self.fractal_cache.clear(); // Clear cache when changing sampling mode
                 self.regenerate_fractal();
             },
-            KeyCode::F(7) => {
+            Action::ClearCache => {
                 // Clear cache manually
                 let cache_size = self.fractal_cache.len();
                 self.fractal_cache.clear();
                 self.status_message = format!("Cleared {} cached fractals", cache_size);
             },
-            KeyCode::F(8) => {
+            Action::PerformanceStats => {
                 // Show performance statistics
                 let avg_time = if self.generation_count > 0 {
                     self.total_generation_time.as_millis() / self.generation_count as u128
@@ -253,7 +723,7 @@ impl App {
                     self.generation_count, avg_time, self.fps, self.fractal_cache.len()
                 );
             },
-            KeyCode::F(9) => {
+            Action::QualityMode => {
                 let quality_mode = !self.fractal_generator.quality_mode;
                 self.fractal_generator.set_quality_mode(quality_mode);
                 self.renderer.set_quality_mode(quality_mode);
@@ -263,7 +733,7 @@ impl App {
                     if quality_mode { "Higher detail, more iterations" } else { "Standard detail" });
                 self.regenerate_fractal();
             },
-            KeyCode::F(10) => {
+            Action::SuperSampling => {
                 let super_sampling = !self.fractal_generator.super_sampling;
                 self.fractal_generator.set_super_sampling(super_sampling);
                 self.renderer.set_super_sampling(super_sampling);
@@ -273,40 +743,176 @@ impl App {
                     if super_sampling { "2x resolution (slower)" } else { "1x resolution (faster)" });
                 self.regenerate_fractal();
             },
-            _ => {}
         }
     }
 
     fn handle_editing_key_event(&mut self, key: KeyEvent) {
         match key.code {
             KeyCode::Enter => {
-                if self.validate_and_apply_equation() {
-                    self.input_mode = InputMode::Normal;
-                    self.mode = AppMode::Interactive;
-                    self.status_message = "Custom equation applied successfully".to_string();
-                    self.regenerate_fractal();
-                } else {
-                    self.status_message = "Invalid equation format. Use supported patterns like 'z^2+c', 'z^3+c', etc.".to_string();
+                match self.validate_and_apply_equation() {
+                    Ok(()) => {
+                        self.equation_error = None;
+                        self.input_mode = InputMode::Normal;
+                        self.mode = AppMode::Interactive;
+                        self.status_message = "Custom equation applied successfully".to_string();
+                        self.regenerate_fractal();
+                    },
+                    Err(error) => {
+                        self.status_message = format!("Invalid equation: {}", error);
+                        self.equation_error = Some(error);
+                    }
                 }
             },
             KeyCode::Esc => {
+                self.equation_error = None;
                 self.input_mode = InputMode::Normal;
                 self.mode = AppMode::Interactive;
                 self.status_message = "Equation editing cancelled".to_string();
             },
             KeyCode::Backspace => {
                 self.current_equation.pop();
+                self.equation_error = None;
+            },
+            KeyCode::Char(c) if self.current_equation.len() < 50 => { // Limit equation length
+                self.current_equation.push(c);
+                self.equation_error = None;
+            },
+            _ => {}
+        }
+    }
+
+    // Keys at the `:` prompt: Tab completes, Up/Down recall earlier commands,
+    // Enter runs the line and Esc (or backspacing past the start) leaves
+    fn handle_command_key_event(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Enter => {
+                let line = std::mem::take(&mut self.command_line);
+                self.input_mode = InputMode::Normal;
+                self.command_history_index = None;
+                if line.trim().is_empty() {
+                    return;
+                }
+                if self.command_history.last() != Some(&line) {
+                    self.command_history.push(line.clone());
+                    if self.command_history.len() > COMMAND_HISTORY_LIMIT {
+                        self.command_history.remove(0);
+                    }
+                }
+                match command::parse(&line) {
+                    Ok(command) => self.execute_command(command),
+                    Err(error) => self.status_message = format!("Command failed: {}", error),
+                }
+            },
+            KeyCode::Esc => {
+                self.command_line.clear();
+                self.input_mode = InputMode::Normal;
             },
-            KeyCode::Char(c) => {
-                if self.current_equation.len() < 50 { // Limit equation length
-                    self.current_equation.push(c);
+            KeyCode::Backspace if self.command_line.is_empty() => self.input_mode = InputMode::Normal,
+            KeyCode::Backspace => {
+                self.command_line.pop();
+            },
+            KeyCode::Tab => {
+                let fractal_names: Vec<String> = self.fractal_generator.registry.names().iter().map(|name| name.to_string()).collect();
+                let (line, candidates) = command::complete(&self.command_line, &fractal_names, &self.renderer.palette_names());
+                self.command_line = line;
+                if !candidates.is_empty() {
+                    self.status_message = candidates.join("  ");
                 }
             },
+            KeyCode::Up => {
+                let index = match self.command_history_index {
+                    Some(index) => index.saturating_sub(1),
+                    None if self.command_history.is_empty() => return,
+                    None => self.command_history.len() - 1,
+                };
+                self.command_history_index = Some(index);
+                self.command_line = self.command_history[index].clone();
+            },
+            KeyCode::Down => {
+                let Some(index) = self.command_history_index else { return };
+                if index + 1 < self.command_history.len() {
+                    self.command_history_index = Some(index + 1);
+                    self.command_line = self.command_history[index + 1].clone();
+                } else {
+                    self.command_history_index = None;
+                    self.command_line.clear();
+                }
+            },
+            KeyCode::Char(c) if self.command_line.len() < 200 => self.command_line.push(c),
             _ => {}
         }
     }
 
+    pub fn execute_command(&mut self, command: Command) {
+        match command {
+            Command::Center { x, y } => {
+                self.center_x = x;
+                self.center_y = y;
+                self.regenerate_fractal();
+                self.status_message = format!("Centered on ({}, {})", self.center_x, self.center_y);
+            },
+            Command::Zoom(zoom) => {
+                self.zoom_factor = zoom;
+                self.regenerate_fractal();
+                self.status_message = format!("Zoom: {:.2}x", self.zoom_factor);
+            },
+            Command::Iterations(iterations) => {
+                self.max_iterations = iterations;
+                self.regenerate_fractal();
+                self.status_message = format!("Max Iterations: {}", self.max_iterations);
+            },
+            Command::Fractal { name, arguments } => {
+                match command::resolve_fractal(&self.fractal_generator, &name, &arguments) {
+                    Ok(fractal) => self.select_fractal(fractal),
+                    Err(error) => self.status_message = format!("Command failed: {}", error),
+                }
+            },
+            Command::Palette(palette) => {
+                self.status_message = match self.set_palette(&palette) {
+                    Ok(()) => format!("Palette: {}", self.renderer.palette_name()),
+                    Err(error) => format!("Command failed: {}", error),
+                };
+            },
+            Command::Export { path, size } => {
+                let samples = match size {
+                    Some(size) if self.density.is_none() => {
+                        // Text sizes are in characters, PNG sizes in pixels
+                        let (width, height) = match export::ExportFormat::from_path(&path) {
+                            export::ExportFormat::Png => size,
+                            _ => self.renderer.sample_size(size),
+                        };
+                        self.fractal_generator.generate_samples(&self.current_params(width, height))
+                    },
+                    _ => self.fractal_data.clone(),
+                };
+                self.status_message = match export::write_file(&self.renderer, &samples, &path) {
+                    Ok(format) => format!("Exported {} ({}, {}x{})", path.display(), format,
+                        samples.first().map_or(0, |row| row.len()), samples.len()),
+                    Err(error) => format!("Export to {} failed: {}", path.display(), error),
+                };
+            },
+            Command::Bookmark(name) => {
+                let bookmark = Bookmark {
+                    name: name.clone(),
+                    view: self.view_state(),
+                    palette: self.config.display.color_scheme.clone(),
+                };
+                match self.bookmarks.add(bookmark) {
+                    Ok(()) => {
+                        self.status_message = format!("Bookmarked '{}'", name.trim());
+                        self.save_bookmarks();
+                    },
+                    Err(error) => self.status_message = format!("Bookmark not saved: {}", error),
+                }
+            },
+            Command::Help => self.show_help = true,
+            Command::Quit => self.should_quit = true,
+        }
+    }
+
     fn update(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.refine_density();
+
         match self.mode {
             AppMode::AutoGenerate => {
                 if self.auto_generation_timer.elapsed() > Duration::from_millis(100) {
@@ -327,10 +933,132 @@ impl App {
 
     pub fn regenerate_fractal(&mut self) {
         self.regenerate_fractal_with_size(None);
+        self.regenerate_julia_pane();
+        // Auto mode drifts every frame; only views picked by hand are worth going back to
+        if self.mode != AppMode::AutoGenerate {
+            self.history.record(self.view_state());
+        }
+    }
+
+    fn view_state(&self) -> ViewState {
+        ViewState {
+            fractal_type: self.current_fractal_type.clone(),
+            equation: self.current_equation.clone(),
+            center_x: self.center_x.clone(),
+            center_y: self.center_y.clone(),
+            zoom_factor: self.zoom_factor.clone(),
+            max_iterations: self.max_iterations,
+        }
+    }
+
+    fn apply_view(&mut self, state: ViewState) {
+        self.current_fractal_type = state.fractal_type;
+        self.current_equation = state.equation;
+        self.center_x = state.center_x;
+        self.center_y = state.center_y;
+        self.zoom_factor = state.zoom_factor;
+        self.max_iterations = state.max_iterations;
+        self.regenerate_fractal();
+    }
+
+    // Shows a view from the history without recording it as a new step
+    fn restore_view(&mut self, state: ViewState, action: &str) {
+        self.apply_view(state);
+        let description = self.history.current().map(|state| self.describe_view(state)).unwrap_or_default();
+        self.status_message = format!("{}: {} ({}/{})", action, description,
+            self.history.position() + 1, self.history.len());
+    }
+
+    // One line per view, e.g. "Mandelbrot - 1.50x at (-0.500, 0.000), 256 iterations"
+    fn describe_view(&self, state: &ViewState) -> String {
+        let name = self.fractal_generator
+            .resolve(&state.fractal_type)
+            .map_or_else(|| state.equation.clone(), |fractal| fractal.name().to_string());
+        format!("{} - {:.2}x at ({:.3}, {:.3}), {} iterations",
+            name, state.zoom_factor, state.center_x, state.center_y, state.max_iterations)
+    }
+
+    // Keys while the history popup is open: arrows pick a view, Enter jumps to it
+    fn handle_history_key_event(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Up => self.history_selection = self.history_selection.saturating_sub(1),
+            KeyCode::Down => {
+                self.history_selection = (self.history_selection + 1).min(self.history.len().saturating_sub(1));
+            },
+            KeyCode::Enter => {
+                self.show_history = false;
+                if let Some(state) = self.history.jump(self.history_selection).cloned() {
+                    self.restore_view(state, "Jumped to");
+                }
+            },
+            KeyCode::Esc => self.show_history = false,
+            _ if matches!(self.keymap.action_for(key), Some(Action::History | Action::Quit)) => self.show_history = false,
+            _ => {},
+        }
+    }
+
+    // Renders the split view's Julia set for the crosshair point, or nothing if
+    // the current fractal has no Julia counterpart
+    fn regenerate_julia_pane(&mut self) {
+        if !self.split_view {
+            return;
+        }
+        let Some(julia) = self.fractal_generator
+            .resolve(&self.current_fractal_type)
+            .and_then(|fractal| fractal.julia(self.crosshair_point())) else {
+            self.julia_data.clear();
+            return;
+        };
+
+        let (width, height) = match self.julia_display_area {
+            Some(area) => ((area.width.saturating_sub(2) as usize).max(20), (area.height.saturating_sub(2) as usize).max(10)),
+            None => (80, 24),
+        };
+        let (width, height) = self.renderer.sample_size((width, height));
+        let viewport = julia.default_viewport();
+        let params = FractalParams {
+            fractal_type: julia.fractal_type(),
+            width,
+            height,
+            zoom: viewport.zoom,
+            center_x: viewport.center_x,
+            center_y: viewport.center_y,
+            max_iterations: self.max_iterations,
+            precise_center: None,
+        };
+        self.julia_data = self.fractal_generator.generate_samples(&params);
+    }
+
+    // The plane point under the crosshair, using the same pixel grid as the generator
+    fn crosshair_point(&self) -> Complex<f64> {
+        let (width, height) = self.image_size();
+        let (width, height) = (width.max(1), height.max(1));
+        let zoom = self.zoom_factor.to_f64();
+        let (dx, dy) = self.crosshair;
+        // Pixel (width / 2, height / 2) sits on the view center
+        Complex::new(
+            self.center_x.to_f64() + (dx as f64 - (width % 2) as f64 / 2.0) * 4.0 / zoom / width as f64,
+            self.center_y.to_f64() + (dy as f64 - (height % 2) as f64 / 2.0) * 4.0 / zoom / height as f64,
+        )
+    }
+
+    fn move_crosshair(&mut self, dx: i32, dy: i32) {
+        if !self.split_view {
+            return;
+        }
+        let (width, height) = self.image_size();
+        let (x, y) = self.crosshair;
+        self.crosshair = (
+            (x + dx).clamp(-width / 2, (width - 1) / 2),
+            (y + dy).clamp(-height / 2, (height - 1) / 2),
+        );
+        self.regenerate_julia_pane();
+        self.status_message = format!("Julia c = {}", format_complex(self.crosshair_point()));
     }
 
     fn regenerate_fractal_with_size(&mut self, size_override: Option<(usize, usize)>) {
         let start_time = Instant::now();
+        self.renderer.set_max_iterations(self.max_iterations);
 
         // Use the fractal display area if available, otherwise fall back to defaults
         let (width, height) = if let Some((w, h)) = size_override {
@@ -344,36 +1072,45 @@ impl App {
             // Default fallback
             (80, 24)
         };
+        let (width, height) = self.renderer.sample_size((width, height));
 
-        let params = FractalParams {
-            fractal_type: self.current_fractal_type.clone(),
-            width,
-            height,
-            zoom: self.zoom_factor,
-            center_x: self.center_x,
-            center_y: self.center_y,
-            max_iterations: self.max_iterations,
-        };
+        let params = self.current_params(width, height);
+
+        // Density renders are refined progressively instead of generated in one go
+        self.density = DensityAccumulator::new(&params);
+        if self.density.is_some() {
+            self.refine_density();
+            self.update_fps();
+            return;
+        }
 
         // Create cache key
         let cache_key = self.create_cache_key(&params);
+        let engine = if self.fractal_generator.uses_perturbation(&params) {
+            " [Perturbation]"
+        } else {
+            match self.fractal_generator.effective_precision(&params) {
+                Precision::DoubleDouble => " [Double-double]",
+                _ => "",
+            }
+        };
 
         // Check cache first
         if let Some(cached_data) = self.fractal_cache.get(&cache_key) {
             self.fractal_data = cached_data.clone();
             let generation_time = start_time.elapsed();
-            self.status_message = format!("Cached fractal - Zoom: {:.2}, Iterations: {}, Time: {:.1}ms",
-                self.zoom_factor, self.max_iterations, generation_time.as_millis());
+            self.status_message = format!("Cached fractal - Zoom: {:.2}, Iterations: {}, Time: {:.1}ms{}",
+                self.zoom_factor, self.max_iterations, generation_time.as_millis(), engine);
         } else {
             // Generate new fractal
-            self.fractal_data = self.fractal_generator.generate(&params);
+            self.fractal_data = self.fractal_generator.generate_samples(&params);
 
-            // Cache the result (limit cache size)
-            if self.fractal_cache.len() < 50 {
+            // Cache the result, starting over once the configured size is reached
+            if self.config.performance.enable_caching {
+                if self.fractal_cache.len() >= self.config.performance.max_cache_size {
+                    self.fractal_cache.clear();
+                }
                 self.fractal_cache.insert(cache_key, self.fractal_data.clone());
-            } else if self.fractal_cache.len() >= 100 {
-                // Clear old cache entries when it gets too large
-                self.fractal_cache.clear();
             }
 
             let generation_time = start_time.elapsed();
@@ -381,58 +1118,208 @@ impl App {
             self.generation_count += 1;
 
             let avg_time = self.total_generation_time.as_millis() / self.generation_count as u128;
-            self.status_message = format!("Generated fractal - Zoom: {:.2}, Iterations: {}, Time: {:.1}ms (Avg: {:.1}ms)",
-                self.zoom_factor, self.max_iterations, generation_time.as_millis(), avg_time);
+            self.status_message = format!("Generated fractal - Zoom: {:.2}, Iterations: {}, Time: {:.1}ms (Avg: {:.1}ms){}",
+                self.zoom_factor, self.max_iterations, generation_time.as_millis(), avg_time, engine);
         }
 
         // Update FPS counter
         self.update_fps();
     }
 
+    /// Switches the renderer to a palette and remembers it as the colour scheme
+    pub fn set_palette(&mut self, name: &str) -> Result<(), String> {
+        self.renderer.set_palette(name)?;
+        self.config.display.color_scheme = self.renderer.palette_name().to_string();
+        Ok(())
+    }
+
+    // Parameters for rendering the current view at the given size
+    fn current_params(&self, width: usize, height: usize) -> FractalParams {
+        FractalParams {
+            fractal_type: self.current_fractal_type.clone(),
+            width,
+            height,
+            zoom: self.zoom_factor.to_f64(),
+            center_x: self.center_x.to_f64(),
+            center_y: self.center_y.to_f64(),
+            max_iterations: self.max_iterations,
+            precise_center: Some((self.center_x.clone(), self.center_y.clone())),
+        }
+    }
+
+    // Traces another frame's worth of Buddhabrot orbits and refreshes the image
+    fn refine_density(&mut self) {
+        let Some(density) = self.density.as_mut() else { return };
+        if density.samples_per_pixel() >= DENSITY_MAX_SAMPLES_PER_PIXEL {
+            return;
+        }
+
+        let start_time = Instant::now();
+        while start_time.elapsed() < DENSITY_FRAME_BUDGET {
+            density.accumulate(DENSITY_BATCH);
+        }
+        self.fractal_data = density.to_samples();
+
+        let (samples, samples_per_pixel) = (density.samples(), density.samples_per_pixel());
+        self.status_message = format!("{} - Zoom: {:.2}, {} samples ({:.0} per pixel)",
+            self.current_fractal_name(), self.zoom_factor, samples, samples_per_pixel);
+    }
+
+    fn current_fractal_name(&self) -> String {
+        self.fractal_generator
+            .resolve(&self.current_fractal_type)
+            .map_or_else(|| "Unknown".to_string(), |fractal| fractal.name().to_string())
+    }
+
+    // Name and parameter values for the controls panel, e.g. "Julia Set (c_real -0.73, c_imag 0.19)"
+    fn current_fractal_description(&self) -> String {
+        let Some(fractal) = self.fractal_generator.resolve(&self.current_fractal_type) else {
+            return "Unknown".to_string();
+        };
+        let parameters: Vec<String> = fractal
+            .parameters()
+            .iter()
+            .map(|parameter| format!("{} {}", parameter.name, parameter.value))
+            .collect();
+        if parameters.is_empty() {
+            fractal.name().to_string()
+        } else {
+            format!("{} ({})", fractal.name(), parameters.join(", "))
+        }
+    }
+
+    /// Shows a fractal framed by its default view, from the next regeneration on
+    pub fn set_fractal(&mut self, fractal: &dyn Fractal) {
+        let viewport = fractal.default_viewport();
+        self.current_fractal_type = fractal.fractal_type();
+        self.current_equation = fractal.name().to_string();
+        self.center_x = BigFloat::from(viewport.center_x);
+        self.center_y = BigFloat::from(viewport.center_y);
+        self.zoom_factor = BigFloat::from(viewport.zoom);
+    }
+
+    // Switches to a registered fractal, framed by its default view
+    fn select_fractal(&mut self, fractal: Arc<dyn Fractal>) {
+        self.set_fractal(fractal.as_ref());
+        self.regenerate_fractal();
+        self.status_message = format!("Switched to {} fractal", fractal.name());
+    }
+
+    // Swaps between a fractal and its Julia set for the view center, and back to
+    // the exact parameter-plane view on the second press
+    fn toggle_julia_mode(&mut self) {
+        if let Some(view) = self.parameter_plane_view.take() {
+            if view.julia_type == self.current_fractal_type {
+                self.current_fractal_type = view.fractal_type;
+                self.current_equation = view.equation;
+                self.center_x = view.center_x;
+                self.center_y = view.center_y;
+                self.zoom_factor = view.zoom_factor;
+                self.regenerate_fractal();
+                self.status_message = format!("Back to {} fractal", self.current_fractal_name());
+                return;
+            }
+        }
+
+        // The split view's crosshair picks c; otherwise the view center does
+        let c = if self.split_view {
+            self.crosshair_point()
+        } else {
+            Complex::new(self.center_x.to_f64(), self.center_y.to_f64())
+        };
+        let Some(julia) = self.fractal_generator.resolve(&self.current_fractal_type).and_then(|fractal| fractal.julia(c)) else {
+            self.status_message = format!("{} has no Julia mode", self.current_fractal_name());
+            return;
+        };
+
+        let viewport = julia.default_viewport();
+        let julia_type = julia.fractal_type();
+        self.parameter_plane_view = Some(ParameterPlaneView {
+            fractal_type: std::mem::replace(&mut self.current_fractal_type, julia_type.clone()),
+            equation: std::mem::replace(
+                &mut self.current_equation,
+                format!("{}: c = {}", julia.name(), format_complex(c)),
+            ),
+            center_x: std::mem::replace(&mut self.center_x, BigFloat::from(viewport.center_x)),
+            center_y: std::mem::replace(&mut self.center_y, BigFloat::from(viewport.center_y)),
+            zoom_factor: std::mem::replace(&mut self.zoom_factor, BigFloat::from(viewport.zoom)),
+            julia_type,
+        });
+        self.regenerate_fractal();
+        self.status_message = format!("Switched to {} (j to go back)", julia.name());
+    }
+
+    // Pushes the trap stored in the config to the generator and redraws
+    fn apply_orbit_trap(&mut self) {
+        self.fractal_generator.set_orbit_trap(self.config.fractal.orbit_trap.active_trap());
+        self.fractal_cache.clear(); // Clear cache since sample values changed
+        let status_message = self.status_message.clone();
+        self.regenerate_fractal();
+        self.status_message = status_message;
+    }
+
     fn zoom_in(&mut self) {
-        self.zoom_factor *= 1.5;
+        self.zoom_factor = &self.zoom_factor * &BigFloat::from(self.zoom_step());
         self.status_message = format!("Zoomed in to {:.2}x", self.zoom_factor);
         self.regenerate_fractal();
     }
 
     fn zoom_out(&mut self) {
-        self.zoom_factor /= 1.5;
+        self.zoom_factor = &self.zoom_factor / &BigFloat::from(self.zoom_step());
         self.status_message = format!("Zoomed out to {:.2}x", self.zoom_factor);
         self.regenerate_fractal();
     }
 
+    // Zoom factor of one key press or scroll notch
+    fn zoom_step(&self) -> f64 {
+        self.config.controls.zoom_factor(self.config.fractal.zoom_step)
+    }
+
+    fn pan_step(&self) -> f64 {
+        self.config.controls.pan_distance(self.config.fractal.pan_step)
+    }
+
     fn pan_up(&mut self) {
-        self.center_y -= 0.1 / self.zoom_factor;
-        self.status_message = format!("Panned to ({:.3}, {:.3})", self.center_x, self.center_y);
-        self.regenerate_fractal();
+        self.pan_by(0.0, -self.pan_step());
     }
 
     fn pan_down(&mut self) {
-        self.center_y += 0.1 / self.zoom_factor;
-        self.status_message = format!("Panned to ({:.3}, {:.3})", self.center_x, self.center_y);
-        self.regenerate_fractal();
+        self.pan_by(0.0, self.pan_step());
     }
 
     fn pan_left(&mut self) {
-        self.center_x -= 0.1 / self.zoom_factor;
-        self.status_message = format!("Panned to ({:.3}, {:.3})", self.center_x, self.center_y);
-        self.regenerate_fractal();
+        self.pan_by(-self.pan_step(), 0.0);
     }
 
     fn pan_right(&mut self) {
-        self.center_x += 0.1 / self.zoom_factor;
+        self.pan_by(self.pan_step(), 0.0);
+    }
+
+    // Moves the center by a distance given in units of the zoom-1 view
+    fn pan_by(&mut self, dx: f64, dy: f64) {
+        let precision = self.coordinate_precision();
+        let zoom = self.zoom_factor.with_precision(precision);
+        self.center_x = &self.center_x.with_precision(precision) + &(&BigFloat::from(dx) / &zoom);
+        self.center_y = &self.center_y.with_precision(precision) + &(&BigFloat::from(dy) / &zoom);
         self.status_message = format!("Panned to ({:.3}, {:.3})", self.center_x, self.center_y);
         self.regenerate_fractal();
     }
 
+    // Bits needed for the center to resolve well below one pixel at the current zoom
+    fn coordinate_precision(&self) -> u32 {
+        let zoom_bits = self.zoom_factor.magnitude_exponent().unwrap_or(0).max(0) as u32;
+        BigFloat::DEFAULT_PRECISION.max(zoom_bits + 64)
+    }
+
     fn increase_iterations(&mut self) {
-        self.max_iterations = (self.max_iterations + 10).min(1000);
+        // Deep zooms need far more iterations than the overview
+        self.max_iterations = self.max_iterations.saturating_add(self.config.controls.iteration_step).min(command::MAX_ITERATIONS);
         self.status_message = format!("Increased iterations to {}", self.max_iterations);
         self.regenerate_fractal();
     }
 
     fn decrease_iterations(&mut self) {
-        self.max_iterations = (self.max_iterations.saturating_sub(10)).max(10);
+        self.max_iterations = (self.max_iterations.saturating_sub(self.config.controls.iteration_step)).max(10);
         self.status_message = format!("Decreased iterations to {}", self.max_iterations);
         self.regenerate_fractal();
     }
@@ -450,17 +1337,19 @@ impl App {
             // Phase 1: Zoom into interesting areas
             201..=400 => {
                 if self.auto_generation_phase == 201 {
-                    // Pick an interesting point
-                    let interesting_points = [
-                        (-0.7269, 0.1889),   // Spiral
-                        (-0.8, 0.156),       // Seahorse valley
-                        (-0.74529, 0.11307), // Lightning
-                        (-0.1, 0.651),       // Rabbit ears
-                        (-0.75, 0.0),        // Needle point
-                    ];
-                    let point = interesting_points[rng.gen_range(0..interesting_points.len())];
-                    self.auto_target_x = point.0;
-                    self.auto_target_y = point.1;
+                    // Head for a bookmark of the fractal on show, or one of the shipped
+                    // Mandelbrot locations if there is none
+                    let mut candidates: Vec<Bookmark> = self.bookmarks.bookmarks
+                        .iter()
+                        .filter(|bookmark| bookmark.view.fractal_type == self.current_fractal_type)
+                        .cloned()
+                        .collect();
+                    if candidates.is_empty() {
+                        candidates = Bookmarks::default().bookmarks;
+                    }
+                    let target = &candidates[rng.gen_range(0..candidates.len())].view;
+                    self.auto_target_x = target.center_x.to_f64();
+                    self.auto_target_y = target.center_y.to_f64();
                 }
                 self.auto_target_zoom = 5.0 + ((self.auto_generation_phase - 200) as f64 / 20.0);
             },
@@ -479,58 +1368,40 @@ impl App {
                 self.auto_target_zoom = 1.0;
                 self.max_iterations = 100;
 
-                // Cycle through different fractal types
-                self.current_fractal_type = match &self.current_fractal_type {
-                    FractalType::Mandelbrot => {
-                        self.current_equation = "Burning Ship".to_string();
-                        FractalType::BurningShip
-                    },
-                    FractalType::BurningShip => {
-                        self.current_equation = "Julia Set".to_string();
-                        FractalType::Julia { c: Complex::new(-0.7269, 0.1889) }
-                    },
-                    FractalType::Julia { .. } => {
-                        self.current_equation = "Tricorn".to_string();
-                        FractalType::Tricorn
-                    },
-                    FractalType::Tricorn => {
-                        self.current_equation = "Multibrot z^3".to_string();
-                        FractalType::Multibrot { power: 3.0 }
-                    },
-                    FractalType::Multibrot { .. } => {
-                        self.current_equation = "z^2 + c".to_string();
-                        FractalType::Mandelbrot
-                    },
-                    _ => {
-                        self.current_equation = "z^2 + c".to_string();
-                        FractalType::Mandelbrot
+                // Cycle through the registered fractals. Density renders are skipped
+                // since they restart their refinement on every step of the tour.
+                let registry = &self.fractal_generator.registry;
+                let mut name = self.current_fractal_name();
+                for _ in 0..registry.len() {
+                    let Some(fractal) = registry.next_after(&name) else { break };
+                    name = fractal.name().to_string();
+                    if !matches!(fractal.fractal_type(), FractalType::Buddhabrot | FractalType::Nebulabrot { .. }) {
+                        self.current_fractal_type = fractal.fractal_type();
+                        self.current_equation = name;
+                        break;
                     }
-                };
+                }
                 return;
             }
         }
 
         // Smooth interpolation towards targets
         let lerp_factor = 0.05;
-        self.center_x += (self.auto_target_x - self.center_x) * lerp_factor;
-        self.center_y += (self.auto_target_y - self.center_y) * lerp_factor;
-        self.zoom_factor += (self.auto_target_zoom - self.zoom_factor) * lerp_factor;
+        let lerp = |value: &BigFloat, target: f64| {
+            value + &(&(&BigFloat::from(target) - value) * &BigFloat::from(lerp_factor))
+        };
+        self.center_x = lerp(&self.center_x, self.auto_target_x);
+        self.center_y = lerp(&self.center_y, self.auto_target_y);
+        self.zoom_factor = lerp(&self.zoom_factor, self.auto_target_zoom);
 
         // Gradually increase iterations for better detail at high zoom
         if self.zoom_factor > 10.0 {
-            self.max_iterations = (100 + (self.zoom_factor * 2.0) as u32).min(500);
+            self.max_iterations = (100 + (self.zoom_factor.to_f64() * 2.0) as u32).min(500);
         }
 
         self.auto_generation_phase += 1;
 
-        let fractal_name = match &self.current_fractal_type {
-            FractalType::Mandelbrot => "Mandelbrot",
-            FractalType::BurningShip => "Burning Ship",
-            FractalType::Julia { .. } => "Julia Set",
-            FractalType::Tricorn => "Tricorn",
-            FractalType::Multibrot { .. } => "Multibrot",
-            _ => "Custom"
-        };
+        let fractal_name = self.current_fractal_name();
 
         self.status_message = format!(
             "Auto-exploring {} - Phase {} - Zoom: {:.1}x, Iterations: {}",
@@ -558,6 +1429,14 @@ impl App {
         if self.show_help {
             self.render_help_popup(f);
         }
+
+        if self.show_history {
+            self.render_history_popup(f);
+        }
+
+        if let Some(popup) = &self.bookmark_popup {
+            self.render_bookmark_popup(f, popup);
+        }
     }
 
     fn render_header(&self, f: &mut Frame, area: Rect) {
@@ -574,6 +1453,22 @@ impl App {
     }
 
     fn render_main_content(&mut self, f: &mut Frame, area: Rect) {
+        if self.split_view {
+            let main_chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([
+                    Constraint::Percentage(40), // Parameter plane
+                    Constraint::Percentage(40), // Julia set at the crosshair
+                    Constraint::Percentage(20), // Controls
+                ])
+                .split(area);
+
+            self.render_fractal_display(f, main_chunks[0]);
+            self.render_julia_display(f, main_chunks[1]);
+            self.render_controls(f, main_chunks[2]);
+            return;
+        }
+
         let main_chunks = Layout::default()
             .direction(Direction::Horizontal)
             .constraints([
@@ -586,6 +1481,8 @@ impl App {
         self.render_controls(f, main_chunks[1]);
     }
 
+    // `is_none_or` would need Rust 1.82
+    #[allow(clippy::unnecessary_map_or)]
     fn render_fractal_display(&mut self, f: &mut Frame, area: Rect) {
         // Store the display area for fractal generation
         let area_changed = self.fractal_display_area.map_or(true, |old_area| {
@@ -601,32 +1498,57 @@ impl App {
             self.regenerate_fractal();
         }
 
-        // Calculate the actual content area (inside the border)
-        let content_width = area.width.saturating_sub(2) as usize;
-        let content_height = area.height.saturating_sub(2) as usize;
+        draw_samples(&mut self.renderer, f, area, &self.fractal_data, "Fractal");
 
-        // Get the fractal dimensions
-        let fractal_height = self.fractal_data.len();
-        let fractal_width = if fractal_height > 0 { self.fractal_data[0].len() } else { 0 };
+        if self.split_view {
+            // Crosshair over the pixel whose point seeds the Julia pane
+            let (width, height) = self.image_size();
+            let pixel = (width / 2 + self.crosshair.0, height / 2 + self.crosshair.1);
+            if let Some((x, y)) = self.pixel_to_cell(pixel) {
+                let crosshair = Paragraph::new("+").style(Style::default().fg(Color::LightRed));
+                f.render_widget(crosshair, Rect { x, y, width: 1, height: 1 });
+            }
+        }
 
-        // Calculate how much of the fractal to display and centering
-        let display_width = content_width.min(fractal_width);
-        let display_height = content_height.min(fractal_height);
+        if let Some(selection) = self.box_selection {
+            let corners = selection.anchor.map(|anchor| (anchor, selection.cursor));
+            match corners.and_then(|(anchor, cursor)| Some((self.pixel_to_cell(anchor)?, self.pixel_to_cell(cursor)?))) {
+                Some(((x0, y0), (x1, y1))) => {
+                    let outline = Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::Yellow));
+                    let rect = Rect { x: x0.min(x1), y: y0.min(y1), width: x0.abs_diff(x1) + 1, height: y0.abs_diff(y1) + 1 };
+                    f.render_widget(outline, rect);
+                },
+                None => {
+                    // Keyboard selection before its first corner is marked
+                    if let Some((x, y)) = self.pixel_to_cell(selection.cursor) {
+                        let cursor = Paragraph::new("+").style(Style::default().fg(Color::Yellow));
+                        f.render_widget(cursor, Rect { x, y, width: 1, height: 1 });
+                    }
+                },
+            }
+        }
+    }
 
-        // Center the fractal if the display area is larger
-        let start_x = if fractal_width < content_width { 0 } else { (fractal_width - content_width) / 2 };
-        let start_y = if fractal_height < content_height { 0 } else { (fractal_height - content_height) / 2 };
+    // `is_none_or` would need Rust 1.82
+    #[allow(clippy::unnecessary_map_or)]
+    fn render_julia_display(&mut self, f: &mut Frame, area: Rect) {
+        let area_changed = self.julia_display_area.map_or(true, |old_area| {
+            old_area.width != area.width || old_area.height != area.height
+        });
+        if area_changed {
+            self.julia_display_area = Some(area);
+            self.regenerate_julia_pane();
+        }
 
-        let fractal_text = self.renderer.render_to_text_with_bounds(
-            &self.fractal_data,
-            start_x, start_y,
-            display_width, display_height,
-            content_width, content_height
-        );
+        if self.julia_data.is_empty() {
+            let message = Paragraph::new(format!("{} has no Julia mode", self.current_fractal_name()))
+                .block(Block::default().borders(Borders::ALL).title("Julia"));
+            f.render_widget(message, area);
+            return;
+        }
 
-        let fractal_widget = Paragraph::new(fractal_text)
-            .block(Block::default().borders(Borders::ALL).title("Fractal"));
-        f.render_widget(fractal_widget, area);
+        let title = format!("Julia c = {}", format_complex(self.crosshair_point()));
+        draw_samples(&mut self.renderer, f, area, &self.julia_data, &title);
     }
 
     fn render_controls(&self, f: &mut Frame, area: Rect) {
@@ -639,14 +1561,26 @@ impl App {
         let input_indicator = match self.input_mode {
             InputMode::Normal => "",
             InputMode::Editing => " [EDITING]",
+            InputMode::Command => " [COMMAND]",
         };
 
         let controls_text = if self.mode == AppMode::EquationEditor {
+            // Point a caret at the offending character when the last parse failed
+            let error_text = match &self.equation_error {
+                Some(error) => format!(
+                    "\n{}^\n{}",
+                    " ".repeat("Current: ".len() + error.position),
+                    error
+                ),
+                None => String::new(),
+            };
+
             format!(
-                "Mode: {}{}\n\nEquation Editor:\nCurrent: {}\n\nExamples:\n• z^2 + c (Mandelbrot)\n• z^3 + c (Multibrot)\n• burning ship\n• tricorn\n• julia(-0.7, 0.27)\n\nControls:\nType equation\nEnter: Apply\nEsc: Cancel\n\nParameters:\nZoom: {:.2}x\nCenter: ({:.3}, {:.3})\nIterations: {}",
+                "Mode: {}{}\n\nEquation Editor:\nCurrent: {}{}\n\nExamples:\n• z^2 + c (Mandelbrot)\n• z^3 + c (Multibrot)\n• burning ship\n• tricorn\n• julia(-0.7, 0.27)\n• sin(z) * c\n• z^2.5 + c\n• z^(1+i) + c\n• newton(z^3 - 1)\n• buddhabrot\n• nebulabrot\n\nControls:\nType equation\nEnter: Apply\nEsc: Cancel\n\nParameters:\nZoom: {:.2}x\nCenter: ({:.3}, {:.3})\nIterations: {}",
                 mode_str,
                 input_indicator,
                 self.current_equation,
+                error_text,
                 self.zoom_factor,
                 self.center_x,
                 self.center_y,
@@ -654,16 +1588,28 @@ impl App {
             )
         } else {
             format!(
-                "Mode: {}{}\n\nParameters:\nZoom: {:.2}x\nCenter: ({:.3}, {:.3})\nIterations: {}\n\nEquation: {}\n\nQuality:\nQuality Mode: {}\nSuper Sampling: {}\n\nControls:\n+/= : Zoom In\n-   : Zoom Out\n↑↓←→: Pan\ni   : More Iterations\nd   : Fewer Iterations\nr/Space: Regenerate\nc   : Reset Center\n1   : Interactive Mode\n2   : Auto Mode\n3   : Edit Equation\nF9  : Quality Mode\nF10 : Super Sampling\nh/F1: Toggle Help\nq/Esc: Quit",
+                "Mode: {}{}\n\nParameters:\nZoom: {:.2}x\nCenter: ({:.3}, {:.3})\nIterations: {}\n\nFractal: {}\nEquation: {}\n\nQuality:\nQuality Mode: {}\nSuper Sampling: {}\nSmooth Colouring: {}\nPrecision: {}\nDistance Estimation: {}\nInterior Detection: {}\nOrbit Trap: {}\nPalette: {}\nCells: {}\n\nControls:\n{}",
                 mode_str,
                 input_indicator,
                 self.zoom_factor,
                 self.center_x,
                 self.center_y,
                 self.max_iterations,
+                self.current_fractal_description(),
                 self.current_equation,
                 if self.fractal_generator.quality_mode { "ON" } else { "OFF" },
-                if self.fractal_generator.super_sampling { "ON" } else { "OFF" }
+                if self.fractal_generator.super_sampling { "ON" } else { "OFF" },
+                if self.fractal_generator.smooth_coloring { "ON" } else { "OFF" },
+                self.fractal_generator.precision,
+                if self.fractal_generator.distance_estimation { "ON" } else { "OFF" },
+                if self.fractal_generator.periodicity_check { "ON" } else { "OFF" },
+                match self.fractal_generator.orbit_trap {
+                    Some(trap) => trap.to_string(),
+                    None => "OFF".to_string(),
+                },
+                self.renderer.palette_name(),
+                self.renderer.cell_mode,
+                self.keymap.controls_text()
             )
         };
 
@@ -673,7 +1619,9 @@ impl App {
     }
 
     fn render_footer(&self, f: &mut Frame, area: Rect) {
-        let footer_text = if self.fps > 0.0 {
+        let footer_text = if self.input_mode == InputMode::Command {
+            format!(":{}_", self.command_line)
+        } else if self.fps > 0.0 {
             format!("{} | FPS: {:.1} | Cache: {} entries",
                 self.status_message, self.fps, self.fractal_cache.len())
         } else {
@@ -697,38 +1645,31 @@ impl App {
 
         f.render_widget(Clear, popup_area);
 
-        let help_text = "FRACTAL GENERATOR HELP\n\n\
-            Modes:\n\
-            1 - Interactive Mode\n\
-            2 - Auto Generation Mode\n\
-            3 - Equation Editor\n\n\
-            Navigation:\n\
-            Arrow Keys - Pan around\n\
-            +/= - Zoom in\n\
-            - - Zoom out\n\
-            c - Reset to center\n\n\
-            Parameters:\n\
-            i - Increase iterations\n\
-            d - Decrease iterations\n\
-            r/Space - Regenerate fractal\n\n\
-            Quick Presets:\n\
-            F2 - Burning Ship\n\
-            F3 - Julia Set\n\
-            F4 - Tricorn\n\n\
-            Performance & Quality:\n\
-            F5 - Toggle Performance Mode\n\
-            F6 - Toggle Adaptive Sampling\n\
-            F7 - Clear Cache\n\
-            F8 - Show Performance Stats\n\
-            F9 - Toggle Quality Mode\n\
-            F10 - Toggle Super Sampling\n\n\
+        // Key sections come from the keymap so remapped keys are shown as bound
+        let help_text = format!("FRACTAL GENERATOR HELP\n\n\
+            {}\n\
+            Mouse:\n\
+            Click to center, drag a box to zoom,\n\
+            scroll to zoom at cursor, right-drag to pan\n\n\
+            Registered fractals:\n\
+            {}\n\n\
+            Command Line ({}):\n\
+            center <x> <y>, zoom <factor>, iter <n>,\n\
+            fractal <name> [params], palette <name>,\n\
+            export <file.png|.txt|.ans> [WxH],\n\
+            bookmark <name>, help, quit\n\
+            Tab completes, Up/Down recall history\n\n\
             Equation Editor:\n\
             Examples: z^3+c, burning ship,\n\
-            tricorn, julia(-0.7, 0.27)\n\n\
-            General:\n\
-            h/F1 - Toggle this help\n\
-            q/Esc - Quit application\n\n\
-            Press 'h' to close this help.";
+            tricorn, julia(-0.7, 0.27),\n\
+            sin(z)*c, exp(z) + c, conj(z)^2 + c,\n\
+            newton(z^3 - 1), buddhabrot,\n\
+            nebulabrot(5000, 500, 50)\n\n\
+            Press {} to close this help.",
+            self.keymap.help_text(),
+            self.fractal_generator.registry.names().join(", "),
+            self.keymap.keys_label(Action::CommandLine),
+            self.keymap.keys_label(Action::ToggleHelp));
 
         let help_widget = Paragraph::new(help_text)
             .style(Style::default().fg(Color::White))
@@ -736,39 +1677,227 @@ impl App {
         f.render_widget(help_widget, popup_area);
     }
 
-    fn validate_and_apply_equation(&mut self) -> bool {
-        let equation = self.current_equation.trim().to_lowercase();
+    // Keys while the bookmarks popup is open: arrows pick, Enter jumps, a/r/d add,
+    // rename and delete; while a name is being typed, Enter saves it and Esc drops it
+    fn handle_bookmark_key_event(&mut self, key: KeyEvent) {
+        let Some(mut popup) = self.bookmark_popup.take() else { return };
+
+        if let Some(edit) = popup.edit.as_mut() {
+            let name = match edit {
+                BookmarkEdit::Add { name } | BookmarkEdit::Rename { name, .. } => name,
+            };
+            match key.code {
+                KeyCode::Char(c) if name.len() < 40 => name.push(c),
+                KeyCode::Backspace => {
+                    name.pop();
+                },
+                KeyCode::Esc => popup.edit = None,
+                KeyCode::Enter => {
+                    let result = match edit {
+                        BookmarkEdit::Add { name } => {
+                            let bookmark = Bookmark {
+                                name: name.clone(),
+                                view: self.view_state(),
+                                palette: self.config.display.color_scheme.clone(),
+                            };
+                            self.bookmarks.add(bookmark).map(|()| format!("Bookmarked '{}'", name.trim()))
+                        },
+                        BookmarkEdit::Rename { index, name } => {
+                            self.bookmarks.rename(*index, name).map(|()| format!("Renamed bookmark to '{}'", name.trim()))
+                        },
+                    };
+                    match result {
+                        Ok(message) => {
+                            popup.edit = None;
+                            self.status_message = message;
+                            self.save_bookmarks();
+                        },
+                        Err(error) => self.status_message = format!("Bookmark not saved: {}", error),
+                    }
+                },
+                _ => {},
+            }
+            self.bookmark_popup = Some(popup);
+            return;
+        }
+
+        match key.code {
+            KeyCode::Up => popup.selection = popup.selection.saturating_sub(1),
+            KeyCode::Down => popup.selection = (popup.selection + 1).min(self.bookmarks.len().saturating_sub(1)),
+            KeyCode::Enter => {
+                if let Some(bookmark) = self.bookmarks.bookmarks.get(popup.selection).cloned() {
+                    self.apply_view(bookmark.view);
+                    self.status_message = match self.set_palette(&bookmark.palette) {
+                        Ok(()) => format!("Jumped to bookmark '{}'", bookmark.name),
+                        Err(error) => format!("Jumped to bookmark '{}' ({})", bookmark.name, error),
+                    };
+                    return;
+                }
+            },
+            KeyCode::Char('a') => popup.edit = Some(BookmarkEdit::Add { name: String::new() }),
+            KeyCode::Char('r') => {
+                if let Some(bookmark) = self.bookmarks.bookmarks.get(popup.selection) {
+                    popup.edit = Some(BookmarkEdit::Rename { index: popup.selection, name: bookmark.name.clone() });
+                }
+            },
+            KeyCode::Char('d') | KeyCode::Delete => {
+                if let Some(bookmark) = self.bookmarks.remove(popup.selection) {
+                    popup.selection = popup.selection.min(self.bookmarks.len().saturating_sub(1));
+                    self.status_message = format!("Deleted bookmark '{}'", bookmark.name);
+                    self.save_bookmarks();
+                }
+            },
+            KeyCode::Esc => return,
+            _ if matches!(self.keymap.action_for(key), Some(Action::Bookmarks | Action::Quit)) => return,
+            _ => {},
+        }
+        self.bookmark_popup = Some(popup);
+    }
 
-        // Parse and validate common fractal equation patterns
-        if equation.is_empty() {
-            return false;
+    fn save_bookmarks(&mut self) {
+        if let Some(error) = &self.bookmarks_error {
+            self.status_message = format!("Not saved - fix or remove the file first: {}", error);
+            return;
         }
+        if let Err(error) = self.bookmarks.save_to_file(&self.bookmarks_path) {
+            self.status_message = format!("Could not save {}: {}", self.bookmarks_path.display(), error);
+        }
+    }
+
+    fn render_bookmark_popup(&self, f: &mut Frame, popup: &BookmarkPopup) {
+        let area = f.size();
+        let popup_area = Rect {
+            x: area.width / 6,
+            y: area.height / 4,
+            width: area.width * 2 / 3,
+            height: area.height / 2,
+        };
+
+        f.render_widget(Clear, popup_area);
+
+        // Room for the name prompt under the list while one is being typed
+        let prompt = popup.edit.as_ref().map(|edit| match edit {
+            BookmarkEdit::Add { name } => format!("New bookmark name: {}_", name),
+            BookmarkEdit::Rename { name, .. } => format!("Rename to: {}_", name),
+        });
+        let visible = (popup_area.height.saturating_sub(2) as usize).saturating_sub(if prompt.is_some() { 2 } else { 0 });
+        let scroll = popup.selection.saturating_sub(visible.saturating_sub(1));
+        let mut lines: Vec<Line> = self.bookmarks.bookmarks
+            .iter()
+            .enumerate()
+            .skip(scroll)
+            .take(visible)
+            .map(|(index, bookmark)| {
+                let text = format!("{:<20} {} [{}]", bookmark.name, self.describe_view(&bookmark.view), bookmark.palette);
+                if index == popup.selection {
+                    Line::styled(text, Style::default().fg(Color::Black).bg(Color::Cyan))
+                } else {
+                    Line::from(text)
+                }
+            })
+            .collect();
+        if self.bookmarks.is_empty() {
+            lines.push(Line::from("No bookmarks - press a to save the current view"));
+        }
+        if let Some(prompt) = prompt {
+            lines.push(Line::from(""));
+            lines.push(Line::styled(prompt, Style::default().fg(Color::Yellow)));
+        }
+
+        let title = match &self.bookmarks_error {
+            Some(_) => format!("Bookmarks - NOT SAVED, {} is unreadable - Esc close", self.bookmarks_path.display()),
+            None => "Bookmarks - ↑↓ select, Enter jump, a add, r rename, d delete, Esc close".to_string(),
+        };
+        let bookmark_widget = Paragraph::new(lines)
+            .style(Style::default().fg(Color::White))
+            .block(Block::default().borders(Borders::ALL).title(title));
+        f.render_widget(bookmark_widget, popup_area);
+    }
+
+    fn render_history_popup(&self, f: &mut Frame) {
+        let area = f.size();
+        let popup_area = Rect {
+            x: area.width / 6,
+            y: area.height / 4,
+            width: area.width * 2 / 3,
+            height: area.height / 2,
+        };
+
+        f.render_widget(Clear, popup_area);
+
+        // Newest first, scrolled so the highlighted entry stays visible
+        let visible = popup_area.height.saturating_sub(2) as usize;
+        let newest_first: Vec<usize> = (0..self.history.len()).rev().collect();
+        let selected_row = newest_first.iter().position(|&index| index == self.history_selection).unwrap_or(0);
+        let scroll = selected_row.saturating_sub(visible.saturating_sub(1));
+        let lines: Vec<Line> = newest_first
+            .iter()
+            .skip(scroll)
+            .take(visible)
+            .map(|&index| {
+                let marker = if index == self.history.position() { "*" } else { " " };
+                let description = self.history.entries().nth(index).map(|state| self.describe_view(state)).unwrap_or_default();
+                let text = format!("{} {:>3}. {}", marker, index + 1, description);
+                if index == self.history_selection {
+                    Line::styled(text, Style::default().fg(Color::Black).bg(Color::Cyan))
+                } else {
+                    Line::from(text)
+                }
+            })
+            .collect();
+
+        let history_widget = Paragraph::new(lines)
+            .style(Style::default().fg(Color::White))
+            .block(Block::default().borders(Borders::ALL).title("History - ↑↓ select, Enter jump, Esc close"));
+        f.render_widget(history_widget, popup_area);
+    }
+
+    fn validate_and_apply_equation(&mut self) -> Result<(), ParseError> {
+        let equation = self.current_equation.trim().to_lowercase();
 
         // Try to parse the equation and set the appropriate fractal type
         if equation == "z^2+c" || equation == "z^2 + c" || equation == "mandelbrot" {
             self.current_fractal_type = FractalType::Mandelbrot;
             self.current_equation = "z^2 + c".to_string();
-            return true;
+            return Ok(());
         }
 
         if equation == "burning ship" || equation == "burningship" {
             self.current_fractal_type = FractalType::BurningShip;
             self.current_equation = "Burning Ship".to_string();
-            return true;
+            return Ok(());
         }
 
         if equation == "tricorn" {
             self.current_fractal_type = FractalType::Tricorn;
             self.current_equation = "Tricorn".to_string();
-            return true;
+            return Ok(());
+        }
+
+        if equation == "buddhabrot" {
+            self.current_fractal_type = FractalType::Buddhabrot;
+            self.current_equation = "Buddhabrot".to_string();
+            return Ok(());
+        }
+
+        // Nebulabrot with the classic limits, or "nebulabrot(red, green, blue)"
+        let nebulabrot_limits = if equation == "nebulabrot" {
+            Some(DEFAULT_NEBULABROT_LIMITS)
+        } else {
+            self.parse_nebulabrot_equation(&equation)
+        };
+        if let Some(limits) = nebulabrot_limits {
+            self.current_fractal_type = FractalType::Nebulabrot { limits };
+            self.current_equation = format!("nebulabrot({}, {}, {})", limits[0], limits[1], limits[2]);
+            return Ok(());
         }
 
         // Parse z^n + c patterns (simple parsing without regex for now)
         if let Some(power) = self.parse_power_equation(&equation) {
-            if power >= 2.0 && power <= 10.0 {
+            if (2.0..=10.0).contains(&power) {
                 self.current_fractal_type = FractalType::Multibrot { power };
                 self.current_equation = format!("z^{} + c", power);
-                return true;
+                return Ok(());
             }
         }
 
@@ -776,10 +1905,46 @@ impl App {
         if let Some((real, imag)) = self.parse_julia_equation(&equation) {
             self.current_fractal_type = FractalType::Julia { c: Complex::new(real, imag) };
             self.current_equation = format!("Julia: c = {} + {}i", real, imag);
-            return true;
+            return Ok(());
         }
 
-        false
+        // Any registered fractal by name, including ones added by other crates
+        if let Some(fractal) = self.fractal_generator.registry.get(&equation) {
+            self.current_fractal_type = fractal.fractal_type();
+            self.current_equation = fractal.name().to_string();
+            return Ok(());
+        }
+
+        let leading_whitespace = self.current_equation.chars().take_while(|ch| ch.is_whitespace()).count();
+
+        // Newton's method on a polynomial, e.g. "newton(z^3 - 1)"
+        if let Some(inner) = equation.strip_prefix("newton(").and_then(|rest| rest.strip_suffix(')')) {
+            return match Polynomial::parse(inner) {
+                Ok(polynomial) => {
+                    self.current_equation = format!("newton({})", polynomial);
+                    self.current_fractal_type = FractalType::Newton { polynomial };
+                    Ok(())
+                },
+                Err(mut error) => {
+                    error.position += leading_whitespace + "newton(".len();
+                    Err(error)
+                }
+            };
+        }
+
+        // Anything else is treated as an iteration formula z -> f(z, c)
+        match expression::parse(&equation) {
+            Ok(_) => {
+                self.current_fractal_type = FractalType::Custom { equation: equation.clone() };
+                self.current_equation = equation;
+                Ok(())
+            },
+            Err(mut error) => {
+                // Report positions relative to what the user typed, not the trimmed text
+                error.position += leading_whitespace;
+                Err(error)
+            }
+        }
     }
 
     fn parse_power_equation(&self, equation: &str) -> Option<f64> {
@@ -816,15 +1981,26 @@ impl App {
         None
     }
 
+    fn parse_nebulabrot_equation(&self, equation: &str) -> Option<[u32; 3]> {
+        // Iteration limits for the red, green and blue channels, e.g. "nebulabrot(5000, 500, 50)"
+        let inner = equation.strip_prefix("nebulabrot(")?.strip_suffix(')')?;
+        let limits: Vec<u32> = inner
+            .split(',')
+            .map(|part| part.trim().parse::<u32>().ok().filter(|&limit| limit > 0))
+            .collect::<Option<_>>()?;
+        limits.try_into().ok()
+    }
+
     fn create_cache_key(&self, params: &FractalParams) -> String {
+        // Full precision: at deep zoom neighbouring views differ only in the last digits
         format!(
-            "{:?}_{}_{}_{:.6}_{:.6}_{:.3}_{}",
+            "{:?}_{}_{}_{}_{}_{}_{}",
             params.fractal_type,
             params.width,
             params.height,
-            params.center_x,
-            params.center_y,
-            params.zoom,
+            self.center_x,
+            self.center_y,
+            self.zoom_factor,
             params.max_iterations
         )
     }
@@ -855,3 +2031,51 @@ impl App {
         }
     }
 }
+
+// Draws a sample grid centred in a bordered pane, cropped to fit
+fn draw_samples(renderer: &mut TerminalRenderer, f: &mut Frame, area: Rect, data: &[Vec<FractalSample>], title: &str) {
+    // Calculate the actual content area (inside the border)
+    let content_width = area.width.saturating_sub(2) as usize;
+    let content_height = area.height.saturating_sub(2) as usize;
+
+    // Get the fractal dimensions in cells
+    let (fractal_width, fractal_height) = renderer.cell_size(data);
+
+    // Calculate how much of the fractal to display and centering
+    let display_width = content_width.min(fractal_width);
+    let display_height = content_height.min(fractal_height);
+
+    // Center the fractal if the display area is larger
+    let start_x = if fractal_width < content_width { 0 } else { (fractal_width - content_width) / 2 };
+    let start_y = if fractal_height < content_height { 0 } else { (fractal_height - content_height) / 2 };
+
+    let fractal_text = renderer.render_samples_to_text_with_bounds(
+        data,
+        start_x, start_y,
+        display_width, display_height,
+        content_width, content_height
+    );
+
+    let fractal_widget = Paragraph::new(fractal_text)
+        .block(Block::default().borders(Borders::ALL).title(title.to_string()));
+    f.render_widget(fractal_widget, area);
+}
+
+// The screen cell `draw_samples` puts the top-left sample of a grid this size on;
+// off-pane when the grid is cropped
+fn sample_origin(area: Rect, data_width: usize, data_height: usize) -> (i32, i32) {
+    let content_width = area.width.saturating_sub(2) as usize;
+    let content_height = area.height.saturating_sub(2) as usize;
+    let offset = |data: usize, content: usize| {
+        if data < content { ((content - data) / 2) as i32 } else { -(((data - content) / 2) as i32) }
+    };
+    (
+        area.x as i32 + 1 + offset(data_width, content_width),
+        area.y as i32 + 1 + offset(data_height, content_height),
+    )
+}
+
+// "a + bi" with four decimals, as shown in equations and pane titles
+fn format_complex(c: Complex<f64>) -> String {
+    format!("{:.4} {} {:.4}i", c.re, if c.im < 0.0 { '-' } else { '+' }, c.im.abs())
+}
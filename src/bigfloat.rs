@@ -0,0 +1,784 @@
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cmp::Ordering;
+use std::f64::consts::{LOG10_2, LOG2_10};
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+use std::str::FromStr;
+
+// Arbitrary-precision binary floating point number: value = ±mantissa * 2^exponent.
+// The mantissa is a little-endian vector of 32-bit limbs and is truncated to
+// `precision` significant bits after every operation. Values are read and
+// written as decimal strings so viewport coordinates survive a round trip
+// through config and bookmark files without being squeezed through an f64.
+#[derive(Debug, Clone)]
+pub struct BigFloat {
+    negative: bool,
+    mantissa: Vec<u32>,
+    exponent: i64,
+    precision: u32,
+}
+
+impl BigFloat {
+    pub const DEFAULT_PRECISION: u32 = 128;
+
+    /// Largest power of ten a parsed number may be scaled by, either way. Far
+    /// past anything the deep zoom engine can use, and small enough that
+    /// parsing never stalls on a typo like `1e5000000000`
+    pub const MAX_DECIMAL_EXPONENT: i64 = 4096;
+
+    pub fn zero(precision: u32) -> Self {
+        Self {
+            negative: false,
+            mantissa: Vec::new(),
+            exponent: 0,
+            precision: precision.max(64),
+        }
+    }
+
+    pub fn from_f64(value: f64, precision: u32) -> Self {
+        if value == 0.0 || !value.is_finite() {
+            return Self::zero(precision);
+        }
+
+        let bits = value.to_bits();
+        let exponent_bits = ((bits >> 52) & 0x7ff) as i64;
+        let fraction = bits & ((1u64 << 52) - 1);
+        let (mantissa, exponent) = if exponent_bits == 0 {
+            (fraction, -1074) // subnormal
+        } else {
+            (fraction | (1u64 << 52), exponent_bits - 1075)
+        };
+
+        Self::from_parts(
+            value < 0.0,
+            vec![mantissa as u32, (mantissa >> 32) as u32],
+            exponent,
+            precision,
+        )
+    }
+
+    fn from_parts(negative: bool, mut mantissa: Vec<u32>, mut exponent: i64, precision: u32) -> Self {
+        let precision = precision.max(64);
+        trim(&mut mantissa);
+        if mantissa.is_empty() {
+            return Self::zero(precision);
+        }
+
+        // Drop whole zero limbs at the bottom to keep the mantissa short
+        let low_zeros = mantissa.iter().take_while(|&&limb| limb == 0).count();
+        if low_zeros > 0 {
+            mantissa.drain(..low_zeros);
+            exponent += 32 * low_zeros as i64;
+        }
+
+        let length = bit_length(&mantissa);
+        if length > precision as u64 {
+            let shift = length - precision as u64;
+            mantissa = shift_right(&mantissa, shift);
+            exponent += shift as i64;
+            trim(&mut mantissa);
+        }
+
+        Self { negative, mantissa, exponent, precision }
+    }
+
+    pub fn precision(&self) -> u32 {
+        self.precision
+    }
+
+    pub fn with_precision(&self, precision: u32) -> Self {
+        Self::from_parts(self.negative, self.mantissa.clone(), self.exponent, precision)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.mantissa.is_empty()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative && !self.is_zero()
+    }
+
+    pub fn abs(&self) -> Self {
+        Self { negative: false, ..self.clone() }
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        if self.is_zero() {
+            return 0.0;
+        }
+
+        // The top 64 bits are more than enough to round to a 53-bit double
+        let length = bit_length(&self.mantissa);
+        let (top, exponent) = if length > 64 {
+            let shifted = shift_right(&self.mantissa, length - 64);
+            (limbs_to_u64(&shifted), self.exponent + (length - 64) as i64)
+        } else {
+            (limbs_to_u64(&self.mantissa), self.exponent)
+        };
+
+        let magnitude = scale_by_power_of_two(top as f64, exponent);
+        if self.negative { -magnitude } else { magnitude }
+    }
+
+    /// Position of the highest set bit relative to the binary point (log2 of the magnitude, rounded up)
+    pub fn magnitude_exponent(&self) -> Option<i64> {
+        if self.is_zero() {
+            None
+        } else {
+            Some(self.exponent + bit_length(&self.mantissa) as i64)
+        }
+    }
+
+    pub fn mul_pow2(&self, power: i64) -> Self {
+        if self.is_zero() {
+            return self.clone();
+        }
+        Self { exponent: self.exponent + power, ..self.clone() }
+    }
+
+    pub fn mul_u32(&self, factor: u32) -> Self {
+        Self::from_parts(self.negative, mul_small(&self.mantissa, factor), self.exponent, self.precision)
+    }
+
+    pub fn div_u32(&self, divisor: u32) -> Self {
+        assert!(divisor != 0, "division by zero");
+        if self.is_zero() {
+            return self.clone();
+        }
+
+        // Widen first so the quotient keeps the full working precision
+        let guard = self.precision as u64 + 64;
+        let widened = shift_left(&self.mantissa, guard);
+        let (quotient, _) = div_small(&widened, divisor);
+        Self::from_parts(self.negative, quotient, self.exponent - guard as i64, self.precision)
+    }
+
+    pub fn square(&self) -> Self {
+        self * self
+    }
+
+    fn add_signed(&self, other: &BigFloat, negate_other: bool) -> Self {
+        let precision = self.precision.max(other.precision);
+        let other_negative = other.negative ^ negate_other;
+
+        if other.is_zero() {
+            return self.with_precision(precision);
+        }
+        if self.is_zero() {
+            return Self::from_parts(other_negative, other.mantissa.clone(), other.exponent, precision);
+        }
+
+        // When the operands are too far apart the smaller one cannot affect the result
+        let top_self = self.exponent + bit_length(&self.mantissa) as i64;
+        let top_other = other.exponent + bit_length(&other.mantissa) as i64;
+        if top_self - top_other > precision as i64 + 2 {
+            return self.with_precision(precision);
+        }
+        if top_other - top_self > precision as i64 + 2 {
+            return Self::from_parts(other_negative, other.mantissa.clone(), other.exponent, precision);
+        }
+
+        let exponent = self.exponent.min(other.exponent);
+        let a = shift_left(&self.mantissa, (self.exponent - exponent) as u64);
+        let b = shift_left(&other.mantissa, (other.exponent - exponent) as u64);
+
+        let (negative, mantissa) = if self.negative == other_negative {
+            (self.negative, add_magnitudes(&a, &b))
+        } else {
+            match compare_magnitudes(&a, &b) {
+                Ordering::Less => (other_negative, sub_magnitudes(&b, &a)),
+                _ => (self.negative, sub_magnitudes(&a, &b)),
+            }
+        };
+
+        Self::from_parts(negative, mantissa, exponent, precision)
+    }
+
+    fn multiply(&self, other: &BigFloat) -> Self {
+        let precision = self.precision.max(other.precision);
+        if self.is_zero() || other.is_zero() {
+            return Self::zero(precision);
+        }
+        Self::from_parts(
+            self.negative != other.negative,
+            mul_magnitudes(&self.mantissa, &other.mantissa),
+            self.exponent + other.exponent,
+            precision,
+        )
+    }
+
+    fn divide(&self, other: &BigFloat) -> Self {
+        assert!(!other.is_zero(), "division by zero");
+        let precision = self.precision.max(other.precision);
+        if self.is_zero() {
+            return Self::zero(precision);
+        }
+
+        // Widen the numerator so the integer quotient carries the full precision;
+        // exact quotients such as 3 / 1.5 come out exact
+        let numerator_bits = bit_length(&self.mantissa) as i64;
+        let divisor_bits = bit_length(&other.mantissa) as i64;
+        let shift = (precision as i64 + 2 + divisor_bits - numerator_bits).max(0) as u64;
+        let numerator = shift_left(&self.mantissa, shift);
+        Self::from_parts(
+            self.negative != other.negative,
+            div_magnitudes(&numerator, &other.mantissa),
+            self.exponent - shift as i64 - other.exponent,
+            precision,
+        )
+    }
+
+    /// Parses a decimal string, keeping at least `min_precision` bits and
+    /// enough extra bits to hold every digit given
+    pub fn parse_with_precision(text: &str, min_precision: u32) -> Result<Self, ParseBigFloatError> {
+        let error = || ParseBigFloatError { text: text.to_string() };
+        let trimmed = text.trim();
+        let (negative, unsigned) = match trimmed.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, trimmed.strip_prefix('+').unwrap_or(trimmed)),
+        };
+        let (number, exponent) = match unsigned.find(['e', 'E']) {
+            Some(index) => (&unsigned[..index], unsigned[index + 1..].parse::<i64>().map_err(|_| error())?),
+            None => (unsigned, 0),
+        };
+        let (integer_part, fraction_part) = number.split_once('.').unwrap_or((number, ""));
+        if integer_part.is_empty() && fraction_part.is_empty() {
+            return Err(error());
+        }
+        if !integer_part.bytes().chain(fraction_part.bytes()).all(|byte| byte.is_ascii_digit()) {
+            return Err(error());
+        }
+
+        let digits = format!("{}{}", integer_part, fraction_part);
+        let digits = digits.trim_start_matches('0');
+        let digit_bits = (digits.len() as f64 * LOG2_10).ceil() as u32;
+        let precision = min_precision.max(digit_bits + 64);
+
+        let mut mantissa = Vec::new();
+        for chunk in digits.as_bytes().chunks(9) {
+            let chunk_value = chunk.iter().fold(0u32, |acc, &digit| acc * 10 + (digit - b'0') as u32);
+            mantissa = add_magnitudes(&mul_small(&mantissa, 10u32.pow(chunk.len() as u32)), &[chunk_value]);
+        }
+
+        let decimal_exponent = exponent
+            .checked_sub(fraction_part.len() as i64)
+            .filter(|exponent| exponent.abs() <= Self::MAX_DECIMAL_EXPONENT)
+            .ok_or_else(error)?;
+        let working = precision + 64 + (decimal_exponent.unsigned_abs() as f64 * LOG2_10) as u32;
+        Ok(Self::from_parts(negative, mantissa, 0, working)
+            .mul_pow10(decimal_exponent)
+            .with_precision(precision))
+    }
+
+    fn mul_pow10(&self, power: i64) -> Self {
+        let mut value = self.clone();
+        let mut remaining = power.unsigned_abs();
+        while remaining > 0 {
+            let step = remaining.min(9) as u32;
+            let factor = 10u32.pow(step);
+            value = if power > 0 { value.mul_u32(factor) } else { value.div_u32(factor) };
+            remaining -= step as u64;
+        }
+        value
+    }
+
+    // round(|self| * 10^power) as a string of decimal digits
+    fn scaled_digits(&self, power: i64) -> String {
+        let working = self.precision + 64 + (power.unsigned_abs() as f64 * LOG2_10) as u32;
+        let scaled = self.abs().with_precision(working).mul_pow10(power);
+
+        let integer = if scaled.exponent >= 0 {
+            shift_left(&scaled.mantissa, scaled.exponent as u64)
+        } else {
+            let bits = scaled.exponent.unsigned_abs();
+            let truncated = shift_right(&scaled.mantissa, bits);
+            let round_limb = ((bits - 1) / 32) as usize;
+            let round_up = scaled.mantissa.get(round_limb).is_some_and(|limb| limb >> ((bits - 1) % 32) & 1 == 1);
+            if round_up { add_magnitudes(&truncated, &[1]) } else { truncated }
+        };
+        limbs_to_decimal(&integer)
+    }
+
+    // Significant digits with the decimal exponent of the first one
+    fn significant_digits(&self, count: usize) -> (String, i64) {
+        let top = self.magnitude_exponent().unwrap_or(0);
+        let mut exponent = ((top - 1) as f64 * LOG10_2).floor() as i64;
+        loop {
+            let digits = self.scaled_digits(count as i64 - 1 - exponent);
+            if digits.len() <= count {
+                return (digits, exponent);
+            }
+            // The estimate was one too low or rounding carried into a new digit
+            exponent += (digits.len() - count) as i64;
+        }
+    }
+
+    fn default_digits(&self) -> usize {
+        ((self.precision as f64 * LOG10_2) as usize).saturating_sub(1).max(17)
+    }
+
+    fn to_fixed(&self, places: usize) -> String {
+        let digits = self.scaled_digits(places as i64);
+        let digits = format!("{:0>width$}", digits, width = places + 1);
+        let (integer, fraction) = digits.split_at(digits.len() - places);
+        let sign = if self.is_negative() { "-" } else { "" };
+        if places == 0 {
+            format!("{}{}", sign, integer)
+        } else {
+            format!("{}{}.{}", sign, integer, fraction)
+        }
+    }
+
+    fn to_scientific(&self, places: Option<usize>) -> String {
+        if self.is_zero() {
+            return match places {
+                Some(places) if places > 0 => format!("0.{}e0", "0".repeat(places)),
+                _ => "0e0".to_string(),
+            };
+        }
+        let (digits, exponent) = self.significant_digits(places.map_or(self.default_digits(), |places| places + 1));
+        let digits = match places {
+            Some(places) => format!("{:0<width$}", digits, width = places + 1),
+            None => digits.trim_end_matches('0').to_string(),
+        };
+        let sign = if self.is_negative() { "-" } else { "" };
+        let (first, rest) = digits.split_at(1);
+        if rest.is_empty() {
+            format!("{}{}e{}", sign, first, exponent)
+        } else {
+            format!("{}{}.{}e{}", sign, first, rest, exponent)
+        }
+    }
+
+    fn to_decimal(&self) -> String {
+        if self.is_zero() {
+            return "0".to_string();
+        }
+        let (digits, exponent) = self.significant_digits(self.default_digits());
+        let digits = digits.trim_end_matches('0');
+        if !(-7..21).contains(&exponent) {
+            return self.to_scientific(None);
+        }
+
+        let sign = if self.is_negative() { "-" } else { "" };
+        if exponent < 0 {
+            return format!("{}0.{}{}", sign, "0".repeat((-exponent - 1) as usize), digits);
+        }
+        let integer_length = exponent as usize + 1;
+        if digits.len() <= integer_length {
+            format!("{}{:0<width$}", sign, digits, width = integer_length)
+        } else {
+            let (integer, fraction) = digits.split_at(integer_length);
+            format!("{}{}.{}", sign, integer, fraction)
+        }
+    }
+
+    pub fn cmp_value(&self, other: &BigFloat) -> Ordering {
+        let difference = self - other;
+        if difference.is_zero() {
+            Ordering::Equal
+        } else if difference.negative {
+            Ordering::Less
+        } else {
+            Ordering::Greater
+        }
+    }
+}
+
+impl Default for BigFloat {
+    fn default() -> Self {
+        Self::zero(Self::DEFAULT_PRECISION)
+    }
+}
+
+impl From<f64> for BigFloat {
+    /// Converts via the shortest decimal that round-trips, so `0.1` becomes one tenth
+    /// rather than the binary value nearest to it
+    fn from(value: f64) -> Self {
+        if !value.is_finite() {
+            return Self::default();
+        }
+        Self::parse_with_precision(&format!("{:e}", value), Self::DEFAULT_PRECISION)
+            .unwrap_or_else(|_| Self::from_f64(value, Self::DEFAULT_PRECISION))
+    }
+}
+
+impl FromStr for BigFloat {
+    type Err = ParseBigFloatError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Self::parse_with_precision(text, Self::DEFAULT_PRECISION)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseBigFloatError {
+    pub text: String,
+}
+
+impl fmt::Display for ParseBigFloatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid number '{}'", self.text)
+    }
+}
+
+impl std::error::Error for ParseBigFloatError {}
+
+impl fmt::Display for BigFloat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match f.precision() {
+            Some(places) => f.write_str(&self.to_fixed(places)),
+            None => f.write_str(&self.to_decimal()),
+        }
+    }
+}
+
+impl fmt::LowerExp for BigFloat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_scientific(f.precision()))
+    }
+}
+
+impl Serialize for BigFloat {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for BigFloat {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct BigFloatVisitor;
+
+        impl Visitor<'_> for BigFloatVisitor {
+            type Value = BigFloat;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a number or a decimal string")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<BigFloat, E> {
+                value.parse().map_err(E::custom)
+            }
+
+            fn visit_f64<E: de::Error>(self, value: f64) -> Result<BigFloat, E> {
+                Ok(BigFloat::from(value))
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<BigFloat, E> {
+                value.to_string().parse().map_err(E::custom)
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<BigFloat, E> {
+                value.to_string().parse().map_err(E::custom)
+            }
+        }
+
+        // Strings keep every digit; plain JSON numbers are accepted for hand-written files
+        deserializer.deserialize_any(BigFloatVisitor)
+    }
+}
+
+impl PartialEq<f64> for BigFloat {
+    fn eq(&self, other: &f64) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+impl PartialOrd<f64> for BigFloat {
+    fn partial_cmp(&self, other: &f64) -> Option<Ordering> {
+        if other.is_nan() {
+            return None;
+        }
+        if other.is_infinite() {
+            return Some(if *other > 0.0 { Ordering::Less } else { Ordering::Greater });
+        }
+        let other = Self::parse_with_precision(&format!("{:e}", other), self.precision).ok()?;
+        Some(self.cmp_value(&other))
+    }
+}
+
+impl PartialEq for BigFloat {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp_value(other) == Ordering::Equal
+    }
+}
+
+impl PartialOrd for BigFloat {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp_value(other))
+    }
+}
+
+impl Add for &BigFloat {
+    type Output = BigFloat;
+    fn add(self, other: &BigFloat) -> BigFloat {
+        self.add_signed(other, false)
+    }
+}
+
+impl Sub for &BigFloat {
+    type Output = BigFloat;
+    fn sub(self, other: &BigFloat) -> BigFloat {
+        self.add_signed(other, true)
+    }
+}
+
+impl Mul for &BigFloat {
+    type Output = BigFloat;
+    fn mul(self, other: &BigFloat) -> BigFloat {
+        self.multiply(other)
+    }
+}
+
+impl Div for &BigFloat {
+    type Output = BigFloat;
+    fn div(self, other: &BigFloat) -> BigFloat {
+        self.divide(other)
+    }
+}
+
+impl Neg for &BigFloat {
+    type Output = BigFloat;
+    fn neg(self) -> BigFloat {
+        BigFloat { negative: !self.negative, ..self.clone() }
+    }
+}
+
+impl Add for BigFloat {
+    type Output = BigFloat;
+    fn add(self, other: BigFloat) -> BigFloat {
+        &self + &other
+    }
+}
+
+impl Sub for BigFloat {
+    type Output = BigFloat;
+    fn sub(self, other: BigFloat) -> BigFloat {
+        &self - &other
+    }
+}
+
+impl Mul for BigFloat {
+    type Output = BigFloat;
+    fn mul(self, other: BigFloat) -> BigFloat {
+        &self * &other
+    }
+}
+
+impl Div for BigFloat {
+    type Output = BigFloat;
+    fn div(self, other: BigFloat) -> BigFloat {
+        &self / &other
+    }
+}
+
+impl Neg for BigFloat {
+    type Output = BigFloat;
+    fn neg(self) -> BigFloat {
+        -&self
+    }
+}
+
+fn scale_by_power_of_two(mut value: f64, mut exponent: i64) -> f64 {
+    // powi overflows for large exponents, so apply the scale in steps
+    while exponent > 1000 {
+        value *= 2f64.powi(1000);
+        exponent -= 1000;
+        if value.is_infinite() {
+            return value;
+        }
+    }
+    while exponent < -1000 {
+        value *= 2f64.powi(-1000);
+        exponent += 1000;
+        if value == 0.0 {
+            return value;
+        }
+    }
+    value * 2f64.powi(exponent as i32)
+}
+
+fn limbs_to_u64(limbs: &[u32]) -> u64 {
+    limbs.iter().rev().fold(0u64, |acc, &limb| (acc << 32) | limb as u64)
+}
+
+fn trim(limbs: &mut Vec<u32>) {
+    while limbs.last() == Some(&0) {
+        limbs.pop();
+    }
+}
+
+fn bit_length(limbs: &[u32]) -> u64 {
+    match limbs.last() {
+        Some(&top) => (limbs.len() as u64 - 1) * 32 + (32 - top.leading_zeros()) as u64,
+        None => 0,
+    }
+}
+
+fn shift_left(limbs: &[u32], bits: u64) -> Vec<u32> {
+    let limb_shift = (bits / 32) as usize;
+    let bit_shift = (bits % 32) as u32;
+    let mut result = vec![0u32; limb_shift];
+    result.reserve(limbs.len() + 1);
+
+    if bit_shift == 0 {
+        result.extend_from_slice(limbs);
+    } else {
+        let mut carry = 0u32;
+        for &limb in limbs {
+            result.push((limb << bit_shift) | carry);
+            carry = limb >> (32 - bit_shift);
+        }
+        result.push(carry);
+    }
+    trim(&mut result);
+    result
+}
+
+fn shift_right(limbs: &[u32], bits: u64) -> Vec<u32> {
+    let limb_shift = (bits / 32) as usize;
+    if limb_shift >= limbs.len() {
+        return Vec::new();
+    }
+    let bit_shift = (bits % 32) as u32;
+    let source = &limbs[limb_shift..];
+
+    let mut result: Vec<u32> = if bit_shift == 0 {
+        source.to_vec()
+    } else {
+        source
+            .iter()
+            .enumerate()
+            .map(|(i, &limb)| {
+                let high = source.get(i + 1).map_or(0, |&next| next << (32 - bit_shift));
+                (limb >> bit_shift) | high
+            })
+            .collect()
+    };
+    trim(&mut result);
+    result
+}
+
+fn compare_magnitudes(a: &[u32], b: &[u32]) -> Ordering {
+    if a.len() != b.len() {
+        return a.len().cmp(&b.len());
+    }
+    for (x, y) in a.iter().rev().zip(b.iter().rev()) {
+        match x.cmp(y) {
+            Ordering::Equal => continue,
+            other => return other,
+        }
+    }
+    Ordering::Equal
+}
+
+fn add_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let (long, short) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    let mut result = Vec::with_capacity(long.len() + 1);
+    let mut carry = 0u64;
+    for (i, &limb) in long.iter().enumerate() {
+        let sum = limb as u64 + short.get(i).copied().unwrap_or(0) as u64 + carry;
+        result.push(sum as u32);
+        carry = sum >> 32;
+    }
+    if carry > 0 {
+        result.push(carry as u32);
+    }
+    result
+}
+
+// Requires a >= b
+fn sub_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(a.len());
+    let mut borrow = 0i64;
+    for (i, &limb) in a.iter().enumerate() {
+        let mut difference = limb as i64 - b.get(i).copied().unwrap_or(0) as i64 - borrow;
+        if difference < 0 {
+            difference += 1 << 32;
+            borrow = 1;
+        } else {
+            borrow = 0;
+        }
+        result.push(difference as u32);
+    }
+    trim(&mut result);
+    result
+}
+
+fn mul_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = vec![0u32; a.len() + b.len()];
+    for (i, &x) in a.iter().enumerate() {
+        let mut carry = 0u64;
+        for (j, &y) in b.iter().enumerate() {
+            let product = x as u64 * y as u64 + result[i + j] as u64 + carry;
+            result[i + j] = product as u32;
+            carry = product >> 32;
+        }
+        result[i + b.len()] = carry as u32;
+    }
+    trim(&mut result);
+    result
+}
+
+// Binary long division; only used for viewport arithmetic, never per pixel
+fn div_magnitudes(numerator: &[u32], divisor: &[u32]) -> Vec<u32> {
+    let mut quotient = vec![0u32; numerator.len()];
+    let mut remainder: Vec<u32> = Vec::new();
+    for bit in (0..bit_length(numerator)).rev() {
+        remainder = shift_left(&remainder, 1);
+        if numerator[(bit / 32) as usize] >> (bit % 32) & 1 == 1 {
+            remainder = add_magnitudes(&remainder, &[1]);
+        }
+        if compare_magnitudes(&remainder, divisor) != Ordering::Less {
+            remainder = sub_magnitudes(&remainder, divisor);
+            quotient[(bit / 32) as usize] |= 1 << (bit % 32);
+        }
+    }
+    trim(&mut quotient);
+    quotient
+}
+
+fn mul_small(limbs: &[u32], factor: u32) -> Vec<u32> {
+    let mut result = Vec::with_capacity(limbs.len() + 1);
+    let mut carry = 0u64;
+    for &limb in limbs {
+        let product = limb as u64 * factor as u64 + carry;
+        result.push(product as u32);
+        carry = product >> 32;
+    }
+    if carry > 0 {
+        result.push(carry as u32);
+    }
+    result
+}
+
+fn limbs_to_decimal(limbs: &[u32]) -> String {
+    if limbs.is_empty() {
+        return "0".to_string();
+    }
+    let mut chunks = Vec::new();
+    let mut remaining = limbs.to_vec();
+    while !remaining.is_empty() {
+        let (quotient, remainder) = div_small(&remaining, 1_000_000_000);
+        chunks.push(remainder);
+        remaining = quotient;
+    }
+    let mut text = chunks.pop().map_or_else(String::new, |top| top.to_string());
+    for chunk in chunks.iter().rev() {
+        text.push_str(&format!("{:09}", chunk));
+    }
+    text
+}
+
+fn div_small(limbs: &[u32], divisor: u32) -> (Vec<u32>, u32) {
+    let mut result = vec![0u32; limbs.len()];
+    let mut remainder = 0u64;
+    for (i, &limb) in limbs.iter().enumerate().rev() {
+        let current = (remainder << 32) | limb as u64;
+        result[i] = (current / divisor as u64) as u32;
+        remainder = current % divisor as u64;
+    }
+    trim(&mut result);
+    (result, remainder as u32)
+}
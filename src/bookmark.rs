@@ -0,0 +1,132 @@
+use crate::bigfloat::BigFloat;
+use crate::fractal::FractalType;
+use crate::history::ViewState;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Named views saved to a JSON file beside the config, so a location found in
+// one session can be returned to in the next.
+
+pub const BOOKMARKS_FILE_NAME: &str = "bookmarks.json";
+
+/// The bookmarks file for a config file: same directory, fixed name
+pub fn bookmarks_path_for<P: AsRef<Path>>(config_path: P) -> PathBuf {
+    config_path.as_ref().with_file_name(BOOKMARKS_FILE_NAME)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub name: String,
+    #[serde(flatten)]
+    pub view: ViewState,
+    /// Colour scheme the view was saved with
+    pub palette: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bookmarks {
+    pub bookmarks: Vec<Bookmark>,
+}
+
+impl Default for Bookmarks {
+    /// The shipped set of well-known Mandelbrot locations
+    fn default() -> Self {
+        let location = |name: &str, x: f64, y: f64| Bookmark {
+            name: name.to_string(),
+            view: ViewState {
+                fractal_type: FractalType::Mandelbrot,
+                equation: "z^2 + c".to_string(),
+                center_x: BigFloat::from(x),
+                center_y: BigFloat::from(y),
+                zoom_factor: BigFloat::from(5.0),
+                max_iterations: 256,
+            },
+            palette: "default".to_string(),
+        };
+
+        Self {
+            bookmarks: vec![
+                location("Spiral", -0.7269, 0.1889),
+                location("Seahorse Valley", -0.8, 0.156),
+                location("Lightning", -0.74529, 0.11307),
+                location("Rabbit Ears", -0.1, 0.651),
+                location("Needle Point", -0.75, 0.0),
+            ],
+        }
+    }
+}
+
+impl Bookmarks {
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn std::error::Error>> {
+        let content = fs::read_to_string(path)?;
+        let bookmarks: Bookmarks = serde_json::from_str(&content)?;
+        Ok(bookmarks)
+    }
+
+    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn std::error::Error>> {
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// The saved bookmarks, or the shipped set if there is no file yet. A file
+    /// that exists but can't be read is an error, so it is never replaced
+    /// with the defaults by the next save
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Self::load_from_file(path).map_err(|error| format!("could not read {}: {}", path.display(), error))
+    }
+
+    /// Looks a bookmark up by name, ignoring case
+    pub fn get(&self, name: &str) -> Option<&Bookmark> {
+        self.position(name).map(|index| &self.bookmarks[index])
+    }
+
+    /// Saves a view under `name`, replacing any bookmark already called that
+    pub fn add(&mut self, mut bookmark: Bookmark) -> Result<(), String> {
+        validate_name(&bookmark.name)?;
+        bookmark.name = bookmark.name.trim().to_string();
+        match self.position(&bookmark.name) {
+            Some(index) => self.bookmarks[index] = bookmark,
+            None => self.bookmarks.push(bookmark),
+        }
+        Ok(())
+    }
+
+    pub fn rename(&mut self, index: usize, name: &str) -> Result<(), String> {
+        validate_name(name)?;
+        if self.position(name).is_some_and(|existing| existing != index) {
+            return Err(format!("a bookmark named '{}' already exists", name.trim()));
+        }
+        let bookmark = self.bookmarks.get_mut(index).ok_or("no such bookmark")?;
+        bookmark.name = name.trim().to_string();
+        Ok(())
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<Bookmark> {
+        (index < self.bookmarks.len()).then(|| self.bookmarks.remove(index))
+    }
+
+    pub fn len(&self) -> usize {
+        self.bookmarks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bookmarks.is_empty()
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.bookmarks.iter().position(|bookmark| bookmark.name.eq_ignore_ascii_case(name.trim()))
+    }
+}
+
+fn validate_name(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("bookmark name must not be empty".to_string());
+    }
+    Ok(())
+}
@@ -0,0 +1,198 @@
+use crate::builtin::cardioid_or_bulb_period;
+use crate::fractal::{FractalParams, FractalSample, FractalType};
+use rand::Rng;
+use rayon::prelude::*;
+
+// Buddhabrot: instead of colouring each pixel by its own escape time, random c
+// values are iterated and every point visited by an orbit that escapes is
+// counted in the pixel it lands on. The image is the resulting density.
+
+// Random c values are drawn from this square, which contains the whole set
+const SAMPLE_RADIUS: f64 = 2.0;
+// Orbits are traced until |z|^2 passes this
+const BAILOUT_SQR: f64 = 4.0;
+// Samples per pixel for a one-shot (non-progressive) render
+const SAMPLES_PER_PIXEL: usize = 64;
+// Samples handed to each parallel task so the per-task histograms pay for themselves
+const CHUNK_SIZE: usize = 4096;
+
+/// Classic Nebulabrot limits for the red, green and blue channels
+pub const DEFAULT_NEBULABROT_LIMITS: [u32; 3] = [5000, 500, 50];
+
+pub struct DensityAccumulator {
+    width: usize,
+    height: usize,
+    x_min: f64,
+    y_min: f64,
+    x_scale: f64,
+    y_scale: f64,
+    /// Iteration limit per channel; an orbit counts towards every channel whose limit it escapes within
+    limits: Vec<u32>,
+    /// One width * height histogram per channel
+    histograms: Vec<Vec<u32>>,
+    samples: u64,
+}
+
+impl DensityAccumulator {
+    /// An empty accumulator for the viewport, or `None` if the fractal type is not a density render
+    pub fn new(params: &FractalParams) -> Option<Self> {
+        let limits = match &params.fractal_type {
+            FractalType::Buddhabrot => vec![params.max_iterations],
+            FractalType::Nebulabrot { limits } => limits.to_vec(),
+            _ => return None,
+        };
+
+        let half_extent = 2.0 / params.zoom;
+        let pixels = params.width * params.height;
+        Some(Self {
+            width: params.width,
+            height: params.height,
+            x_min: params.center_x - half_extent,
+            y_min: params.center_y - half_extent,
+            x_scale: 2.0 * half_extent / params.width as f64,
+            y_scale: 2.0 * half_extent / params.height as f64,
+            histograms: vec![vec![0; pixels]; limits.len()],
+            limits,
+            samples: 0,
+        })
+    }
+
+    /// Number of random c values traced so far
+    pub fn samples(&self) -> u64 {
+        self.samples
+    }
+
+    pub fn samples_per_pixel(&self) -> f64 {
+        self.samples as f64 / (self.width * self.height).max(1) as f64
+    }
+
+    /// Traces another `samples` random orbits into the histograms
+    pub fn accumulate(&mut self, samples: usize) {
+        let pixels = self.width * self.height;
+        let channels = self.limits.len();
+        let chunks: Vec<usize> = (0..samples.div_ceil(CHUNK_SIZE))
+            .map(|chunk| CHUNK_SIZE.min(samples - chunk * CHUNK_SIZE))
+            .collect();
+
+        let this = &*self;
+        let counts = chunks
+            .into_par_iter()
+            .fold(
+                || vec![vec![0u32; pixels]; channels],
+                |mut histograms, count| {
+                    this.trace_samples(count, &mut histograms);
+                    histograms
+                },
+            )
+            .reduce(
+                || vec![vec![0u32; pixels]; channels],
+                |mut total, part| {
+                    for (total, part) in total.iter_mut().zip(&part) {
+                        for (a, b) in total.iter_mut().zip(part) {
+                            *a = a.saturating_add(*b);
+                        }
+                    }
+                    total
+                },
+            );
+
+        for (histogram, counts) in self.histograms.iter_mut().zip(&counts) {
+            for (a, b) in histogram.iter_mut().zip(counts) {
+                *a = a.saturating_add(*b);
+            }
+        }
+        self.samples += samples as u64;
+    }
+
+    fn trace_samples(&self, count: usize, histograms: &mut [Vec<u32>]) {
+        let mut rng = rand::thread_rng();
+        let max_limit = self.limits.iter().copied().max().unwrap_or(0);
+        let mut orbit = Vec::with_capacity(max_limit as usize);
+
+        for _ in 0..count {
+            let cx = rng.gen_range(-SAMPLE_RADIUS..SAMPLE_RADIUS);
+            let cy = rng.gen_range(-SAMPLE_RADIUS..SAMPLE_RADIUS);
+            // These never escape, and would otherwise cost the full iteration limit
+            if cardioid_or_bulb_period(cx, cy).is_some() {
+                continue;
+            }
+
+            orbit.clear();
+            let (mut x, mut y) = (0.0f64, 0.0f64);
+            let mut escaped_at = None;
+            for iteration in 0..max_limit {
+                let next_x = x * x - y * y + cx;
+                y = 2.0 * x * y + cy;
+                x = next_x;
+                if x * x + y * y > BAILOUT_SQR {
+                    escaped_at = Some(iteration);
+                    break;
+                }
+                orbit.push((x, y));
+            }
+
+            let Some(escaped_at) = escaped_at else { continue };
+            for (channel, &limit) in self.limits.iter().enumerate() {
+                if escaped_at >= limit {
+                    continue;
+                }
+                for &(x, y) in &orbit {
+                    if let Some(index) = self.pixel_index(x, y) {
+                        histograms[channel][index] += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    fn pixel_index(&self, x: f64, y: f64) -> Option<usize> {
+        let px = (x - self.x_min) / self.x_scale;
+        let py = (y - self.y_min) / self.y_scale;
+        if px < 0.0 || py < 0.0 || px >= self.width as f64 || py >= self.height as f64 {
+            return None;
+        }
+        Some(py as usize * self.width + px as usize)
+    }
+
+    /// The density image so far, with each channel normalized to its brightest pixel
+    pub fn to_samples(&self) -> Vec<Vec<FractalSample>> {
+        // Square root keeps the faint outer orbits visible next to the bright core
+        let brightness: Vec<Vec<f64>> = self
+            .histograms
+            .iter()
+            .map(|histogram| {
+                let max = histogram.iter().copied().max().unwrap_or(0).max(1) as f64;
+                histogram.iter().map(|&count| (count as f64 / max).sqrt()).collect()
+            })
+            .collect();
+
+        (0..self.height)
+            .map(|y| {
+                (0..self.width)
+                    .map(|x| {
+                        let index = y * self.width + x;
+                        let level = |channel: &Vec<f64>| (channel[index] * 255.0).round() as u8;
+                        let color = match brightness.as_slice() {
+                            [red, green, blue, ..] => (level(red), level(green), level(blue)),
+                            [gray, ..] => (level(gray), level(gray), level(gray)),
+                            [] => (0, 0, 0),
+                        };
+                        let level = color.0.max(color.1).max(color.2);
+                        FractalSample { color: Some(color), ..FractalSample::new(level as u32) }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// Blocking render with a fixed sample budget, for callers that can't refine over time
+pub fn generate(params: &FractalParams) -> Vec<Vec<FractalSample>> {
+    match DensityAccumulator::new(params) {
+        Some(mut density) => {
+            density.accumulate(params.width * params.height * SAMPLES_PER_PIXEL);
+            density.to_samples()
+        }
+        None => vec![vec![FractalSample::default(); params.width]; params.height],
+    }
+}
@@ -0,0 +1,752 @@
+use crate::buddhabrot::{self, DEFAULT_NEBULABROT_LIMITS};
+use crate::expression::{self, Expr};
+use crate::fractal::{FractalGenerator, FractalParams, FractalSample, FractalType};
+use crate::orbit_trap::TrapTracker;
+use crate::perturbation::PerturbationKind;
+use crate::polynomial::Polynomial;
+use crate::precision::{KernelComplex, KernelFloat};
+use crate::registry::{Fractal, FractalParameter, PlanePoint, Viewport};
+use num_complex::Complex;
+use std::sync::Arc;
+
+// A Newton pixel has converged once it is this close to one of the roots
+const NEWTON_TOLERANCE: f64 = 1e-6;
+
+// Periodicity checking treats two orbit points as the same when they are
+// this many ulps of the kernel float apart, relative to their magnitude
+const PERIODICITY_TOLERANCE_ULPS: f64 = 64.0;
+
+// Julia constant the presets and the registry start from
+const DEFAULT_JULIA_C: Complex<f64> = Complex::new(-0.7269, 0.1889);
+
+// Runs a kernel that is generic over the float type in whichever precision the point arrived in
+macro_rules! in_precision {
+    ($point:expr, $c:ident => $kernel:expr) => {
+        match $point {
+            PlanePoint::F32($c) => $kernel,
+            PlanePoint::F64($c) => $kernel,
+            PlanePoint::DoubleDouble($c) => $kernel,
+        }
+    };
+}
+
+/// Every built-in fractal with its default parameters, in cycling order. Custom
+/// formulas are left out since they only exist once typed into the editor.
+pub fn defaults() -> Vec<Arc<dyn Fractal>> {
+    let newton = Polynomial::parse("z^3 - 1").expect("default Newton polynomial parses");
+    vec![
+        Arc::new(Mandelbrot),
+        Arc::new(BurningShip { julia: None }),
+        Arc::new(Julia { c: DEFAULT_JULIA_C }),
+        Arc::new(Tricorn { julia: None }),
+        Arc::new(Multibrot { power: 3.0, julia: None }),
+        Arc::new(Newton { polynomial: newton }),
+        Arc::new(Buddhabrot),
+        Arc::new(Nebulabrot { limits: DEFAULT_NEBULABROT_LIMITS }),
+    ]
+}
+
+/// Stands in for custom formulas in the registry, so it can build them from a
+/// `FractalType` without offering them by name
+pub fn custom_template() -> Arc<dyn Fractal> {
+    Arc::new(Custom::new("z^2 + c").expect("default custom formula parses"))
+}
+
+pub struct Mandelbrot;
+
+impl Fractal for Mandelbrot {
+    fn name(&self) -> &str {
+        "Mandelbrot"
+    }
+
+    fn iterate(&self, generator: &FractalGenerator, point: PlanePoint, max_iterations: u32) -> FractalSample {
+        in_precision!(point, c => mandelbrot_iterations(generator, c, max_iterations))
+    }
+
+    fn default_viewport(&self) -> Viewport {
+        Viewport { center_x: -0.5, ..Viewport::default() }
+    }
+
+    fn fractal_type(&self) -> FractalType {
+        FractalType::Mandelbrot
+    }
+
+    fn with_type(&self, fractal_type: &FractalType) -> Option<Arc<dyn Fractal>> {
+        matches!(fractal_type, FractalType::Mandelbrot).then(|| Arc::new(Mandelbrot) as Arc<dyn Fractal>)
+    }
+
+    fn julia(&self, c: Complex<f64>) -> Option<Arc<dyn Fractal>> {
+        Some(Arc::new(Julia { c }))
+    }
+
+    fn supports_precision(&self) -> bool {
+        true
+    }
+
+    fn perturbation_kind(&self) -> Option<PerturbationKind> {
+        Some(PerturbationKind::Mandelbrot)
+    }
+
+    fn render(&self, generator: &FractalGenerator, params: &FractalParams) -> Option<Vec<Vec<FractalSample>>> {
+        let max_iterations = generator.mandelbrot_max_iterations(params);
+        // Use adaptive sampling for better performance at high zoom levels
+        let sample_step = if generator.use_adaptive_sampling && params.zoom > 10.0 { 2 } else { 1 };
+        Some(generator.generate_points(params, sample_step, |c| self.iterate(generator, c, max_iterations)))
+    }
+}
+
+pub struct Julia {
+    pub c: Complex<f64>,
+}
+
+impl Fractal for Julia {
+    fn name(&self) -> &str {
+        "Julia Set"
+    }
+
+    fn iterate(&self, generator: &FractalGenerator, point: PlanePoint, max_iterations: u32) -> FractalSample {
+        in_precision!(point, z => julia_iterations(generator, z, KernelComplex::from_f64(self.c), max_iterations))
+    }
+
+    fn parameters(&self) -> Vec<FractalParameter> {
+        vec![
+            FractalParameter::new("c_real", self.c.re, -2.0, 2.0),
+            FractalParameter::new("c_imag", self.c.im, -2.0, 2.0),
+        ]
+    }
+
+    fn fractal_type(&self) -> FractalType {
+        FractalType::Julia { c: self.c }
+    }
+
+    fn with_type(&self, fractal_type: &FractalType) -> Option<Arc<dyn Fractal>> {
+        match fractal_type {
+            FractalType::Julia { c } => Some(Arc::new(Julia { c: *c })),
+            _ => None,
+        }
+    }
+
+    fn supports_precision(&self) -> bool {
+        true
+    }
+}
+
+// The escape-time fractals below double as their own Julia sets: with `julia`
+// set, each pixel is the starting z and c stays fixed at that value.
+
+// Where the orbit starts and which c it uses, in either the parameter plane or Julia mode
+fn orbit_start<T: KernelFloat>(point: KernelComplex<T>, julia: Option<Complex<f64>>) -> (KernelComplex<T>, KernelComplex<T>) {
+    match julia {
+        Some(c) => (point, KernelComplex::from_f64(c)),
+        None => (KernelComplex::zero(), point),
+    }
+}
+
+fn julia_parameters(julia: Option<Complex<f64>>) -> Vec<FractalParameter> {
+    julia.map_or_else(Vec::new, |c| {
+        vec![
+            FractalParameter::new("c_real", c.re, -2.0, 2.0),
+            FractalParameter::new("c_imag", c.im, -2.0, 2.0),
+        ]
+    })
+}
+
+fn julia_type(parameter_plane: FractalType, julia: Option<Complex<f64>>) -> FractalType {
+    match julia {
+        Some(c) => FractalType::JuliaOf { parameter_plane: Box::new(parameter_plane), c },
+        None => parameter_plane,
+    }
+}
+
+pub struct BurningShip {
+    pub julia: Option<Complex<f64>>,
+}
+
+impl Fractal for BurningShip {
+    fn name(&self) -> &str {
+        if self.julia.is_some() { "Burning Ship Julia" } else { "Burning Ship" }
+    }
+
+    fn iterate(&self, generator: &FractalGenerator, point: PlanePoint, max_iterations: u32) -> FractalSample {
+        in_precision!(point, point => {
+            let (z, c) = orbit_start(point, self.julia);
+            burning_ship_iterations(generator, z, c, max_iterations)
+        })
+    }
+
+    fn default_viewport(&self) -> Viewport {
+        match self.julia {
+            Some(_) => Viewport::default(),
+            None => Viewport { center_x: -0.5, center_y: -0.5, ..Viewport::default() },
+        }
+    }
+
+    fn parameters(&self) -> Vec<FractalParameter> {
+        julia_parameters(self.julia)
+    }
+
+    fn fractal_type(&self) -> FractalType {
+        julia_type(FractalType::BurningShip, self.julia)
+    }
+
+    fn with_type(&self, fractal_type: &FractalType) -> Option<Arc<dyn Fractal>> {
+        matches!(fractal_type, FractalType::BurningShip).then(|| Arc::new(BurningShip { julia: None }) as Arc<dyn Fractal>)
+    }
+
+    fn julia(&self, c: Complex<f64>) -> Option<Arc<dyn Fractal>> {
+        self.julia.is_none().then(|| Arc::new(BurningShip { julia: Some(c) }) as Arc<dyn Fractal>)
+    }
+
+    fn supports_precision(&self) -> bool {
+        true
+    }
+
+    fn perturbation_kind(&self) -> Option<PerturbationKind> {
+        self.julia.is_none().then_some(PerturbationKind::BurningShip)
+    }
+}
+
+pub struct Tricorn {
+    pub julia: Option<Complex<f64>>,
+}
+
+impl Fractal for Tricorn {
+    fn name(&self) -> &str {
+        if self.julia.is_some() { "Tricorn Julia" } else { "Tricorn" }
+    }
+
+    fn iterate(&self, generator: &FractalGenerator, point: PlanePoint, max_iterations: u32) -> FractalSample {
+        in_precision!(point, point => {
+            let (z, c) = orbit_start(point, self.julia);
+            tricorn_iterations(generator, z, c, max_iterations)
+        })
+    }
+
+    fn parameters(&self) -> Vec<FractalParameter> {
+        julia_parameters(self.julia)
+    }
+
+    fn fractal_type(&self) -> FractalType {
+        julia_type(FractalType::Tricorn, self.julia)
+    }
+
+    fn with_type(&self, fractal_type: &FractalType) -> Option<Arc<dyn Fractal>> {
+        matches!(fractal_type, FractalType::Tricorn).then(|| Arc::new(Tricorn { julia: None }) as Arc<dyn Fractal>)
+    }
+
+    fn julia(&self, c: Complex<f64>) -> Option<Arc<dyn Fractal>> {
+        self.julia.is_none().then(|| Arc::new(Tricorn { julia: Some(c) }) as Arc<dyn Fractal>)
+    }
+
+    fn supports_precision(&self) -> bool {
+        true
+    }
+
+    fn perturbation_kind(&self) -> Option<PerturbationKind> {
+        self.julia.is_none().then_some(PerturbationKind::Tricorn)
+    }
+}
+
+pub struct Multibrot {
+    pub power: f64,
+    pub julia: Option<Complex<f64>>,
+}
+
+impl Fractal for Multibrot {
+    fn name(&self) -> &str {
+        if self.julia.is_some() { "Multibrot Julia" } else { "Multibrot" }
+    }
+
+    fn iterate(&self, generator: &FractalGenerator, point: PlanePoint, max_iterations: u32) -> FractalSample {
+        in_precision!(point, point => {
+            let (z, c) = orbit_start(point, self.julia);
+            multibrot_iterations(generator, z, c, self.power, self.julia.is_some(), max_iterations)
+        })
+    }
+
+    fn parameters(&self) -> Vec<FractalParameter> {
+        let mut parameters = vec![FractalParameter::new("power", self.power, 2.0, 10.0)];
+        parameters.extend(julia_parameters(self.julia));
+        parameters
+    }
+
+    fn fractal_type(&self) -> FractalType {
+        julia_type(FractalType::Multibrot { power: self.power }, self.julia)
+    }
+
+    fn with_type(&self, fractal_type: &FractalType) -> Option<Arc<dyn Fractal>> {
+        match fractal_type {
+            FractalType::Multibrot { power } => Some(Arc::new(Multibrot { power: *power, julia: None })),
+            _ => None,
+        }
+    }
+
+    fn julia(&self, c: Complex<f64>) -> Option<Arc<dyn Fractal>> {
+        self.julia.is_none().then(|| Arc::new(Multibrot { power: self.power, julia: Some(c) }) as Arc<dyn Fractal>)
+    }
+
+    fn supports_precision(&self) -> bool {
+        true
+    }
+
+    fn perturbation_kind(&self) -> Option<PerturbationKind> {
+        // Only integer powers have a finite binomial expansion
+        (self.julia.is_none() && self.power.fract() == 0.0 && (2.0..=64.0).contains(&self.power))
+            .then_some(PerturbationKind::Multibrot { power: self.power as u32 })
+    }
+}
+
+// Formulas are evaluated on Complex<f64>, so they always run in f64
+pub struct Custom {
+    equation: String,
+    formula: Expr,
+    julia: Option<Complex<f64>>,
+}
+
+impl Custom {
+    pub fn new(equation: &str) -> Option<Self> {
+        let formula = expression::parse(equation).ok()?;
+        Some(Self { equation: equation.to_string(), formula, julia: None })
+    }
+}
+
+impl Fractal for Custom {
+    fn name(&self) -> &str {
+        if self.julia.is_some() { "Custom Julia" } else { "Custom" }
+    }
+
+    fn iterate(&self, generator: &FractalGenerator, point: PlanePoint, max_iterations: u32) -> FractalSample {
+        // The parameter plane follows the orbit of the critical value c, which
+        // is z1 for z^p + c and the singular value of sin(z) * c and exp(z) + c.
+        // Starting at z0 = 0 instead would leave sin(z) * c stuck at 0 and
+        // z^(1+i) + c undefined, so the orbit starts one step in at z1 = c.
+        let (z, c, iterations) = match self.julia {
+            Some(c) => (point.to_f64(), c, 0),
+            None => (point.to_f64(), point.to_f64(), 1),
+        };
+        custom_iterations(generator, &self.formula, z, c, iterations.min(max_iterations), max_iterations)
+    }
+
+    fn parameters(&self) -> Vec<FractalParameter> {
+        julia_parameters(self.julia)
+    }
+
+    fn fractal_type(&self) -> FractalType {
+        julia_type(FractalType::Custom { equation: self.equation.clone() }, self.julia)
+    }
+
+    // `None` as well for formulas that no longer parse
+    fn with_type(&self, fractal_type: &FractalType) -> Option<Arc<dyn Fractal>> {
+        match fractal_type {
+            FractalType::Custom { equation } => Some(Arc::new(Custom::new(equation)?)),
+            _ => None,
+        }
+    }
+
+    fn julia(&self, c: Complex<f64>) -> Option<Arc<dyn Fractal>> {
+        self.julia.is_none().then(|| {
+            Arc::new(Custom { equation: self.equation.clone(), formula: self.formula.clone(), julia: Some(c) }) as Arc<dyn Fractal>
+        })
+    }
+}
+
+pub struct Newton {
+    pub polynomial: Polynomial,
+}
+
+impl Fractal for Newton {
+    fn name(&self) -> &str {
+        "Newton"
+    }
+
+    fn iterate(&self, generator: &FractalGenerator, point: PlanePoint, max_iterations: u32) -> FractalSample {
+        newton_iterations(generator, &self.polynomial, point.to_f64(), max_iterations)
+    }
+
+    fn fractal_type(&self) -> FractalType {
+        FractalType::Newton { polynomial: self.polynomial.clone() }
+    }
+
+    fn with_type(&self, fractal_type: &FractalType) -> Option<Arc<dyn Fractal>> {
+        match fractal_type {
+            FractalType::Newton { polynomial } => Some(Arc::new(Newton { polynomial: polynomial.clone() })),
+            _ => None,
+        }
+    }
+}
+
+pub struct Buddhabrot;
+
+impl Fractal for Buddhabrot {
+    fn name(&self) -> &str {
+        "Buddhabrot"
+    }
+
+    // Density renders have no per-pixel orbit; see `render`
+    fn iterate(&self, _generator: &FractalGenerator, _point: PlanePoint, _max_iterations: u32) -> FractalSample {
+        FractalSample::default()
+    }
+
+    fn default_viewport(&self) -> Viewport {
+        Viewport { center_x: -0.5, ..Viewport::default() }
+    }
+
+    fn fractal_type(&self) -> FractalType {
+        FractalType::Buddhabrot
+    }
+
+    fn with_type(&self, fractal_type: &FractalType) -> Option<Arc<dyn Fractal>> {
+        matches!(fractal_type, FractalType::Buddhabrot).then(|| Arc::new(Buddhabrot) as Arc<dyn Fractal>)
+    }
+
+    fn render(&self, _generator: &FractalGenerator, params: &FractalParams) -> Option<Vec<Vec<FractalSample>>> {
+        Some(buddhabrot::generate(params))
+    }
+}
+
+pub struct Nebulabrot {
+    pub limits: [u32; 3],
+}
+
+impl Fractal for Nebulabrot {
+    fn name(&self) -> &str {
+        "Nebulabrot"
+    }
+
+    fn iterate(&self, _generator: &FractalGenerator, _point: PlanePoint, _max_iterations: u32) -> FractalSample {
+        FractalSample::default()
+    }
+
+    fn default_viewport(&self) -> Viewport {
+        Viewport { center_x: -0.5, ..Viewport::default() }
+    }
+
+    fn parameters(&self) -> Vec<FractalParameter> {
+        let [red, green, blue] = self.limits.map(|limit| limit as f64);
+        vec![
+            FractalParameter::new("red_limit", red, 1.0, 100_000.0),
+            FractalParameter::new("green_limit", green, 1.0, 100_000.0),
+            FractalParameter::new("blue_limit", blue, 1.0, 100_000.0),
+        ]
+    }
+
+    fn fractal_type(&self) -> FractalType {
+        FractalType::Nebulabrot { limits: self.limits }
+    }
+
+    fn with_type(&self, fractal_type: &FractalType) -> Option<Arc<dyn Fractal>> {
+        match fractal_type {
+            FractalType::Nebulabrot { limits } => Some(Arc::new(Nebulabrot { limits: *limits })),
+            _ => None,
+        }
+    }
+
+    fn render(&self, _generator: &FractalGenerator, params: &FractalParams) -> Option<Vec<Vec<FractalSample>>> {
+        Some(buddhabrot::generate(params))
+    }
+}
+
+fn mandelbrot_iterations<T: KernelFloat>(generator: &FractalGenerator, c: KernelComplex<T>, max_iterations: u32) -> FractalSample {
+    // A trap needs the interior orbits too, so they can't be skipped
+    if generator.cardioid_check && generator.orbit_trap.is_none() {
+        let c = c.to_f64();
+        if let Some(period) = cardioid_or_bulb_period(c.re, c.im) {
+            return FractalSample { period: Some(period), ..FractalSample::new(max_iterations) };
+        }
+    }
+
+    let bailout_sqr = generator.bailout_sqr();
+    let mut trap = TrapTracker::new(generator.orbit_trap);
+    let mut cycle = CycleDetector::new(generator);
+    let mut z = KernelComplex::zero();
+    let mut derivative = Complex::new(0.0, 0.0);
+    let mut iterations = 0;
+
+    while iterations < max_iterations && z.norm_sqr() <= bailout_sqr {
+        if generator.distance_estimation {
+            // dz/dc: z' -> 2 z z' + 1
+            derivative = derivative * z.to_f64() * 2.0 + 1.0;
+        }
+        z = z.square() + c;
+        trap.visit(z.to_f64());
+        iterations += 1;
+        if cycle.visit(z) {
+            iterations = max_iterations;
+        }
+    }
+
+    let z = z.to_f64();
+    FractalSample {
+        trap_distance: trap.distance(),
+        period: cycle.period(),
+        distance: generator.distance_estimate(iterations, max_iterations, z, derivative),
+        ..generator.finish_sample(iterations, max_iterations, z, 2.0)
+    }
+}
+
+fn julia_iterations<T: KernelFloat>(generator: &FractalGenerator, mut z: KernelComplex<T>, c: KernelComplex<T>, max_iterations: u32) -> FractalSample {
+    let bailout_sqr = generator.bailout_sqr();
+    let mut trap = TrapTracker::new(generator.orbit_trap);
+    let mut cycle = CycleDetector::new(generator);
+    let mut derivative = Complex::new(1.0, 0.0);
+    let mut iterations = 0;
+
+    while iterations < max_iterations && z.norm_sqr() <= bailout_sqr {
+        if generator.distance_estimation {
+            // dz/dz0: z' -> 2 z z'
+            derivative = derivative * z.to_f64() * 2.0;
+        }
+        z = z.square() + c;
+        trap.visit(z.to_f64());
+        iterations += 1;
+        if cycle.visit(z) {
+            iterations = max_iterations;
+        }
+    }
+
+    let z = z.to_f64();
+    FractalSample {
+        trap_distance: trap.distance(),
+        period: cycle.period(),
+        distance: generator.distance_estimate(iterations, max_iterations, z, derivative),
+        ..generator.finish_sample(iterations, max_iterations, z, 2.0)
+    }
+}
+
+fn burning_ship_iterations<T: KernelFloat>(generator: &FractalGenerator, mut z: KernelComplex<T>, c: KernelComplex<T>, max_iterations: u32) -> FractalSample {
+    let bailout_sqr = generator.bailout_sqr();
+    let mut trap = TrapTracker::new(generator.orbit_trap);
+    let mut cycle = CycleDetector::new(generator);
+    let mut iterations = 0;
+
+    while iterations < max_iterations && z.norm_sqr() <= bailout_sqr {
+        // Burning ship: z = (|Re(z)| + i|Im(z)|)^2 + c
+        z = KernelComplex::new(z.re.abs(), z.im.abs());
+        z = z.square() + c;
+        trap.visit(z.to_f64());
+        iterations += 1;
+        if cycle.visit(z) {
+            iterations = max_iterations;
+        }
+    }
+
+    FractalSample {
+        trap_distance: trap.distance(),
+        period: cycle.period(),
+        ..generator.finish_sample(iterations, max_iterations, z.to_f64(), 2.0)
+    }
+}
+
+fn tricorn_iterations<T: KernelFloat>(generator: &FractalGenerator, mut z: KernelComplex<T>, c: KernelComplex<T>, max_iterations: u32) -> FractalSample {
+    let bailout_sqr = generator.bailout_sqr();
+    let mut trap = TrapTracker::new(generator.orbit_trap);
+    let mut cycle = CycleDetector::new(generator);
+    let mut iterations = 0;
+
+    while iterations < max_iterations && z.norm_sqr() <= bailout_sqr {
+        // Tricorn: z = conj(z)^2 + c
+        z = z.conj().square() + c;
+        trap.visit(z.to_f64());
+        iterations += 1;
+        if cycle.visit(z) {
+            iterations = max_iterations;
+        }
+    }
+
+    FractalSample {
+        trap_distance: trap.distance(),
+        period: cycle.period(),
+        ..generator.finish_sample(iterations, max_iterations, z.to_f64(), 2.0)
+    }
+}
+
+fn multibrot_iterations<T: KernelFloat>(
+    generator: &FractalGenerator,
+    mut z: KernelComplex<T>,
+    c: KernelComplex<T>,
+    power: f64,
+    julia: bool,
+    max_iterations: u32,
+) -> FractalSample {
+    let bailout_sqr = generator.bailout_sqr();
+    let mut trap = TrapTracker::new(generator.orbit_trap);
+    let mut cycle = CycleDetector::new(generator);
+    let mut iterations = 0;
+
+    // Integer powers multiply out exactly in any precision; fractional ones
+    // go through the f64 polar form
+    let integer_power = (power.fract() == 0.0 && (1.0..=64.0).contains(&power)).then_some(power as u32);
+    // dz/dc starts at 0 and gains 1 per step; in Julia mode dz/dz0 starts at 1 and doesn't
+    let (mut derivative, derivative_step) = if julia { (Complex::new(1.0, 0.0), 0.0) } else { (Complex::new(0.0, 0.0), 1.0) };
+
+    while iterations < max_iterations && z.norm_sqr() <= bailout_sqr {
+        if generator.distance_estimation {
+            // z' -> p z^(p-1) z' (+ 1)
+            let z = z.to_f64();
+            let slope = match integer_power {
+                Some(power) => z.powu(power - 1),
+                None => z.powf(power - 1.0),
+            };
+            derivative = derivative * slope * power + derivative_step;
+        }
+        // Multibrot: z = z^power + c
+        z = match integer_power {
+            Some(power) => z.powi(power),
+            None => KernelComplex::from_f64(z.to_f64().powf(power)),
+        } + c;
+        trap.visit(z.to_f64());
+        iterations += 1;
+        if cycle.visit(z) {
+            iterations = max_iterations;
+        }
+    }
+
+    let z = z.to_f64();
+    FractalSample {
+        trap_distance: trap.distance(),
+        period: cycle.period(),
+        distance: generator.distance_estimate(iterations, max_iterations, z, derivative),
+        ..generator.finish_sample(iterations, max_iterations, z, power.abs().max(1.01))
+    }
+}
+
+// Iterates from `z`, which is already `iterations` steps into the orbit
+fn custom_iterations(generator: &FractalGenerator, formula: &Expr, mut z: Complex<f64>, c: Complex<f64>, mut iterations: u32, max_iterations: u32) -> FractalSample {
+    let bailout_sqr = generator.bailout_sqr();
+    let mut trap = TrapTracker::new(generator.orbit_trap);
+    let mut cycle = CycleDetector::new(generator);
+    let mut previous_norm_sqr = 0.0;
+    if iterations > 0 {
+        trap.visit(z);
+        cycle.visit(KernelComplex::<f64>::from_f64(z));
+    }
+
+    // Transcendental formulas can overflow to NaN instead of growing past the bailout
+    while iterations < max_iterations && z.norm_sqr() <= bailout_sqr {
+        let next = formula.eval(z, c);
+        if !next.re.is_finite() || !next.im.is_finite() {
+            return FractalSample { trap_distance: trap.distance(), ..FractalSample::new(iterations) };
+        }
+        previous_norm_sqr = z.norm_sqr();
+        z = next;
+        trap.visit(z);
+        iterations += 1;
+        if cycle.visit(KernelComplex::<f64>::from_f64(z)) {
+            iterations = max_iterations;
+        }
+    }
+
+    // The degree of an arbitrary formula is unknown, so estimate it from how
+    // fast ln|z| grew over the last step
+    let power = if previous_norm_sqr > 1.0 {
+        (z.norm_sqr().ln() / previous_norm_sqr.ln()).clamp(1.01, 16.0)
+    } else {
+        2.0
+    };
+    FractalSample {
+        trap_distance: trap.distance(),
+        period: cycle.period(),
+        ..generator.finish_sample(iterations, max_iterations, z, power)
+    }
+}
+
+fn newton_iterations(generator: &FractalGenerator, polynomial: &Polynomial, mut z: Complex<f64>, max_iterations: u32) -> FractalSample {
+    let roots = polynomial.roots();
+    let tolerance_sqr = NEWTON_TOLERANCE * NEWTON_TOLERANCE;
+
+    for iterations in 0..max_iterations {
+        let nearest = roots
+            .iter()
+            .map(|root| (z - root).norm_sqr())
+            .enumerate()
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((root, distance_sqr)) = nearest {
+            if distance_sqr < tolerance_sqr {
+                // Convergence is quadratic, so ln|z - root| roughly doubles each step;
+                // how far past the tolerance the last step landed gives the fraction
+                let smooth = generator.smooth_coloring.then(|| {
+                    let overshoot = distance_sqr.max(f64::MIN_POSITIVE).ln() / tolerance_sqr.ln();
+                    (iterations as f64 + 1.0 - overshoot.log2()).max(0.0)
+                });
+                return FractalSample { smooth, root: Some(root), ..FractalSample::new(iterations) };
+            }
+        }
+
+        // z -> z - p(z) / p'(z)
+        let (value, derivative) = polynomial.eval_with_derivative(z);
+        if derivative.norm_sqr() == 0.0 {
+            break;
+        }
+        z -= value / derivative;
+        if !z.re.is_finite() || !z.im.is_finite() {
+            break;
+        }
+    }
+
+    FractalSample::new(max_iterations)
+}
+
+
+/// Period of the attracting cycle for a Mandelbrot `c` in the main cardioid (1)
+/// or the period-2 bulb (2), found without iterating
+pub(crate) fn cardioid_or_bulb_period(x: f64, y: f64) -> Option<u32> {
+    let q = (x - 0.25) * (x - 0.25) + y * y;
+    if q * (q + (x - 0.25)) <= 0.25 * y * y {
+        Some(1)
+    } else if (x + 1.0) * (x + 1.0) + y * y <= 0.0625 {
+        Some(2)
+    } else {
+        None
+    }
+}
+
+// Brent's cycle detection: each orbit point is compared against a checkpoint
+// that jumps forward after 1, 2, 4, 8... steps, so a cycle of any length is
+// caught within a couple of its periods of the orbit settling into it
+struct CycleDetector<T> {
+    enabled: bool,
+    checkpoint: Option<KernelComplex<T>>,
+    steps: u32,
+    window: u32,
+    period: Option<u32>,
+}
+
+impl<T: KernelFloat> CycleDetector<T> {
+    // Cutting an orbit short would leave an orbit trap with a partial orbit,
+    // so detection is off while a trap is set
+    fn new(generator: &FractalGenerator) -> Self {
+        let enabled = generator.periodicity_check && generator.orbit_trap.is_none();
+        Self { enabled, checkpoint: None, steps: 0, window: 1, period: None }
+    }
+
+    /// Records the next orbit point; true once the orbit has been seen to repeat
+    fn visit(&mut self, z: KernelComplex<T>) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        let Some(checkpoint) = self.checkpoint else {
+            self.checkpoint = Some(z);
+            return false;
+        };
+
+        self.steps += 1;
+        // Float spacing grows with magnitude, so the tolerance does too; points
+        // of a cycle through 0 still match exactly
+        let magnitude = checkpoint.re.abs().to_f64().max(checkpoint.im.abs().to_f64());
+        let tolerance = T::EPSILON * PERIODICITY_TOLERANCE_ULPS * magnitude;
+        if (z.re - checkpoint.re).abs().to_f64() <= tolerance && (z.im - checkpoint.im).abs().to_f64() <= tolerance {
+            self.period = Some(self.steps);
+            return true;
+        }
+        if self.steps == self.window {
+            self.checkpoint = Some(z);
+            self.steps = 0;
+            self.window *= 2;
+        }
+        false
+    }
+
+    fn period(&self) -> Option<u32> {
+        self.period
+    }
+}
@@ -0,0 +1,290 @@
+use crate::app::{App, AppMode};
+use crate::bigfloat::BigFloat;
+use crate::command;
+use crate::config::{Config, PerformanceConfig, DEFAULT_CONFIG_PATH};
+use crate::export::{self, ExportFormat};
+use crate::fractal::{FractalGenerator, FractalParams, FractalType};
+use crate::history::ViewState;
+use crate::registry::Fractal;
+use crate::renderer::{CellMode, TerminalRenderer};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+// Command-line arguments. Everything given here overrides the config file,
+// which in turn overrides the built-in defaults.
+
+#[derive(Debug, Parser)]
+// Options can't come before a subcommand, where the TUI's would be ignored
+#[command(name = "fractal-generator", version, about = "Explore fractals in the terminal", args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    /// Config file to load; config.json is used if it exists
+    #[arg(long, value_name = "PATH", global = true)]
+    pub config: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Option<CliCommand>,
+
+    #[command(flatten)]
+    pub view: ViewArgs,
+
+    /// Mode to start the TUI in
+    #[arg(long, value_enum, default_value_t = StartMode::Interactive)]
+    pub mode: StartMode,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CliCommand {
+    /// Render one image to a file or stdout without starting the TUI
+    Render(RenderArgs),
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct RenderArgs {
+    #[command(flatten)]
+    pub view: ViewArgs,
+
+    /// Image size in characters (or pixels for PNG); defaults to the config's display size
+    #[arg(long, value_name = "WIDTHxHEIGHT", value_parser = command::parse_size)]
+    pub size: Option<(usize, usize)>,
+
+    /// Output format; defaults to the one the output file's extension names, else txt
+    #[arg(long, value_parser = parse_format)]
+    pub format: Option<ExportFormat>,
+
+    /// File to write; stdout if not given
+    #[arg(short, long, value_name = "PATH")]
+    pub output: Option<PathBuf>,
+}
+
+/// What to show and how to draw it
+#[derive(Debug, Clone, Default, Args)]
+pub struct ViewArgs {
+    /// Fractal and its parameters, e.g. mandelbrot, burning-ship or "julia -0.8 0.156"
+    #[arg(long, value_name = "NAME")]
+    pub fractal: Option<String>,
+
+    /// View center, e.g. -0.743643887,0.131825904
+    #[arg(long, value_name = "X,Y", value_parser = parse_center, allow_hyphen_values = true)]
+    pub center: Option<(BigFloat, BigFloat)>,
+
+    /// Zoom factor, e.g. 1e6
+    #[arg(long, value_parser = parse_zoom, allow_hyphen_values = true)]
+    pub zoom: Option<BigFloat>,
+
+    /// Maximum iterations per point
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..=command::MAX_ITERATIONS as i64))]
+    pub iterations: Option<u32>,
+
+    /// Colour scheme
+    #[arg(long, value_name = "NAME")]
+    pub palette: Option<String>,
+
+    /// Samples per character: text (one), braille (2x4 dots) or half-block (two stacked)
+    #[arg(long, value_name = "MODE", value_parser = parse_cell_mode)]
+    pub cells: Option<CellMode>,
+
+    /// Draw without colours
+    #[arg(long)]
+    pub no_color: bool,
+
+    /// Draw with ASCII characters only
+    #[arg(long)]
+    pub ascii: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum StartMode {
+    Interactive,
+    Auto,
+}
+
+impl Cli {
+    /// The config the arguments name, with their overrides applied and validated
+    pub fn load_config(&self) -> Result<(Config, PathBuf), String> {
+        self.load_config_with(&self.view)
+    }
+
+    /// Loads the config the arguments name and applies `view`'s overrides to it
+    pub fn load_config_with(&self, view: &ViewArgs) -> Result<(Config, PathBuf), String> {
+        let (mut config, path) = load_config(self.config.as_deref())?;
+        view.apply_to_config(&mut config);
+        config.validate().map_err(|error| format!("invalid config {}: {}", path.display(), error))?;
+        Ok((config, path))
+    }
+
+    /// The TUI, set up from the config and arguments
+    pub fn build_app(&self) -> Result<App, String> {
+        let (config, path) = self.load_config()?;
+        let mut app = App::with_config(config, path);
+        self.view.apply_to_app(&mut app)?;
+        if self.mode == StartMode::Auto {
+            app.mode = AppMode::AutoGenerate;
+        }
+        Ok(app)
+    }
+}
+
+impl ViewArgs {
+    pub fn apply_to_config(&self, config: &mut Config) {
+        if let Some(palette) = &self.palette {
+            config.display.color_scheme = palette.clone();
+        }
+        if let Some(cells) = self.cells {
+            config.display.cell_mode = cells;
+        }
+        if self.no_color {
+            config.display.use_colors = false;
+        }
+        if self.ascii {
+            config.display.use_unicode = false;
+        }
+    }
+
+    /// The fractal named by `--fractal`, if any
+    pub fn fractal(&self, generator: &FractalGenerator) -> Result<Option<Arc<dyn Fractal>>, String> {
+        let Some(fractal) = &self.fractal else { return Ok(None) };
+        let mut words = fractal.split_whitespace().map(String::from);
+        let name = words.next().ok_or("--fractal needs a name")?;
+        let arguments: Vec<String> = words.collect();
+        command::resolve_fractal(generator, &name, &arguments).map(Some)
+    }
+
+    /// The view to start on: the config's default view, or the default framing
+    /// of the fractal given, with `--center`, `--zoom` and `--iterations` on top
+    pub fn view_state(&self, config: &Config, generator: &FractalGenerator) -> Result<ViewState, String> {
+        let (zoom_factor, center_x, center_y, max_iterations) = config.get_default_fractal_params();
+        let mut view = ViewState {
+            fractal_type: FractalType::Mandelbrot,
+            equation: "z^2 + c".to_string(),
+            center_x,
+            center_y,
+            zoom_factor,
+            max_iterations,
+        };
+        if let Some(fractal) = self.fractal(generator)? {
+            let viewport = fractal.default_viewport();
+            view.fractal_type = fractal.fractal_type();
+            view.equation = fractal.name().to_string();
+            view.center_x = BigFloat::from(viewport.center_x);
+            view.center_y = BigFloat::from(viewport.center_y);
+            view.zoom_factor = BigFloat::from(viewport.zoom);
+        }
+        if let Some((x, y)) = &self.center {
+            view.center_x = x.clone();
+            view.center_y = y.clone();
+        }
+        if let Some(zoom) = &self.zoom {
+            view.zoom_factor = zoom.clone();
+        }
+        if let Some(iterations) = self.iterations {
+            view.max_iterations = iterations;
+        }
+        Ok(view)
+    }
+
+    /// Starts the app on the requested view
+    pub fn apply_to_app(&self, app: &mut App) -> Result<(), String> {
+        let view = self.view_state(&app.config, &app.fractal_generator)?;
+        app.current_fractal_type = view.fractal_type;
+        app.current_equation = view.equation;
+        app.center_x = view.center_x;
+        app.center_y = view.center_y;
+        app.zoom_factor = view.zoom_factor;
+        app.max_iterations = view.max_iterations;
+        Ok(())
+    }
+}
+
+impl RenderArgs {
+    /// The format to write: `--format`, else the output file's extension, else text
+    pub fn format(&self) -> ExportFormat {
+        self.format.unwrap_or_else(|| self.output.as_deref().map_or(ExportFormat::Text, ExportFormat::from_path))
+    }
+
+    /// Renders the requested view with the same generator and renderer the TUI
+    /// uses, and returns the encoded bytes
+    pub fn render(&self, config: &Config) -> Result<Vec<u8>, String> {
+        let generator = FractalGenerator::from_config(config);
+        let mut renderer = TerminalRenderer::from_config(&config.display);
+        let view = self.view.view_state(config, &generator)?;
+        renderer.set_max_iterations(view.max_iterations);
+        let size = self.size.unwrap_or_else(|| config.get_display_size());
+        let format = self.format();
+        let (width, height) = if format == ExportFormat::Png { size } else { renderer.sample_size(size) };
+        let params = FractalParams {
+            fractal_type: view.fractal_type,
+            width,
+            height,
+            zoom: view.zoom_factor.to_f64(),
+            center_x: view.center_x.to_f64(),
+            center_y: view.center_y.to_f64(),
+            max_iterations: view.max_iterations,
+            precise_center: Some((view.center_x, view.center_y)),
+        };
+        let samples = generator.generate_samples(&params);
+        export::encode(&renderer, &samples, format).map_err(|error| error.to_string())
+    }
+
+    /// Renders and writes to `--output`, or to stdout
+    pub fn run(&self, config: &Config) -> Result<(), String> {
+        let bytes = self.render(config)?;
+        match &self.output {
+            Some(path) => fs::write(path, bytes).map_err(|error| format!("could not write {}: {}", path.display(), error)),
+            None => {
+                let mut stdout = std::io::stdout().lock();
+                stdout.write_all(&bytes).and_then(|()| stdout.flush()).map_err(|error| format!("could not write to stdout: {}", error))
+            },
+        }
+    }
+}
+
+/// Loads the config at `path`, or `config.json` if there is one, or the
+/// defaults. Returns the config with the path bookmarks are kept beside.
+pub fn load_config(path: Option<&Path>) -> Result<(Config, PathBuf), String> {
+    let (path, required) = match path {
+        Some(path) => (path.to_path_buf(), true),
+        None => (PathBuf::from(DEFAULT_CONFIG_PATH), false),
+    };
+    if !required && !path.exists() {
+        return Ok((Config::default(), path));
+    }
+    let config = Config::load_from_file(&path).map_err(|error| format!("could not load config {}: {}", path.display(), error))?;
+    Ok((config, path))
+}
+
+/// Sizes rayon's global pool from the config: one thread when parallel
+/// processing is off, `thread_count` threads when set, else one per core.
+/// Only takes effect before the first parallel render.
+pub fn configure_thread_pool(performance: &PerformanceConfig) -> Result<(), String> {
+    let threads = if performance.use_parallel_processing { performance.thread_count } else { Some(1) };
+    let Some(threads) = threads else { return Ok(()) };
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build_global()
+        .map_err(|error| format!("could not start {} worker threads: {}", threads, error))
+}
+
+fn parse_cell_mode(text: &str) -> Result<CellMode, String> {
+    text.parse()
+}
+
+fn parse_center(text: &str) -> Result<(BigFloat, BigFloat), String> {
+    let (x, y) = text.split_once(',').ok_or_else(|| format!("expected X,Y but got '{}'", text))?;
+    let parse = |value: &str| value.trim().parse::<BigFloat>().map_err(|error| error.to_string());
+    Ok((parse(x)?, parse(y)?))
+}
+
+fn parse_format(text: &str) -> Result<ExportFormat, String> {
+    text.parse()
+}
+
+fn parse_zoom(text: &str) -> Result<BigFloat, String> {
+    let zoom: BigFloat = text.parse().map_err(|error: crate::bigfloat::ParseBigFloatError| error.to_string())?;
+    if zoom.is_negative() || zoom.is_zero() {
+        return Err("zoom must be positive".to_string());
+    }
+    Ok(zoom)
+}
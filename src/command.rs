@@ -0,0 +1,213 @@
+use crate::bigfloat::BigFloat;
+use crate::buddhabrot::DEFAULT_NEBULABROT_LIMITS;
+use crate::fractal::{FractalGenerator, FractalType};
+use crate::polynomial::Polynomial;
+use crate::registry::Fractal;
+use num_complex::Complex;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+// The TUI's `:` command line, for typing exact values instead of steering with
+// keys. Parsing is kept apart from the App so it can be tested on its own.
+
+/// Command names, for completion and the help text
+pub const COMMANDS: [&str; 9] = ["bookmark", "center", "export", "fractal", "help", "iter", "palette", "quit", "zoom"];
+
+/// Largest iteration count `iter` accepts; past this a frame takes minutes, not seconds
+pub const MAX_ITERATIONS: u32 = 1_000_000;
+
+/// Largest side `parse_size` accepts
+pub const MAX_SIZE: usize = 8192;
+
+/// Largest width times height `parse_size` accepts, so an export can't ask for more samples than fit in memory
+pub const MAX_AREA: usize = 4096 * 4096;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Center { x: BigFloat, y: BigFloat },
+    Zoom(BigFloat),
+    Iterations(u32),
+    /// A fractal by name, with whatever followed the name as its parameters
+    Fractal { name: String, arguments: Vec<String> },
+    Palette(String),
+    /// Writes the view to a file; `size` renders it at that resolution instead of the pane's
+    Export { path: PathBuf, size: Option<(usize, usize)> },
+    Bookmark(String),
+    Help,
+    Quit,
+}
+
+pub fn parse(line: &str) -> Result<Command, String> {
+    let line = line.trim().trim_start_matches(':');
+    let mut words = line.split_whitespace();
+    let Some(name) = words.next() else {
+        return Err("empty command".to_string());
+    };
+    let arguments: Vec<&str> = words.collect();
+    let rest = line[name.len()..].trim();
+
+    match name.to_ascii_lowercase().as_str() {
+        "center" | "c" => match arguments.as_slice() {
+            [x, y] => Ok(Command::Center { x: parse_number(x)?, y: parse_number(y)? }),
+            _ => Err("usage: center <x> <y>".to_string()),
+        },
+        "zoom" | "z" => match arguments.as_slice() {
+            [zoom] => {
+                let zoom = parse_number(zoom)?;
+                if zoom.is_negative() || zoom.is_zero() {
+                    return Err("zoom must be positive".to_string());
+                }
+                Ok(Command::Zoom(zoom))
+            },
+            _ => Err("usage: zoom <factor>".to_string()),
+        },
+        "iter" | "iterations" | "i" => match arguments.as_slice() {
+            [iterations] => match iterations.parse::<u32>() {
+                Ok(iterations) if (1..=MAX_ITERATIONS).contains(&iterations) => Ok(Command::Iterations(iterations)),
+                _ => Err(format!("invalid iteration count '{}' (expected 1 to {})", iterations, MAX_ITERATIONS)),
+            },
+            _ => Err("usage: iter <count>".to_string()),
+        },
+        "fractal" | "f" => {
+            if arguments.is_empty() {
+                return Err("usage: fractal <name> [parameters]".to_string());
+            }
+            Ok(Command::Fractal {
+                name: arguments[0].to_string(),
+                arguments: arguments[1..].iter().map(|argument| argument.to_string()).collect(),
+            })
+        },
+        "palette" | "p" => match arguments.as_slice() {
+            [palette] => Ok(Command::Palette(palette.to_string())),
+            _ => Err("usage: palette <name>".to_string()),
+        },
+        "export" | "e" | "w" => match arguments.as_slice() {
+            [path] => Ok(Command::Export { path: PathBuf::from(path), size: None }),
+            [path, size] => Ok(Command::Export { path: PathBuf::from(path), size: Some(parse_size(size)?) }),
+            _ => Err("usage: export <file.png|file.txt|file.ans> [WIDTHxHEIGHT]".to_string()),
+        },
+        "bookmark" | "b" => {
+            if rest.is_empty() {
+                return Err("usage: bookmark <name>".to_string());
+            }
+            Ok(Command::Bookmark(rest.to_string()))
+        },
+        "help" | "h" => Ok(Command::Help),
+        "quit" | "q" => Ok(Command::Quit),
+        other => Err(format!("unknown command '{}'", other)),
+    }
+}
+
+/// The built-in fractal a `fractal` command names. `Ok(None)` means the name is
+/// not a built-in one, so the caller should look it up in its registry.
+pub fn fractal_type(name: &str, arguments: &[String]) -> Result<Option<FractalType>, String> {
+    let numbers = || arguments.iter().map(|argument| parse_f64(argument)).collect::<Result<Vec<f64>, String>>();
+    let fractal_type = match name.to_ascii_lowercase().as_str() {
+        "mandelbrot" if arguments.is_empty() => FractalType::Mandelbrot,
+        "julia" => match numbers()?.as_slice() {
+            [re, im] => FractalType::Julia { c: Complex::new(*re, *im) },
+            _ => return Err("usage: fractal julia <re> <im>".to_string()),
+        },
+        "multibrot" => match numbers()?.as_slice() {
+            [power] => FractalType::Multibrot { power: *power },
+            [] => FractalType::Multibrot { power: 3.0 },
+            _ => return Err("usage: fractal multibrot <power>".to_string()),
+        },
+        "newton" => {
+            let formula = if arguments.is_empty() { "z^3 - 1".to_string() } else { arguments.join(" ") };
+            let polynomial = Polynomial::parse(&formula).map_err(|error| error.to_string())?;
+            FractalType::Newton { polynomial }
+        },
+        "nebulabrot" => match numbers()?.as_slice() {
+            [] => FractalType::Nebulabrot { limits: DEFAULT_NEBULABROT_LIMITS },
+            [red, green, blue] => FractalType::Nebulabrot { limits: [*red as u32, *green as u32, *blue as u32] },
+            _ => return Err("usage: fractal nebulabrot <red> <green> <blue>".to_string()),
+        },
+        "custom" if !arguments.is_empty() => FractalType::Custom { equation: arguments.join(" ") },
+        _ => return Ok(None),
+    };
+    Ok(Some(fractal_type))
+}
+
+/// The fractal a name and its parameters pick: a built-in one from
+/// `fractal_type`, or else a registered one. Registered names may be written
+/// with '-' for spaces, as completion offers them, or as several words.
+pub fn resolve_fractal(generator: &FractalGenerator, name: &str, arguments: &[String]) -> Result<Arc<dyn Fractal>, String> {
+    let fractal = match fractal_type(name, arguments)? {
+        Some(fractal_type) => generator.resolve(&fractal_type),
+        None => {
+            let words: Vec<&str> = std::iter::once(name).chain(arguments.iter().map(String::as_str)).collect();
+            generator.registry.get(&name.replace('-', " ")).or_else(|| generator.registry.get(&words.join(" ")))
+        },
+    };
+    fractal.ok_or_else(|| format!("unknown fractal '{}'", name))
+}
+
+/// Completes the word under the cursor at the end of `line`. Returns the new
+/// line and, when the word is still ambiguous, the candidates it could become.
+pub fn complete(line: &str, fractal_names: &[String], palette_names: &[String]) -> (String, Vec<String>) {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let typing_new_word = line.is_empty() || line.ends_with(char::is_whitespace);
+    let (position, prefix) = if typing_new_word { (words.len(), "") } else { (words.len() - 1, words[words.len() - 1]) };
+
+    let candidates: Vec<String> = match (position, words.first().map(|word| word.to_ascii_lowercase())) {
+        (0, _) => COMMANDS.iter().map(|command| command.to_string()).collect(),
+        // Registered names may contain spaces; complete them as one word
+        (1, Some(command)) if command == "fractal" => fractal_names
+            .iter()
+            .map(|name| name.to_ascii_lowercase().replace(' ', "-"))
+            .chain(["julia", "newton", "custom"].iter().map(|name| name.to_string()))
+            .collect(),
+        (1, Some(command)) if command == "palette" => palette_names.to_vec(),
+        _ => Vec::new(),
+    };
+    let mut matches: Vec<String> = candidates
+        .into_iter()
+        .filter(|candidate| candidate.to_ascii_lowercase().starts_with(&prefix.to_ascii_lowercase()))
+        .collect();
+    matches.sort();
+    matches.dedup();
+
+    let stem = &line[..line.len() - prefix.len()];
+    match matches.as_slice() {
+        [] => (line.to_string(), Vec::new()),
+        [only] => (format!("{}{} ", stem, only), Vec::new()),
+        _ => {
+            let common = common_prefix(&matches);
+            let completed = if common.len() > prefix.len() { common } else { prefix.to_string() };
+            (format!("{}{}", stem, completed), matches)
+        },
+    }
+}
+
+fn common_prefix(words: &[String]) -> String {
+    let first = &words[0];
+    let length = words[1..].iter().fold(first.len(), |length, word| {
+        first.bytes().zip(word.bytes()).take(length).take_while(|(a, b)| a.eq_ignore_ascii_case(b)).count()
+    });
+    first[..length].to_string()
+}
+
+fn parse_number(text: &str) -> Result<BigFloat, String> {
+    text.parse().map_err(|error: crate::bigfloat::ParseBigFloatError| error.to_string())
+}
+
+fn parse_f64(text: &str) -> Result<f64, String> {
+    text.parse().map_err(|_| format!("invalid number '{}'", text))
+}
+
+/// Parses an image size written as `WIDTHxHEIGHT`, e.g. `200x60`
+pub fn parse_size(text: &str) -> Result<(usize, usize), String> {
+    let error = || {
+        format!("invalid size '{}' (expected WIDTHxHEIGHT, up to {} a side and {} in total)", text, MAX_SIZE, MAX_AREA)
+    };
+    let (width, height) = text.split_once(['x', 'X']).ok_or_else(error)?;
+    match (width.parse::<usize>(), height.parse::<usize>()) {
+        (Ok(width), Ok(height))
+            if (1..=MAX_SIZE).contains(&width) && (1..=MAX_SIZE).contains(&height) && width * height <= MAX_AREA =>
+        {
+            Ok((width, height))
+        }
+        _ => Err(error()),
+    }
+}
@@ -1,7 +1,17 @@
+use crate::bigfloat::BigFloat;
+use crate::command;
+use crate::orbit_trap::{OrbitTrap, TrapShape};
+use crate::keymap::Keymap;
+use crate::palette::{Interpolation, Palette, DEFAULT_PALETTE};
+use crate::renderer::CellMode;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::Path;
 
+/// Where the TUI looks for its config file; bookmarks are kept beside it
+pub const DEFAULT_CONFIG_PATH: &str = "config.json";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub display: DisplayConfig,
@@ -19,17 +29,65 @@ pub struct DisplayConfig {
     pub color_scheme: String,
     pub quality_mode: bool,
     pub super_sampling: bool,
+    /// Samples per terminal cell: one shade character, a 2x4 braille block or two half blocks
+    #[serde(default)]
+    pub cell_mode: CellMode,
+    /// User-defined gradients by name, selectable like the built-in ones
+    #[serde(default)]
+    pub palettes: BTreeMap<String, PaletteConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaletteConfig {
+    pub stops: Vec<ColorStopConfig>,
+    #[serde(default)]
+    pub interpolation: Interpolation,
+    /// Iterations per repeat of the gradient; without it the gradient spans
+    /// the iteration limit once
+    #[serde(default)]
+    pub cycle_length: Option<f64>,
+    /// `#rrggbb` colour of points inside the set; black if not given
+    #[serde(default)]
+    pub interior: Option<String>,
+    /// `#rrggbb` colour of points that escape straight away
+    #[serde(default)]
+    pub exterior: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColorStopConfig {
+    /// From 0 to 1
+    pub position: f64,
+    /// `#rrggbb`
+    pub color: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FractalConfig {
-    pub default_zoom: f64,
-    pub default_center_x: f64,
-    pub default_center_y: f64,
+    // Stored as decimal strings so deep locations keep every digit
+    pub default_zoom: BigFloat,
+    pub default_center_x: BigFloat,
+    pub default_center_y: BigFloat,
     pub default_max_iterations: u32,
     pub auto_generation_interval_ms: u64,
     pub zoom_step: f64,
     pub pan_step: f64,
+    // Older config files have no trap section
+    #[serde(default)]
+    pub orbit_trap: OrbitTrapConfig,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OrbitTrapConfig {
+    pub enabled: bool,
+    pub trap: OrbitTrap,
+}
+
+impl OrbitTrapConfig {
+    /// The trap the generator should use, if trap colouring is on
+    pub fn active_trap(&self) -> Option<OrbitTrap> {
+        self.enabled.then_some(self.trap)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -44,9 +102,26 @@ pub struct PerformanceConfig {
 pub struct ControlsConfig {
     pub zoom_in_key: String,
     pub zoom_out_key: String,
+    /// Multiplies `FractalConfig::pan_step`
     pub pan_speed: f64,
+    /// Power `FractalConfig::zoom_step` is raised to
     pub zoom_speed: f64,
     pub iteration_step: u32,
+    /// Key chords per action name, replacing that action's default keys
+    #[serde(default)]
+    pub keys: BTreeMap<String, Vec<String>>,
+}
+
+impl ControlsConfig {
+    /// Zoom factor of one zoom key press or scroll notch
+    pub fn zoom_factor(&self, zoom_step: f64) -> f64 {
+        zoom_step.powf(self.zoom_speed)
+    }
+
+    /// Pan distance of one arrow key press, in units of the zoom-1 view
+    pub fn pan_distance(&self, pan_step: f64) -> f64 {
+        pan_step * self.pan_speed
+    }
 }
 
 impl Default for Config {
@@ -60,15 +135,18 @@ impl Default for Config {
                 color_scheme: "default".to_string(),
                 quality_mode: true,
                 super_sampling: false,
+                cell_mode: CellMode::Text,
+                palettes: BTreeMap::new(),
             },
             fractal: FractalConfig {
-                default_zoom: 1.0,
-                default_center_x: -0.5,
-                default_center_y: 0.0,
+                default_zoom: BigFloat::from(1.0),
+                default_center_x: BigFloat::from(-0.5),
+                default_center_y: BigFloat::from(0.0),
                 default_max_iterations: 256,
                 auto_generation_interval_ms: 2000,
                 zoom_step: 1.5,
                 pan_step: 0.1,
+                orbit_trap: OrbitTrapConfig::default(),
             },
             performance: PerformanceConfig {
                 use_parallel_processing: true,
@@ -82,6 +160,7 @@ impl Default for Config {
                 pan_speed: 1.0,
                 zoom_speed: 1.0,
                 iteration_step: 10,
+                keys: BTreeMap::new(),
             },
         }
     }
@@ -110,12 +189,42 @@ impl Config {
             return Err("Display dimensions must be greater than 0".to_string());
         }
 
+        let palettes = Palette::from_config(&self.display).map_err(|error| format!("Invalid {}", error))?;
+        let scheme = &self.display.color_scheme;
+        if !scheme.eq_ignore_ascii_case(DEFAULT_PALETTE) && !palettes.iter().any(|palette| palette.name.eq_ignore_ascii_case(scheme)) {
+            return Err(format!("Unknown colour scheme '{}'", scheme));
+        }
+
         if self.fractal.default_max_iterations == 0 {
             return Err("Max iterations must be greater than 0".to_string());
         }
 
-        if self.fractal.zoom_step <= 0.0 {
-            return Err("Zoom step must be positive".to_string());
+        if self.fractal.default_zoom <= 0.0 {
+            return Err("Default zoom must be positive".to_string());
+        }
+
+        if self.fractal.zoom_step <= 1.0 {
+            return Err("Zoom step must be greater than 1".to_string());
+        }
+
+        if self.fractal.pan_step <= 0.0 {
+            return Err("Pan step must be positive".to_string());
+        }
+
+        if self.controls.zoom_speed <= 0.0 || self.controls.pan_speed <= 0.0 {
+            return Err("Zoom and pan speeds must be positive".to_string());
+        }
+
+        if self.controls.iteration_step == 0 || self.controls.iteration_step > command::MAX_ITERATIONS {
+            return Err(format!("Iteration step must be between 1 and {}", command::MAX_ITERATIONS));
+        }
+
+        Keymap::from_config(&self.controls).map_err(|error| format!("Invalid key bindings: {}", error))?;
+
+        if let TrapShape::Circle { radius } = self.fractal.orbit_trap.trap.shape {
+            if radius <= 0.0 {
+                return Err("Orbit trap radius must be positive".to_string());
+            }
         }
 
         if self.fractal.auto_generation_interval_ms == 0 {
@@ -136,11 +245,11 @@ impl Config {
         (self.display.default_width, self.display.default_height)
     }
 
-    pub fn get_default_fractal_params(&self) -> (f64, f64, f64, u32) {
+    pub fn get_default_fractal_params(&self) -> (BigFloat, BigFloat, BigFloat, u32) {
         (
-            self.fractal.default_zoom,
-            self.fractal.default_center_x,
-            self.fractal.default_center_y,
+            self.fractal.default_zoom.clone(),
+            self.fractal.default_center_x.clone(),
+            self.fractal.default_center_y.clone(),
             self.fractal.default_max_iterations,
         )
     }
@@ -166,7 +275,7 @@ impl Config {
         self.display.use_unicode = use_unicode;
     }
 
-    pub fn set_fractal_defaults(&mut self, zoom: f64, center_x: f64, center_y: f64, max_iterations: u32) {
+    pub fn set_fractal_defaults(&mut self, zoom: BigFloat, center_x: BigFloat, center_y: BigFloat, max_iterations: u32) {
         self.fractal.default_zoom = zoom;
         self.fractal.default_center_x = center_x;
         self.fractal.default_center_y = center_y;
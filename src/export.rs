@@ -0,0 +1,95 @@
+use crate::fractal::FractalSample;
+use crate::renderer::{color_to_rgb, TerminalRenderer};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+// Writing rendered fractals out of the terminal: plain text, text with ANSI
+// colour escapes, or a PNG with one pixel per sample in the renderer's colours.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Text,
+    Ansi,
+    Png,
+}
+
+impl ExportFormat {
+    /// Picks the format from a file extension; anything unrecognised is plain text
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Self {
+        let extension = path.as_ref().extension().and_then(|extension| extension.to_str()).unwrap_or("");
+        match extension.to_ascii_lowercase().as_str() {
+            "png" => ExportFormat::Png,
+            "ans" | "ansi" => ExportFormat::Ansi,
+            _ => ExportFormat::Text,
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text.to_ascii_lowercase().as_str() {
+            "txt" | "text" => Ok(ExportFormat::Text),
+            "ansi" => Ok(ExportFormat::Ansi),
+            "png" => Ok(ExportFormat::Png),
+            _ => Err(format!("unknown format '{}' (expected txt, ansi or png)", text)),
+        }
+    }
+}
+
+impl fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExportFormat::Text => write!(f, "txt"),
+            ExportFormat::Ansi => write!(f, "ansi"),
+            ExportFormat::Png => write!(f, "png"),
+        }
+    }
+}
+
+/// Encodes samples in the given format
+pub fn encode(renderer: &TerminalRenderer, samples: &[Vec<FractalSample>], format: ExportFormat) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    match format {
+        ExportFormat::Text => Ok(renderer.render_samples_to_string(samples).into_bytes()),
+        ExportFormat::Ansi => Ok(renderer.render_samples_to_ansi(samples).into_bytes()),
+        ExportFormat::Png => encode_png(renderer, samples),
+    }
+}
+
+/// Writes samples to a file in the format its extension names, and returns that format
+pub fn write_file<P: AsRef<Path>>(renderer: &TerminalRenderer, samples: &[Vec<FractalSample>], path: P) -> Result<ExportFormat, Box<dyn std::error::Error>> {
+    let format = ExportFormat::from_path(&path);
+    let mut writer = BufWriter::new(File::create(path)?);
+    writer.write_all(&encode(renderer, samples, format)?)?;
+    writer.flush()?;
+    Ok(format)
+}
+
+fn encode_png(renderer: &TerminalRenderer, samples: &[Vec<FractalSample>]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let height = samples.len();
+    let width = samples.first().map_or(0, |row| row.len());
+    if width == 0 || height == 0 {
+        return Err("nothing to export".into());
+    }
+
+    let mut pixels = Vec::with_capacity(width * height * 3);
+    for row in samples {
+        for sample in row {
+            let (character, color) = renderer.sample_to_char_and_color(sample);
+            // A blank cell shows the background whatever its colour
+            let (red, green, blue) = if character == ' ' { (0, 0, 0) } else { color_to_rgb(color) };
+            pixels.extend_from_slice(&[red, green, blue]);
+        }
+    }
+
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(&pixels)?;
+    Ok(bytes)
+}
@@ -0,0 +1,430 @@
+use num_complex::Complex;
+use std::fmt;
+
+// Formula language for custom fractals, e.g. "z^3 - 0.5*z + c" or "sin(z) * c".
+// Grammar (lowest to highest precedence):
+//   expr    := term (('+' | '-') term)*
+//   term    := unary (('*' | '/') unary | primary)*   -- juxtaposition is multiplication
+//   unary   := ('-' | '+') unary | power
+//   power   := primary ('^' unary)?                  -- right associative
+//   primary := number | number 'i' | ident | ident '(' expr ')' | '(' expr ')'
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    Number(f64),
+    Imaginary(f64),
+    Ident(String),
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Caret,
+    LParen,
+    RParen,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpannedToken {
+    pub token: Token,
+    /// Character offset of the first character of the token
+    pub position: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Function {
+    Sin,
+    Cos,
+    Tan,
+    Sinh,
+    Cosh,
+    Tanh,
+    Exp,
+    Log,
+    Sqrt,
+    Abs,
+    Conj,
+    Re,
+    Im,
+}
+
+impl Function {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "sin" => Some(Function::Sin),
+            "cos" => Some(Function::Cos),
+            "tan" => Some(Function::Tan),
+            "sinh" => Some(Function::Sinh),
+            "cosh" => Some(Function::Cosh),
+            "tanh" => Some(Function::Tanh),
+            "exp" => Some(Function::Exp),
+            "log" | "ln" => Some(Function::Log),
+            "sqrt" => Some(Function::Sqrt),
+            "abs" => Some(Function::Abs),
+            "conj" => Some(Function::Conj),
+            "re" => Some(Function::Re),
+            "im" => Some(Function::Im),
+            _ => None,
+        }
+    }
+
+    pub fn apply(self, value: Complex<f64>) -> Complex<f64> {
+        match self {
+            Function::Sin => value.sin(),
+            Function::Cos => value.cos(),
+            Function::Tan => value.tan(),
+            Function::Sinh => value.sinh(),
+            Function::Cosh => value.cosh(),
+            Function::Tanh => value.tanh(),
+            Function::Exp => value.exp(),
+            Function::Log => value.ln(),
+            Function::Sqrt => value.sqrt(),
+            Function::Abs => Complex::new(value.norm(), 0.0),
+            Function::Conj => value.conj(),
+            Function::Re => Complex::new(value.re, 0.0),
+            Function::Im => Complex::new(value.im, 0.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Constant(Complex<f64>),
+    Z,
+    C,
+    Neg(Box<Expr>),
+    Add(Box<Expr>, Box<Expr>),
+    Sub(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+    Div(Box<Expr>, Box<Expr>),
+    // Exponents known at parse time are classified so evaluation can use the cheapest power
+    PowInt(Box<Expr>, i32),
+    PowReal(Box<Expr>, f64),
+    Pow(Box<Expr>, Box<Expr>),
+    Call(Function, Box<Expr>),
+}
+
+impl Expr {
+    pub fn eval(&self, z: Complex<f64>, c: Complex<f64>) -> Complex<f64> {
+        match self {
+            Expr::Constant(value) => *value,
+            Expr::Z => z,
+            Expr::C => c,
+            Expr::Neg(inner) => -inner.eval(z, c),
+            Expr::Add(a, b) => a.eval(z, c) + b.eval(z, c),
+            Expr::Sub(a, b) => a.eval(z, c) - b.eval(z, c),
+            Expr::Mul(a, b) => a.eval(z, c) * b.eval(z, c),
+            Expr::Div(a, b) => a.eval(z, c) / b.eval(z, c),
+            Expr::PowInt(base, exponent) => base.eval(z, c).powi(*exponent),
+            Expr::PowReal(base, exponent) => base.eval(z, c).powf(*exponent),
+            Expr::Pow(base, exponent) => pow(base.eval(z, c), exponent.eval(z, c)),
+            Expr::Call(function, argument) => function.apply(argument.eval(z, c)),
+        }
+    }
+
+    pub fn is_constant(&self) -> bool {
+        match self {
+            Expr::Constant(_) => true,
+            Expr::Z | Expr::C => false,
+            Expr::Neg(inner) | Expr::PowInt(inner, _) | Expr::PowReal(inner, _) | Expr::Call(_, inner) => {
+                inner.is_constant()
+            }
+            Expr::Add(a, b) | Expr::Sub(a, b) | Expr::Mul(a, b) | Expr::Div(a, b) | Expr::Pow(a, b) => {
+                a.is_constant() && b.is_constant()
+            }
+        }
+    }
+}
+
+fn pow(base: Complex<f64>, exponent: Complex<f64>) -> Complex<f64> {
+    if exponent.im == 0.0 {
+        if exponent.re.fract() == 0.0 && exponent.re.abs() <= i32::MAX as f64 {
+            base.powi(exponent.re as i32)
+        } else {
+            base.powf(exponent.re)
+        }
+    } else {
+        base.powc(exponent)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    /// Character offset into the source where the error was detected
+    pub position: usize,
+}
+
+impl ParseError {
+    fn new(message: impl Into<String>, position: usize) -> Self {
+        Self { message: message.into(), position }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at column {}", self.message, self.position + 1)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+pub fn tokenize(input: &str) -> Result<Vec<SpannedToken>, ParseError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+
+    while pos < chars.len() {
+        let ch = chars[pos];
+        let start = pos;
+
+        if ch.is_whitespace() {
+            pos += 1;
+            continue;
+        }
+
+        if ch.is_ascii_digit() || ch == '.' {
+            while pos < chars.len() && (chars[pos].is_ascii_digit() || chars[pos] == '.') {
+                pos += 1;
+            }
+            // Scientific notation: 1e-3, 2.5E4
+            if pos < chars.len() && (chars[pos] == 'e' || chars[pos] == 'E') {
+                let mut lookahead = pos + 1;
+                if lookahead < chars.len() && (chars[lookahead] == '+' || chars[lookahead] == '-') {
+                    lookahead += 1;
+                }
+                if lookahead < chars.len() && chars[lookahead].is_ascii_digit() {
+                    pos = lookahead;
+                    while pos < chars.len() && chars[pos].is_ascii_digit() {
+                        pos += 1;
+                    }
+                }
+            }
+
+            let text: String = chars[start..pos].iter().collect();
+            let value = text
+                .parse::<f64>()
+                .map_err(|_| ParseError::new(format!("invalid number '{}'", text), start))?;
+
+            // A number immediately followed by a lone 'i' is an imaginary literal, e.g. 0.5i
+            let imaginary = pos < chars.len()
+                && chars[pos] == 'i'
+                && !chars.get(pos + 1).is_some_and(|next| next.is_alphanumeric() || *next == '_');
+            if imaginary {
+                pos += 1;
+                tokens.push(SpannedToken { token: Token::Imaginary(value), position: start });
+            } else {
+                tokens.push(SpannedToken { token: Token::Number(value), position: start });
+            }
+            continue;
+        }
+
+        if ch.is_alphabetic() || ch == '_' {
+            while pos < chars.len() && (chars[pos].is_alphanumeric() || chars[pos] == '_') {
+                pos += 1;
+            }
+            let name: String = chars[start..pos].iter().collect::<String>().to_lowercase();
+            tokens.push(SpannedToken { token: Token::Ident(name), position: start });
+            continue;
+        }
+
+        let token = match ch {
+            '+' => Token::Plus,
+            '-' => Token::Minus,
+            '*' => Token::Star,
+            '/' => Token::Slash,
+            '^' => Token::Caret,
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            _ => return Err(ParseError::new(format!("unexpected character '{}'", ch), start)),
+        };
+        tokens.push(SpannedToken { token, position: start });
+        pos += 1;
+    }
+
+    Ok(tokens)
+}
+
+pub fn parse(input: &str) -> Result<Expr, ParseError> {
+    let tokens = tokenize(input)?;
+    let mut parser = Parser { tokens, index: 0, end: input.chars().count() };
+
+    if parser.tokens.is_empty() {
+        return Err(ParseError::new("empty formula", 0));
+    }
+
+    let expr = parser.parse_expr()?;
+    if let Some(token) = parser.peek() {
+        return Err(ParseError::new(
+            format!("unexpected {}", describe(&token.token)),
+            token.position,
+        ));
+    }
+    Ok(expr)
+}
+
+struct Parser {
+    tokens: Vec<SpannedToken>,
+    index: usize,
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&SpannedToken> {
+        self.tokens.get(self.index)
+    }
+
+    fn next(&mut self) -> Option<SpannedToken> {
+        let token = self.tokens.get(self.index).cloned();
+        self.index += 1;
+        token
+    }
+
+    fn position(&self) -> usize {
+        self.peek().map(|token| token.position).unwrap_or(self.end)
+    }
+
+    fn expect(&mut self, expected: Token, what: &str) -> Result<(), ParseError> {
+        match self.peek() {
+            Some(token) if token.token == expected => {
+                self.index += 1;
+                Ok(())
+            }
+            Some(token) => Err(ParseError::new(
+                format!("expected {} but found {}", what, describe(&token.token)),
+                token.position,
+            )),
+            None => Err(ParseError::new(format!("expected {}", what), self.end)),
+        }
+    }
+
+    fn parse_expr(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.parse_term()?;
+        while let Some(token) = self.peek() {
+            match token.token {
+                Token::Plus => {
+                    self.index += 1;
+                    left = Expr::Add(Box::new(left), Box::new(self.parse_term()?));
+                }
+                Token::Minus => {
+                    self.index += 1;
+                    left = Expr::Sub(Box::new(left), Box::new(self.parse_term()?));
+                }
+                _ => break,
+            }
+        }
+        Ok(left)
+    }
+
+    fn parse_term(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.parse_unary()?;
+        while let Some(token) = self.peek() {
+            match token.token {
+                Token::Star => {
+                    self.index += 1;
+                    left = Expr::Mul(Box::new(left), Box::new(self.parse_unary()?));
+                }
+                Token::Slash => {
+                    self.index += 1;
+                    left = Expr::Div(Box::new(left), Box::new(self.parse_unary()?));
+                }
+                // Implicit multiplication: "2z", "3(z + 1)", "z sin(z)"
+                Token::Number(_) | Token::Imaginary(_) | Token::Ident(_) | Token::LParen => {
+                    left = Expr::Mul(Box::new(left), Box::new(self.parse_power()?));
+                }
+                _ => break,
+            }
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, ParseError> {
+        match self.peek().map(|token| &token.token) {
+            Some(Token::Minus) => {
+                self.index += 1;
+                let inner = self.parse_unary()?;
+                Ok(match inner {
+                    Expr::Constant(value) => Expr::Constant(-value),
+                    other => Expr::Neg(Box::new(other)),
+                })
+            }
+            Some(Token::Plus) => {
+                self.index += 1;
+                self.parse_unary()
+            }
+            _ => self.parse_power(),
+        }
+    }
+
+    fn parse_power(&mut self) -> Result<Expr, ParseError> {
+        let base = self.parse_primary()?;
+        if !matches!(self.peek().map(|token| &token.token), Some(Token::Caret)) {
+            return Ok(base);
+        }
+        self.index += 1;
+        let exponent = self.parse_unary()?;
+
+        if !exponent.is_constant() {
+            return Ok(Expr::Pow(Box::new(base), Box::new(exponent)));
+        }
+
+        let value = exponent.eval(Complex::new(0.0, 0.0), Complex::new(0.0, 0.0));
+        Ok(if value.im != 0.0 {
+            Expr::Pow(Box::new(base), Box::new(Expr::Constant(value)))
+        } else if value.re.fract() == 0.0 && value.re.abs() <= i32::MAX as f64 {
+            Expr::PowInt(Box::new(base), value.re as i32)
+        } else {
+            Expr::PowReal(Box::new(base), value.re)
+        })
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, ParseError> {
+        let position = self.position();
+        let token = match self.next() {
+            Some(token) => token,
+            None => return Err(ParseError::new("unexpected end of formula", self.end)),
+        };
+
+        match token.token {
+            Token::Number(value) => Ok(Expr::Constant(Complex::new(value, 0.0))),
+            Token::Imaginary(value) => Ok(Expr::Constant(Complex::new(0.0, value))),
+            Token::LParen => {
+                let inner = self.parse_expr()?;
+                self.expect(Token::RParen, "')'")?;
+                Ok(inner)
+            }
+            Token::Ident(name) => {
+                if let Some(function) = Function::from_name(&name) {
+                    self.expect(Token::LParen, &format!("'(' after '{}'", name))?;
+                    let argument = self.parse_expr()?;
+                    self.expect(Token::RParen, "')'")?;
+                    return Ok(Expr::Call(function, Box::new(argument)));
+                }
+
+                match name.as_str() {
+                    "z" => Ok(Expr::Z),
+                    "c" => Ok(Expr::C),
+                    "i" => Ok(Expr::Constant(Complex::new(0.0, 1.0))),
+                    "pi" => Ok(Expr::Constant(Complex::new(std::f64::consts::PI, 0.0))),
+                    "e" => Ok(Expr::Constant(Complex::new(std::f64::consts::E, 0.0))),
+                    _ => Err(ParseError::new(format!("unknown identifier '{}'", name), position)),
+                }
+            }
+            other => Err(ParseError::new(format!("unexpected {}", describe(&other)), position)),
+        }
+    }
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Number(value) => format!("number {}", value),
+        Token::Imaginary(value) => format!("imaginary number {}i", value),
+        Token::Ident(name) => format!("'{}'", name),
+        Token::Plus => "'+'".to_string(),
+        Token::Minus => "'-'".to_string(),
+        Token::Star => "'*'".to_string(),
+        Token::Slash => "'/'".to_string(),
+        Token::Caret => "'^'".to_string(),
+        Token::LParen => "'('".to_string(),
+        Token::RParen => "')'".to_string(),
+    }
+}
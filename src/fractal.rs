@@ -1,7 +1,17 @@
+use crate::bigfloat::BigFloat;
+use crate::config::Config;
+use crate::perturbation::{self, PerturbationKind};
+use crate::orbit_trap::OrbitTrap;
+use crate::polynomial::Polynomial;
+use crate::precision::{DoubleDouble, KernelComplex, KernelFloat, Precision};
+use crate::registry::{Fractal, FractalRegistry, PlanePoint};
 use num_complex::Complex;
 use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum FractalType {
     Mandelbrot,
     Julia { c: Complex<f64> },
@@ -9,8 +19,61 @@ pub enum FractalType {
     Tricorn,
     Multibrot { power: f64 },
     Custom { equation: String },
+    Newton { polynomial: Polynomial },
+    /// Orbit density of escaping points, traced up to `max_iterations`
+    Buddhabrot,
+    /// Buddhabrot with separate iteration limits for the red, green and blue channels
+    Nebulabrot { limits: [u32; 3] },
+    /// A fractal added to the generator's registry, looked up by name
+    Registered { name: String },
+    /// Julia set of a parameter-plane fractal: its iteration with `c` fixed and
+    /// each pixel as the starting z
+    JuliaOf { parameter_plane: Box<FractalType>, c: Complex<f64> },
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FractalSample {
+    pub iterations: u32,
+    /// Continuous iteration count for points that escaped, when smooth colouring is enabled
+    pub smooth: Option<f64>,
+    /// Index of the root a Newton pixel converged to; `iterations` then counts the steps it took
+    pub root: Option<usize>,
+    /// Explicit RGB colour for density renders, used instead of the palette
+    pub color: Option<(u8, u8, u8)>,
+    /// Closest approach of the orbit to the orbit trap, when one is set
+    pub trap_distance: Option<f64>,
+    /// Estimated distance from an escaped point to the set boundary, in pixels
+    pub distance: Option<f64>,
+    /// Length of the cycle an interior point's orbit was found to settle into
+    pub period: Option<u32>,
+}
+
+impl FractalSample {
+    pub fn new(iterations: u32) -> Self {
+        Self { iterations, smooth: None, root: None, color: None, trap_distance: None, distance: None, period: None }
+    }
+
+    /// The value palettes should map: the smooth count when available, otherwise the integer count
+    pub fn value(&self) -> f64 {
+        self.smooth.unwrap_or(self.iterations as f64)
+    }
+}
+
+impl From<u32> for FractalSample {
+    fn from(iterations: u32) -> Self {
+        Self::new(iterations)
+    }
+}
+
+// A float type stops resolving pixels once neighbouring pixels are only this many
+// of its ulps of the center apart; below that the next wider type takes over,
+// and past double-double the perturbation engine does
+const PRECISION_THRESHOLD_ULPS: f64 = 1024.0;
+
+// Escape radius used when smooth colouring is on. A large radius makes the
+// fractional part of the normalized iteration count continuous across bands.
+const SMOOTH_BAILOUT: f64 = 256.0;
+
 #[derive(Debug, Clone)]
 pub struct FractalParams {
     pub fractal_type: FractalType,
@@ -20,6 +83,69 @@ pub struct FractalParams {
     pub center_x: f64,
     pub center_y: f64,
     pub max_iterations: u32,
+    /// Exact view center; `center_x`/`center_y` hold its f64 rounding for the plain kernels
+    pub precise_center: Option<(BigFloat, BigFloat)>,
+}
+
+impl FractalParams {
+    /// The view center at the given precision, falling back to the f64 center
+    pub fn center_with_precision(&self, precision: u32) -> (BigFloat, BigFloat) {
+        match &self.precise_center {
+            Some((x, y)) => (x.with_precision(precision), y.with_precision(precision)),
+            None => (
+                BigFloat::from_f64(self.center_x, precision),
+                BigFloat::from_f64(self.center_y, precision),
+            ),
+        }
+    }
+}
+
+// Pixel coordinates are the view center plus a small f64 offset, so a wide
+// float type keeps the low bits of the center that f64 would round away
+struct PixelGrid<T> {
+    center: KernelComplex<T>,
+    half_extent: f64,
+    x_scale: f64,
+    y_scale: f64,
+}
+
+impl<T: KernelFloat> PixelGrid<T> {
+    fn new(params: &FractalParams) -> Self {
+        let center = match &params.precise_center {
+            Some((x, y)) => KernelComplex::new(T::from_bigfloat(x), T::from_bigfloat(y)),
+            None => KernelComplex::new(T::from_f64(params.center_x), T::from_f64(params.center_y)),
+        };
+        let half_extent = 2.0 / params.zoom;
+
+        Self {
+            center,
+            half_extent,
+            x_scale: 2.0 * half_extent / params.width as f64,
+            y_scale: 2.0 * half_extent / params.height as f64,
+        }
+    }
+
+    fn point(&self, x: usize, y: usize) -> KernelComplex<T> {
+        KernelComplex::new(
+            self.center.re + T::from_f64(-self.half_extent + x as f64 * self.x_scale),
+            self.center.im + T::from_f64(-self.half_extent + y as f64 * self.y_scale),
+        )
+    }
+}
+
+impl Default for FractalParams {
+    fn default() -> Self {
+        Self {
+            fractal_type: FractalType::Mandelbrot,
+            width: 80,
+            height: 40,
+            zoom: 1.0,
+            center_x: -0.5,
+            center_y: 0.0,
+            max_iterations: 256,
+            precise_center: None,
+        }
+    }
 }
 
 pub struct FractalGenerator {
@@ -27,6 +153,20 @@ pub struct FractalGenerator {
     pub performance_mode: bool,
     pub quality_mode: bool,
     pub super_sampling: bool,
+    pub smooth_coloring: bool,
+    pub deep_zoom: bool,
+    /// Narrowest float type the kernels run in; widened automatically as the zoom deepens
+    pub precision: Precision,
+    /// When set, the escape-time kernels also record each orbit's distance to this trap
+    pub orbit_trap: Option<OrbitTrap>,
+    /// Track dz/dc in the Mandelbrot, Julia and Multibrot kernels for boundary distance estimates
+    pub distance_estimation: bool,
+    /// Skip iterating Mandelbrot points inside the main cardioid and period-2 bulb
+    pub cardioid_check: bool,
+    /// Stop iterating once an orbit is caught repeating itself (Brent's cycle detection)
+    pub periodicity_check: bool,
+    /// Fractals every `FractalType` is resolved through; starts out with the built-ins
+    pub registry: FractalRegistry,
 }
 
 impl FractalGenerator {
@@ -36,9 +176,26 @@ impl FractalGenerator {
             performance_mode: false,
             quality_mode: true,
             super_sampling: false,
+            smooth_coloring: true,
+            deep_zoom: true,
+            precision: Precision::F64,
+            orbit_trap: None,
+            distance_estimation: false,
+            cardioid_check: true,
+            periodicity_check: true,
+            registry: FractalRegistry::with_builtins(),
         }
     }
 
+    /// A generator with the quality settings and orbit trap from a config
+    pub fn from_config(config: &Config) -> Self {
+        let mut generator = Self::new();
+        generator.set_quality_mode(config.display.quality_mode);
+        generator.set_super_sampling(config.display.super_sampling);
+        generator.set_orbit_trap(config.fractal.orbit_trap.active_trap());
+        generator
+    }
+
     pub fn set_performance_mode(&mut self, enabled: bool) {
         self.performance_mode = enabled;
     }
@@ -55,345 +212,333 @@ impl FractalGenerator {
         self.super_sampling = enabled;
     }
 
-    pub fn generate(&self, params: &FractalParams) -> Vec<Vec<u32>> {
-        if self.super_sampling {
-            self.generate_with_super_sampling(params)
-        } else {
-            self.generate_standard(params)
-        }
+    pub fn set_smooth_coloring(&mut self, enabled: bool) {
+        self.smooth_coloring = enabled;
     }
 
-    fn generate_standard(&self, params: &FractalParams) -> Vec<Vec<u32>> {
-        match &params.fractal_type {
-            FractalType::Mandelbrot => self.generate_mandelbrot(params),
-            FractalType::Julia { c } => self.generate_julia(params, *c),
-            FractalType::BurningShip => self.generate_burning_ship(params),
-            FractalType::Tricorn => self.generate_tricorn(params),
-            FractalType::Multibrot { power } => self.generate_multibrot(params, *power),
-            FractalType::Custom { equation: _ } => {
-                // For now, fallback to Mandelbrot for custom equations
-                // TODO: Implement equation parser
-                self.generate_mandelbrot(params)
-            }
-        }
+    pub fn set_deep_zoom(&mut self, enabled: bool) {
+        self.deep_zoom = enabled;
     }
 
-    fn generate_with_super_sampling(&self, params: &FractalParams) -> Vec<Vec<u32>> {
-        // Generate at 2x resolution then downsample for better quality
-        let super_params = FractalParams {
-            width: params.width * 2,
-            height: params.height * 2,
-            ..params.clone()
-        };
+    pub fn set_precision(&mut self, precision: Precision) {
+        self.precision = precision;
+    }
 
-        let super_data = self.generate_standard(&super_params);
-        self.downsample_fractal(super_data, params.width, params.height)
+    pub fn set_orbit_trap(&mut self, orbit_trap: Option<OrbitTrap>) {
+        self.orbit_trap = orbit_trap;
     }
 
-    fn downsample_fractal(&self, data: Vec<Vec<u32>>, target_width: usize, target_height: usize) -> Vec<Vec<u32>> {
-        let mut result = vec![vec![0u32; target_width]; target_height];
+    pub fn set_distance_estimation(&mut self, enabled: bool) {
+        self.distance_estimation = enabled;
+    }
 
-        for y in 0..target_height {
-            for x in 0..target_width {
-                // Sample 2x2 area and take average
-                let src_x = x * 2;
-                let src_y = y * 2;
+    pub fn set_cardioid_check(&mut self, enabled: bool) {
+        self.cardioid_check = enabled;
+    }
 
-                let mut sum = 0u32;
-                let mut count = 0u32;
+    pub fn set_periodicity_check(&mut self, enabled: bool) {
+        self.periodicity_check = enabled;
+    }
 
-                for dy in 0..2 {
-                    for dx in 0..2 {
-                        let sample_x = src_x + dx;
-                        let sample_y = src_y + dy;
+    /// Makes a fractal available as `FractalType::Registered { name }` and in the TUI
+    pub fn register(&mut self, fractal: Arc<dyn Fractal>) {
+        self.registry.register(fractal);
+    }
 
-                        if sample_y < data.len() && sample_x < data[sample_y].len() {
-                            sum += data[sample_y][sample_x];
-                            count += 1;
-                        }
-                    }
-                }
+    /// The fractal a `FractalType` describes, or `None` if it names nothing registered
+    pub fn resolve(&self, fractal_type: &FractalType) -> Option<Arc<dyn Fractal>> {
+        self.registry.resolve(fractal_type)
+    }
+
+    // Distance between neighbouring pixels measured in ulps of the view center at the given precision
+    pub fn pixel_spacing_in_ulps(params: &FractalParams, precision: Precision) -> f64 {
+        let spacing = 4.0 / params.zoom / params.width.max(params.height).max(1) as f64;
+        let magnitude = params.center_x.abs().max(params.center_y.abs());
+        if magnitude == 0.0 {
+            return f64::INFINITY;
+        }
+        spacing / (magnitude * precision.epsilon())
+    }
+
+    pub fn uses_perturbation(&self, params: &FractalParams) -> bool {
+        // Traps need every orbit point, which series approximation skips over
+        self.deep_zoom
+            && self.orbit_trap.is_none()
+            && self.perturbation_kind(params).is_some()
+            && Self::pixel_spacing_in_ulps(params, Precision::DoubleDouble) < PRECISION_THRESHOLD_ULPS
+    }
 
-                result[y][x] = if count > 0 { sum / count } else { 0 };
+    /// The precision the direct kernels will run in: the configured one, widened
+    /// until it still resolves neighbouring pixels at the current center
+    pub fn effective_precision(&self, params: &FractalParams) -> Precision {
+        if !self.resolve(&params.fractal_type).is_some_and(|fractal| fractal.supports_precision()) {
+            return Precision::F64;
+        }
+
+        let mut precision = self.precision;
+        while Self::pixel_spacing_in_ulps(params, precision) < PRECISION_THRESHOLD_ULPS {
+            match precision.wider() {
+                Some(wider) => precision = wider,
+                None => break,
             }
         }
+        precision
+    }
 
-        result
+    pub fn generate(&self, params: &FractalParams) -> Vec<Vec<u32>> {
+        self.generate_samples(params)
+            .into_iter()
+            .map(|row| row.into_iter().map(|sample| sample.iterations).collect())
+            .collect()
     }
 
-    fn generate_mandelbrot(&self, params: &FractalParams) -> Vec<Vec<u32>> {
-        let width = params.width;
-        let height = params.height;
-        let zoom = params.zoom;
-        let center_x = params.center_x;
-        let center_y = params.center_y;
-        let max_iterations = if self.performance_mode {
-            (params.max_iterations / 2).max(20)
-        } else if self.quality_mode {
-            // In quality mode, use higher iterations for better detail
-            (params.max_iterations * 3 / 2).min(512)
+    pub fn generate_samples(&self, params: &FractalParams) -> Vec<Vec<FractalSample>> {
+        let mut samples = if self.super_sampling {
+            self.generate_with_super_sampling(params)
         } else {
-            params.max_iterations
+            self.generate_standard(params)
         };
 
-        // Calculate the bounds of the complex plane to render
-        let x_min = center_x - 2.0 / zoom;
-        let x_max = center_x + 2.0 / zoom;
-        let y_min = center_y - 2.0 / zoom;
-        let y_max = center_y + 2.0 / zoom;
+        // Kernels estimate distances in the complex plane; report them in output pixels
+        if self.distance_estimation {
+            let pixel_size = 4.0 / params.zoom / params.width.max(params.height).max(1) as f64;
+            for sample in samples.iter_mut().flatten() {
+                if let Some(distance) = sample.distance.as_mut() {
+                    *distance /= pixel_size;
+                }
+            }
+        }
+        samples
+    }
 
-        let x_scale = (x_max - x_min) / width as f64;
-        let y_scale = (y_max - y_min) / height as f64;
+    fn perturbation_kind(&self, params: &FractalParams) -> Option<PerturbationKind> {
+        self.resolve(&params.fractal_type)?.perturbation_kind()
+    }
 
-        // Use adaptive sampling for better performance at high zoom levels
-        if self.use_adaptive_sampling && zoom > 10.0 {
-            self.generate_mandelbrot_adaptive(width, height, x_min, x_max, y_min, y_max, max_iterations)
-        } else {
-            // Standard generation using parallel processing
-            (0..height)
-                .into_par_iter()
-                .map(|y| {
-                    (0..width)
-                        .map(|x| {
-                            let real = x_min + x as f64 * x_scale;
-                            let imag = y_min + y as f64 * y_scale;
-                            let c = Complex::new(real, imag);
-                            self.mandelbrot_iterations(c, max_iterations)
-                        })
-                        .collect()
-                })
-                .collect()
+    fn generate_standard(&self, params: &FractalParams) -> Vec<Vec<FractalSample>> {
+        // Names nothing is registered under, and formulas that no longer parse,
+        // render as an empty (fully escaped) image
+        let Some(fractal) = self.resolve(&params.fractal_type) else {
+            return vec![vec![FractalSample::default(); params.width]; params.height];
+        };
+
+        if self.uses_perturbation(params) {
+            // Deep views need every iteration asked for, so the quality and
+            // performance heuristics don't apply here
+            if let Some(kind) = fractal.perturbation_kind() {
+                return perturbation::generate(self, params, kind, params.max_iterations);
+            }
+        }
+
+        if let Some(samples) = fractal.render(self, params) {
+            return samples;
         }
+        let max_iterations = params.max_iterations;
+        self.generate_points(params, 1, |point| fractal.iterate(self, point, max_iterations))
     }
 
-    fn generate_mandelbrot_adaptive(&self, width: usize, height: usize, x_min: f64, x_max: f64, y_min: f64, y_max: f64, max_iterations: u32) -> Vec<Vec<u32>> {
-        let x_scale = (x_max - x_min) / width as f64;
-        let y_scale = (y_max - y_min) / height as f64;
+    /// Runs `kernel` on each pixel's point in the effective precision. With a
+    /// `sample_step` above 1 only every step-th pixel in each direction is
+    /// computed and its neighbours copy it, trading detail for speed.
+    pub fn generate_points<F>(&self, params: &FractalParams, sample_step: usize, kernel: F) -> Vec<Vec<FractalSample>>
+    where
+        F: Fn(PlanePoint) -> FractalSample + Sync,
+    {
+        match self.effective_precision(params) {
+            Precision::F32 => self.generate_pixels::<f32, _>(params, sample_step, |c| kernel(c.into())),
+            Precision::F64 => self.generate_pixels::<f64, _>(params, sample_step, |c| kernel(c.into())),
+            Precision::DoubleDouble => self.generate_pixels::<DoubleDouble, _>(params, sample_step, |c| kernel(c.into())),
+        }
+    }
 
-        // Generate with reduced resolution for performance
-        let sample_step = 2;
-        let sample_width = (width + sample_step - 1) / sample_step;
-        let sample_height = (height + sample_step - 1) / sample_step;
+    fn generate_pixels<T, F>(&self, params: &FractalParams, sample_step: usize, kernel: F) -> Vec<Vec<FractalSample>>
+    where
+        T: KernelFloat,
+        F: Fn(KernelComplex<T>) -> FractalSample + Sync,
+    {
+        let grid = PixelGrid::<T>::new(params);
+        let width = params.width;
+        let height = params.height;
+        let sample_step = sample_step.max(1);
+        if sample_step == 1 {
+            return (0..height)
+                .into_par_iter()
+                .map(|y| (0..width).map(|x| kernel(grid.point(x, y))).collect())
+                .collect();
+        }
 
-        // Generate sampled data
-        let sampled_data: Vec<Vec<u32>> = (0..sample_height)
+        // Generate with reduced resolution for performance
+        let sample_width = width.div_ceil(sample_step);
+        let sample_height = height.div_ceil(sample_step);
+        let sampled_data: Vec<Vec<FractalSample>> = (0..sample_height)
             .into_par_iter()
             .map(|sy| {
                 (0..sample_width)
-                    .map(|sx| {
-                        let x = sx * sample_step;
-                        let y = sy * sample_step;
-                        let real = x_min + x as f64 * x_scale;
-                        let imag = y_min + y as f64 * y_scale;
-                        let c = Complex::new(real, imag);
-                        self.mandelbrot_iterations(c, max_iterations)
-                    })
+                    .map(|sx| kernel(grid.point(sx * sample_step, sy * sample_step)))
                     .collect()
             })
             .collect();
 
         // Upscale the sampled data to full resolution
-        let mut result = vec![vec![0u32; width]; height];
-        for y in 0..height {
-            for x in 0..width {
+        let mut result = vec![vec![FractalSample::default(); width]; height];
+        for (y, row) in result.iter_mut().enumerate() {
+            for (x, value) in row.iter_mut().enumerate() {
                 let sx = (x / sample_step).min(sample_width - 1);
                 let sy = (y / sample_step).min(sample_height - 1);
-                result[y][x] = sampled_data[sy][sx];
+                *value = sampled_data[sy][sx];
             }
         }
 
         result
     }
 
-    fn generate_julia(&self, params: &FractalParams, c: Complex<f64>) -> Vec<Vec<u32>> {
-        let width = params.width;
-        let height = params.height;
-        let zoom = params.zoom;
-        let center_x = params.center_x;
-        let center_y = params.center_y;
-        let max_iterations = params.max_iterations;
-
-        let x_min = center_x - 2.0 / zoom;
-        let x_max = center_x + 2.0 / zoom;
-        let y_min = center_y - 2.0 / zoom;
-        let y_max = center_y + 2.0 / zoom;
+    pub(crate) fn mandelbrot_max_iterations(&self, params: &FractalParams) -> u32 {
+        if self.performance_mode {
+            (params.max_iterations / 2).max(20)
+        } else if self.quality_mode {
+            // In quality mode, use higher iterations for better detail (never fewer than requested)
+            (params.max_iterations.saturating_mul(3) / 2).min(512).max(params.max_iterations)
+        } else {
+            params.max_iterations
+        }
+    }
 
-        let x_scale = (x_max - x_min) / width as f64;
-        let y_scale = (y_max - y_min) / height as f64;
+    fn generate_with_super_sampling(&self, params: &FractalParams) -> Vec<Vec<FractalSample>> {
+        // Generate at 2x resolution then downsample for better quality
+        let super_params = FractalParams {
+            width: params.width * 2,
+            height: params.height * 2,
+            ..params.clone()
+        };
 
-        (0..height)
-            .into_par_iter()
-            .map(|y| {
-                (0..width)
-                    .map(|x| {
-                        let real = x_min + x as f64 * x_scale;
-                        let imag = y_min + y as f64 * y_scale;
-                        let z = Complex::new(real, imag);
-                        self.julia_iterations(z, c, max_iterations)
-                    })
-                    .collect()
-            })
-            .collect()
+        let super_data = self.generate_standard(&super_params);
+        self.downsample_fractal(super_data, params.width, params.height)
     }
 
-    fn generate_burning_ship(&self, params: &FractalParams) -> Vec<Vec<u32>> {
-        let width = params.width;
-        let height = params.height;
-        let zoom = params.zoom;
-        let center_x = params.center_x;
-        let center_y = params.center_y;
-        let max_iterations = params.max_iterations;
+    fn downsample_fractal(&self, data: Vec<Vec<FractalSample>>, target_width: usize, target_height: usize) -> Vec<Vec<FractalSample>> {
+        let mut result = vec![vec![FractalSample::default(); target_width]; target_height];
 
-        let x_min = center_x - 2.0 / zoom;
-        let x_max = center_x + 2.0 / zoom;
-        let y_min = center_y - 2.0 / zoom;
-        let y_max = center_y + 2.0 / zoom;
+        for (y, row) in result.iter_mut().enumerate() {
+            for (x, value) in row.iter_mut().enumerate() {
+                // Sample 2x2 area and take average
+                let src_x = x * 2;
+                let src_y = y * 2;
 
-        let x_scale = (x_max - x_min) / width as f64;
-        let y_scale = (y_max - y_min) / height as f64;
+                let mut sum = 0u32;
+                let mut smooth_sum = 0.0;
+                let mut escaped = false;
+                let mut count = 0u32;
+                let mut roots = Vec::with_capacity(4);
+                let mut colors = Vec::with_capacity(4);
+                let mut trap_distances = Vec::with_capacity(4);
+                let mut distances = Vec::with_capacity(4);
+                let mut periods = Vec::with_capacity(4);
 
-        (0..height)
-            .into_par_iter()
-            .map(|y| {
-                (0..width)
-                    .map(|x| {
-                        let real = x_min + x as f64 * x_scale;
-                        let imag = y_min + y as f64 * y_scale;
-                        let c = Complex::new(real, imag);
-                        self.burning_ship_iterations(c, max_iterations)
-                    })
-                    .collect()
-            })
-            .collect()
-    }
+                for dy in 0..2 {
+                    for dx in 0..2 {
+                        let sample_x = src_x + dx;
+                        let sample_y = src_y + dy;
 
-    fn mandelbrot_iterations(&self, c: Complex<f64>, max_iterations: u32) -> u32 {
-        let mut z = Complex::new(0.0, 0.0);
-        let mut iterations = 0;
+                        if sample_y < data.len() && sample_x < data[sample_y].len() {
+                            let sample = data[sample_y][sample_x];
+                            sum += sample.iterations;
+                            // Average real values so edges blend instead of truncating
+                            smooth_sum += sample.value();
+                            escaped |= sample.smooth.is_some();
+                            roots.extend(sample.root);
+                            colors.extend(sample.color);
+                            trap_distances.extend(sample.trap_distance);
+                            distances.extend(sample.distance);
+                            periods.extend(sample.period);
+                            count += 1;
+                        }
+                    }
+                }
 
-        while iterations < max_iterations && z.norm_sqr() <= 4.0 {
-            z = z * z + c;
-            iterations += 1;
+                *value = FractalSample {
+                    iterations: sum.checked_div(count).unwrap_or(0),
+                    smooth: if escaped && count > 0 { Some(smooth_sum / count as f64) } else { None },
+                    // Basins don't blend, so take the one most of the block landed in
+                    root: most_common(&roots),
+                    color: average_color(&colors),
+                    trap_distance: average(&trap_distances),
+                    // The nearest of the four keeps thin filaments from averaging away
+                    distance: distances.iter().copied().reduce(f64::min),
+                    period: most_common(&periods),
+                };
+            }
         }
 
-        iterations
+        result
     }
 
-    fn julia_iterations(&self, mut z: Complex<f64>, c: Complex<f64>, max_iterations: u32) -> u32 {
-        let mut iterations = 0;
-
-        while iterations < max_iterations && z.norm_sqr() <= 4.0 {
-            z = z * z + c;
-            iterations += 1;
+    pub(crate) fn bailout_sqr(&self) -> f64 {
+        // Both the normalized count and the distance estimate need |z| well past 2
+        if self.smooth_coloring || self.distance_estimation {
+            SMOOTH_BAILOUT * SMOOTH_BAILOUT
+        } else {
+            4.0
         }
-
-        iterations
     }
 
-    fn burning_ship_iterations(&self, c: Complex<f64>, max_iterations: u32) -> u32 {
-        let mut z: Complex<f64> = Complex::new(0.0, 0.0);
-        let mut iterations = 0;
-
-        while iterations < max_iterations && z.norm_sqr() <= 4.0 {
-            // Burning ship: z = (|Re(z)| + i|Im(z)|)^2 + c
-            let re_abs = z.re.abs();
-            let im_abs = z.im.abs();
-            z = Complex::new(re_abs, im_abs);
-            z = z * z + c;
-            iterations += 1;
+    // Exterior distance estimate 0.5 |z| ln|z| / |dz/dc| for an orbit that escaped
+    // at `z` with derivative `derivative`, in complex plane units
+    pub(crate) fn distance_estimate(&self, iterations: u32, max_iterations: u32, z: Complex<f64>, derivative: Complex<f64>) -> Option<f64> {
+        if !self.distance_estimation || iterations >= max_iterations {
+            return None;
         }
 
-        iterations
-    }
-
-    fn generate_tricorn(&self, params: &FractalParams) -> Vec<Vec<u32>> {
-        let width = params.width;
-        let height = params.height;
-        let zoom = params.zoom;
-        let center_x = params.center_x;
-        let center_y = params.center_y;
-        let max_iterations = params.max_iterations;
-
-        let x_min = center_x - 2.0 / zoom;
-        let x_max = center_x + 2.0 / zoom;
-        let y_min = center_y - 2.0 / zoom;
-        let y_max = center_y + 2.0 / zoom;
-
-        let x_scale = (x_max - x_min) / width as f64;
-        let y_scale = (y_max - y_min) / height as f64;
-
-        (0..height)
-            .into_par_iter()
-            .map(|y| {
-                (0..width)
-                    .map(|x| {
-                        let real = x_min + x as f64 * x_scale;
-                        let imag = y_min + y as f64 * y_scale;
-                        let c = Complex::new(real, imag);
-                        self.tricorn_iterations(c, max_iterations)
-                    })
-                    .collect()
-            })
-            .collect()
-    }
-
-    fn generate_multibrot(&self, params: &FractalParams, power: f64) -> Vec<Vec<u32>> {
-        let width = params.width;
-        let height = params.height;
-        let zoom = params.zoom;
-        let center_x = params.center_x;
-        let center_y = params.center_y;
-        let max_iterations = params.max_iterations;
-
-        let x_min = center_x - 2.0 / zoom;
-        let x_max = center_x + 2.0 / zoom;
-        let y_min = center_y - 2.0 / zoom;
-        let y_max = center_y + 2.0 / zoom;
-
-        let x_scale = (x_max - x_min) / width as f64;
-        let y_scale = (y_max - y_min) / height as f64;
-
-        (0..height)
-            .into_par_iter()
-            .map(|y| {
-                (0..width)
-                    .map(|x| {
-                        let real = x_min + x as f64 * x_scale;
-                        let imag = y_min + y as f64 * y_scale;
-                        let c = Complex::new(real, imag);
-                        self.multibrot_iterations(c, power, max_iterations)
-                    })
-                    .collect()
-            })
-            .collect()
+        let modulus = z.norm();
+        let derivative_modulus = derivative.norm();
+        if !derivative_modulus.is_finite() || modulus <= 1.0 {
+            // A runaway derivative means the point sits right on the boundary
+            return Some(0.0);
+        }
+        if derivative_modulus == 0.0 {
+            return None;
+        }
+        Some(0.5 * modulus * modulus.ln() / derivative_modulus)
     }
 
-    fn tricorn_iterations(&self, c: Complex<f64>, max_iterations: u32) -> u32 {
-        let mut z: Complex<f64> = Complex::new(0.0, 0.0);
-        let mut iterations = 0;
+    // Builds the sample for an orbit that stopped after `iterations` steps at `z`.
+    // Escaped points get the normalized iteration count n + 1 - log_p(ln|z|).
+    pub(crate) fn finish_sample(&self, iterations: u32, max_iterations: u32, z: Complex<f64>, power: f64) -> FractalSample {
+        if !self.smooth_coloring || iterations >= max_iterations {
+            return FractalSample::new(iterations);
+        }
 
-        while iterations < max_iterations && z.norm_sqr() <= 4.0 {
-            // Tricorn: z = conj(z)^2 + c
-            z = z.conj();
-            z = z * z + c;
-            iterations += 1;
+        let log_modulus = z.norm_sqr().ln() / 2.0;
+        if !log_modulus.is_finite() || log_modulus <= 0.0 {
+            return FractalSample::new(iterations);
         }
 
-        iterations
+        let smooth = iterations as f64 + 1.0 - log_modulus.ln() / power.ln();
+        FractalSample {
+            iterations,
+            smooth: Some(smooth.max(0.0)),
+            ..FractalSample::new(iterations)
+        }
     }
 
-    fn multibrot_iterations(&self, c: Complex<f64>, power: f64, max_iterations: u32) -> u32 {
-        let mut z: Complex<f64> = Complex::new(0.0, 0.0);
-        let mut iterations = 0;
+}
 
-        while iterations < max_iterations && z.norm_sqr() <= 4.0 {
-            // Multibrot: z = z^power + c
-            z = z.powf(power) + c;
-            iterations += 1;
-        }
+fn most_common<T: Copy + PartialEq>(values: &[T]) -> Option<T> {
+    values.iter().copied().max_by_key(|&value| values.iter().filter(|&&other| other == value).count())
+}
+
+fn average(values: &[f64]) -> Option<f64> {
+    (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+}
 
-        iterations
+fn average_color(colors: &[(u8, u8, u8)]) -> Option<(u8, u8, u8)> {
+    if colors.is_empty() {
+        return None;
     }
+    let count = colors.len() as u32;
+    let sum = colors.iter().fold((0u32, 0u32, 0u32), |sum, color| {
+        (sum.0 + color.0 as u32, sum.1 + color.1 as u32, sum.2 + color.2 as u32)
+    });
+    Some(((sum.0 / count) as u8, (sum.1 / count) as u8, (sum.2 / count) as u8))
 }
 
 impl Default for FractalGenerator {
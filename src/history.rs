@@ -0,0 +1,104 @@
+use crate::bigfloat::BigFloat;
+use crate::fractal::FractalType;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+// Undo/redo for navigation: every view the TUI settles on is recorded, and
+// stepping back and forth moves a cursor through the list. Recording a new
+// view after stepping back drops the views that were ahead of the cursor.
+
+/// How many views the TUI keeps before forgetting the oldest
+pub const DEFAULT_HISTORY_CAPACITY: usize = 100;
+
+/// Everything needed to return to a view
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ViewState {
+    pub fractal_type: FractalType,
+    pub equation: String,
+    pub center_x: BigFloat,
+    pub center_y: BigFloat,
+    pub zoom_factor: BigFloat,
+    pub max_iterations: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct NavigationHistory {
+    entries: VecDeque<ViewState>,
+    /// Index of the view currently shown
+    position: usize,
+    capacity: usize,
+}
+
+impl NavigationHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self { entries: VecDeque::new(), position: 0, capacity: capacity.max(1) }
+    }
+
+    /// Records the view now shown; repeats of the current view are ignored
+    pub fn record(&mut self, state: ViewState) {
+        if self.current() == Some(&state) {
+            return;
+        }
+        if !self.entries.is_empty() {
+            self.entries.truncate(self.position + 1);
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(state);
+        self.position = self.entries.len() - 1;
+    }
+
+    pub fn current(&self) -> Option<&ViewState> {
+        self.entries.get(self.position)
+    }
+
+    /// Steps back to the previous view, if there is one
+    pub fn undo(&mut self) -> Option<&ViewState> {
+        if self.position == 0 {
+            return None;
+        }
+        self.position -= 1;
+        self.current()
+    }
+
+    /// Steps forward again after an undo
+    pub fn redo(&mut self) -> Option<&ViewState> {
+        if self.position + 1 >= self.entries.len() {
+            return None;
+        }
+        self.position += 1;
+        self.current()
+    }
+
+    /// Moves straight to the view at `index`, oldest first, keeping the views after it for redo
+    pub fn jump(&mut self, index: usize) -> Option<&ViewState> {
+        if index >= self.entries.len() {
+            return None;
+        }
+        self.position = index;
+        self.current()
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &ViewState> {
+        self.entries.iter()
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl Default for NavigationHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_HISTORY_CAPACITY)
+    }
+}
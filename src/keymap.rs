@@ -0,0 +1,372 @@
+use crate::config::ControlsConfig;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+// Every key the TUI reacts to in normal mode goes through the keymap, so the
+// bindings, the help popup and the controls panel can never disagree. Popups
+// and text prompts keep their own fixed keys (arrows, Enter, Esc).
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    InteractiveMode,
+    AutoMode,
+    EquationEditor,
+    PanUp,
+    PanDown,
+    PanLeft,
+    PanRight,
+    ZoomIn,
+    ZoomOut,
+    ResetCenter,
+    Undo,
+    Redo,
+    History,
+    Bookmarks,
+    BoxZoom,
+    MoreIterations,
+    FewerIterations,
+    Regenerate,
+    NextFractal,
+    JuliaMode,
+    SplitView,
+    CrosshairUp,
+    CrosshairDown,
+    CrosshairLeft,
+    CrosshairRight,
+    BurningShip,
+    JuliaSet,
+    Tricorn,
+    PerformanceMode,
+    AdaptiveSampling,
+    ClearCache,
+    PerformanceStats,
+    QualityMode,
+    SuperSampling,
+    SmoothColouring,
+    DeepZoom,
+    FloatPrecision,
+    DistanceEstimation,
+    InteriorDetection,
+    PeriodColouring,
+    NextPalette,
+    NextCellMode,
+    OrbitTrap,
+    TrapShape,
+    PlaceTrap,
+    TrapSmaller,
+    TrapLarger,
+    CommandLine,
+    ToggleHelp,
+    Quit,
+}
+
+struct ActionInfo {
+    action: Action,
+    /// Name used in the config file
+    name: &'static str,
+    /// Help popup heading the action is listed under
+    section: &'static str,
+    description: &'static str,
+    /// Short label for the controls panel; `None` keeps the action out of it
+    label: Option<&'static str>,
+    default_keys: &'static [&'static str],
+}
+
+// In the order the help popup and controls panel list them
+const ACTIONS: &[ActionInfo] = &[
+    ActionInfo { action: Action::InteractiveMode, name: "interactive_mode", section: "Modes", description: "Interactive Mode", label: Some("Interactive Mode"), default_keys: &["1"] },
+    ActionInfo { action: Action::AutoMode, name: "auto_mode", section: "Modes", description: "Auto Generation Mode", label: Some("Auto Mode"), default_keys: &["2"] },
+    ActionInfo { action: Action::EquationEditor, name: "equation_editor", section: "Modes", description: "Equation Editor", label: Some("Edit Equation"), default_keys: &["3"] },
+    ActionInfo { action: Action::PanUp, name: "pan_up", section: "Navigation", description: "Pan up", label: Some("Pan Up"), default_keys: &["Up"] },
+    ActionInfo { action: Action::PanDown, name: "pan_down", section: "Navigation", description: "Pan down", label: Some("Pan Down"), default_keys: &["Down"] },
+    ActionInfo { action: Action::PanLeft, name: "pan_left", section: "Navigation", description: "Pan left", label: Some("Pan Left"), default_keys: &["Left"] },
+    ActionInfo { action: Action::PanRight, name: "pan_right", section: "Navigation", description: "Pan right", label: Some("Pan Right"), default_keys: &["Right"] },
+    ActionInfo { action: Action::ZoomIn, name: "zoom_in", section: "Navigation", description: "Zoom in", label: Some("Zoom In"), default_keys: &["+", "="] },
+    ActionInfo { action: Action::ZoomOut, name: "zoom_out", section: "Navigation", description: "Zoom out", label: Some("Zoom Out"), default_keys: &["-", "_"] },
+    ActionInfo { action: Action::ResetCenter, name: "reset_center", section: "Navigation", description: "Reset to center", label: Some("Reset Center"), default_keys: &["c"] },
+    ActionInfo { action: Action::Undo, name: "undo", section: "Navigation", description: "Undo the last view change", label: Some("Undo View"), default_keys: &["u"] },
+    ActionInfo { action: Action::Redo, name: "redo", section: "Navigation", description: "Redo the last undone view change", label: Some("Redo View"), default_keys: &["U"] },
+    ActionInfo { action: Action::History, name: "history", section: "Navigation", description: "History of recent views", label: Some("History"), default_keys: &["H"] },
+    ActionInfo { action: Action::Bookmarks, name: "bookmarks", section: "Navigation", description: "Bookmarks (save, jump, rename, delete)", label: Some("Bookmarks"), default_keys: &["B"] },
+    ActionInfo { action: Action::BoxZoom, name: "box_zoom", section: "Navigation", description: "Box zoom (arrows + Enter for each corner)", label: Some("Box Zoom"), default_keys: &["b"] },
+    ActionInfo { action: Action::MoreIterations, name: "more_iterations", section: "Parameters", description: "Increase iterations", label: Some("More Iterations"), default_keys: &["i"] },
+    ActionInfo { action: Action::FewerIterations, name: "fewer_iterations", section: "Parameters", description: "Decrease iterations", label: Some("Fewer Iterations"), default_keys: &["d"] },
+    ActionInfo { action: Action::Regenerate, name: "regenerate", section: "Parameters", description: "Regenerate fractal", label: Some("Regenerate"), default_keys: &["r", "Space"] },
+    ActionInfo { action: Action::NextFractal, name: "next_fractal", section: "Fractals", description: "Next registered fractal", label: Some("Next Fractal"), default_keys: &["n"] },
+    ActionInfo { action: Action::JuliaMode, name: "julia_mode", section: "Fractals", description: "Julia mode at the view center / back", label: Some("Julia Mode"), default_keys: &["j"] },
+    ActionInfo { action: Action::SplitView, name: "split_view", section: "Fractals", description: "Split view: parameter plane + live Julia set", label: Some("Split View"), default_keys: &["J"] },
+    ActionInfo { action: Action::CrosshairUp, name: "crosshair_up", section: "Fractals", description: "Move the split view crosshair up", label: Some("Crosshair Up"), default_keys: &["Shift+Up"] },
+    ActionInfo { action: Action::CrosshairDown, name: "crosshair_down", section: "Fractals", description: "Move the split view crosshair down", label: Some("Crosshair Down"), default_keys: &["Shift+Down"] },
+    ActionInfo { action: Action::CrosshairLeft, name: "crosshair_left", section: "Fractals", description: "Move the split view crosshair left", label: Some("Crosshair Left"), default_keys: &["Shift+Left"] },
+    ActionInfo { action: Action::CrosshairRight, name: "crosshair_right", section: "Fractals", description: "Move the split view crosshair right", label: Some("Crosshair Right"), default_keys: &["Shift+Right"] },
+    ActionInfo { action: Action::BurningShip, name: "burning_ship", section: "Quick Presets", description: "Burning Ship", label: None, default_keys: &["F2"] },
+    ActionInfo { action: Action::JuliaSet, name: "julia_set", section: "Quick Presets", description: "Julia Set", label: None, default_keys: &["F3"] },
+    ActionInfo { action: Action::Tricorn, name: "tricorn", section: "Quick Presets", description: "Tricorn", label: None, default_keys: &["F4"] },
+    ActionInfo { action: Action::PerformanceMode, name: "performance_mode", section: "Performance & Quality", description: "Toggle Performance Mode", label: None, default_keys: &["F5"] },
+    ActionInfo { action: Action::AdaptiveSampling, name: "adaptive_sampling", section: "Performance & Quality", description: "Toggle Adaptive Sampling", label: None, default_keys: &["F6"] },
+    ActionInfo { action: Action::ClearCache, name: "clear_cache", section: "Performance & Quality", description: "Clear Cache", label: None, default_keys: &["F7"] },
+    ActionInfo { action: Action::PerformanceStats, name: "performance_stats", section: "Performance & Quality", description: "Show Performance Stats", label: None, default_keys: &["F8"] },
+    ActionInfo { action: Action::QualityMode, name: "quality_mode", section: "Performance & Quality", description: "Toggle Quality Mode", label: Some("Quality Mode"), default_keys: &["F9"] },
+    ActionInfo { action: Action::SuperSampling, name: "super_sampling", section: "Performance & Quality", description: "Toggle Super Sampling", label: Some("Super Sampling"), default_keys: &["F10"] },
+    ActionInfo { action: Action::SmoothColouring, name: "smooth_colouring", section: "Performance & Quality", description: "Toggle Smooth Colouring", label: Some("Smooth Colouring"), default_keys: &["s"] },
+    ActionInfo { action: Action::DeepZoom, name: "deep_zoom", section: "Performance & Quality", description: "Toggle Deep Zoom (perturbation)", label: Some("Deep Zoom"), default_keys: &["p"] },
+    ActionInfo { action: Action::FloatPrecision, name: "float_precision", section: "Performance & Quality", description: "Cycle Float Precision (f32/f64/double-double)", label: Some("Float Precision"), default_keys: &["f"] },
+    ActionInfo { action: Action::DistanceEstimation, name: "distance_estimation", section: "Performance & Quality", description: "Toggle Distance Estimation (boundary outline)", label: Some("Distance Estimation"), default_keys: &["e"] },
+    ActionInfo { action: Action::InteriorDetection, name: "interior_detection", section: "Performance & Quality", description: "Toggle Interior Detection (cardioid/bulb + periodicity)", label: Some("Interior Detection"), default_keys: &["I"] },
+    ActionInfo { action: Action::PeriodColouring, name: "period_colouring", section: "Performance & Quality", description: "Toggle Period Colouring of interior points", label: Some("Period Colouring"), default_keys: &["P"] },
+    ActionInfo { action: Action::NextPalette, name: "next_palette", section: "Performance & Quality", description: "Cycle colour palette (default, truecolor gradients)", label: Some("Palette"), default_keys: &["C"] },
+    ActionInfo { action: Action::NextCellMode, name: "next_cell_mode", section: "Performance & Quality", description: "Cycle cell mode (text, braille, half-block)", label: Some("Cell Mode"), default_keys: &["m"] },
+    ActionInfo { action: Action::OrbitTrap, name: "orbit_trap", section: "Orbit Traps", description: "Toggle Orbit Trap colouring", label: Some("Orbit Trap"), default_keys: &["o"] },
+    ActionInfo { action: Action::TrapShape, name: "trap_shape", section: "Orbit Traps", description: "Cycle trap shape (point/line/cross/circle)", label: Some("Trap Shape"), default_keys: &["O"] },
+    ActionInfo { action: Action::PlaceTrap, name: "place_trap", section: "Orbit Traps", description: "Place trap at view center", label: Some("Place Trap"), default_keys: &["t"] },
+    ActionInfo { action: Action::TrapSmaller, name: "trap_smaller", section: "Orbit Traps", description: "Shrink circle trap, rotate line trap back", label: Some("Trap Smaller"), default_keys: &["["] },
+    ActionInfo { action: Action::TrapLarger, name: "trap_larger", section: "Orbit Traps", description: "Grow circle trap, rotate line trap on", label: Some("Trap Larger"), default_keys: &["]"] },
+    ActionInfo { action: Action::CommandLine, name: "command_line", section: "General", description: "Command line (:zoom 1e6, :export out.png, ...)", label: Some("Command Line"), default_keys: &[":"] },
+    ActionInfo { action: Action::ToggleHelp, name: "toggle_help", section: "General", description: "Toggle this help", label: Some("Toggle Help"), default_keys: &["h", "F1"] },
+    ActionInfo { action: Action::Quit, name: "quit", section: "General", description: "Quit application", label: Some("Quit"), default_keys: &["q", "Esc"] },
+];
+
+impl Action {
+    fn info(self) -> &'static ActionInfo {
+        ACTIONS.iter().find(|info| info.action == self).expect("every action has an entry in ACTIONS")
+    }
+
+    /// Name used for the action in the config file, e.g. `zoom_in`
+    pub fn name(self) -> &'static str {
+        self.info().name
+    }
+
+    pub fn description(self) -> &'static str {
+        self.info().description
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        ACTIONS.iter().find(|info| info.name.eq_ignore_ascii_case(name)).map(|info| info.action)
+    }
+
+    /// Every action, in help order
+    pub fn all() -> impl Iterator<Item = Action> {
+        ACTIONS.iter().map(|info| info.action)
+    }
+}
+
+/// A key with its modifiers, written like `q`, `F1`, `Shift+Up` or `Ctrl+s`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        // A typed character already says whether Shift was down ('U' vs 'u'), and
+        // terminals disagree on whether they report it, so it is left out
+        let modifiers = match code {
+            KeyCode::Char(_) => modifiers - KeyModifiers::SHIFT,
+            _ => modifiers,
+        };
+        Self { code, modifiers: modifiers & (KeyModifiers::SHIFT | KeyModifiers::CONTROL | KeyModifiers::ALT) }
+    }
+}
+
+impl From<KeyEvent> for KeyChord {
+    fn from(key: KeyEvent) -> Self {
+        Self::new(key.code, key.modifiers)
+    }
+}
+
+impl FromStr for KeyChord {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        // The key comes last, and may itself be '+'
+        let (prefix, key) = match text.strip_suffix('+') {
+            Some(prefix) if prefix.is_empty() || prefix.ends_with('+') => (prefix, "+"),
+            _ => text.rsplit_once('+').unwrap_or(("", text)),
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        for modifier in prefix.split('+').filter(|modifier| !modifier.is_empty()) {
+            modifiers |= match modifier.to_ascii_lowercase().as_str() {
+                "shift" => KeyModifiers::SHIFT,
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                _ => return Err(format!("unknown modifier '{}' in key '{}'", modifier, text)),
+            };
+        }
+
+        let mut characters = key.chars();
+        let code = match (characters.next(), characters.next()) {
+            (Some(c), None) => match c {
+                '↑' => KeyCode::Up,
+                '↓' => KeyCode::Down,
+                '←' => KeyCode::Left,
+                '→' => KeyCode::Right,
+                // "Shift+a" means the key that types 'A'
+                c if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::Char(c.to_ascii_uppercase()),
+                c => KeyCode::Char(c),
+            },
+            _ => match key.to_ascii_lowercase().as_str() {
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" | "ins" => KeyCode::Insert,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                "space" => KeyCode::Char(' '),
+                name => match name.strip_prefix('f').and_then(|number| number.parse::<u8>().ok()) {
+                    Some(number) if (1..=12).contains(&number) => KeyCode::F(number),
+                    _ => return Err(format!("unknown key '{}'", text)),
+                },
+            },
+        };
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "Shift+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(number) => write!(f, "F{}", number),
+            KeyCode::Up => write!(f, "↑"),
+            KeyCode::Down => write!(f, "↓"),
+            KeyCode::Left => write!(f, "←"),
+            KeyCode::Right => write!(f, "→"),
+            KeyCode::PageUp => write!(f, "PageUp"),
+            KeyCode::PageDown => write!(f, "PageDown"),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+/// The key chords bound to each action
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keymap {
+    /// Chords per action, in help order
+    bindings: Vec<(Action, Vec<KeyChord>)>,
+    actions: HashMap<KeyChord, Action>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = ACTIONS
+            .iter()
+            .map(|info| (info.action, info.default_keys.iter().map(|key| key.parse().expect("default keys parse")).collect()))
+            .collect();
+        Self::from_bindings(bindings).expect("default keys do not clash")
+    }
+}
+
+impl Keymap {
+    /// The default bindings with the config's changes applied. `zoom_in_key` and
+    /// `zoom_out_key` replace the first zoom chord; each entry of `keys` replaces
+    /// every chord of the action it names, and an empty list unbinds it.
+    pub fn from_config(controls: &ControlsConfig) -> Result<Self, String> {
+        let mut bindings = Self::default().bindings;
+        let mut set = |action: Action, index: Option<usize>, chords: Vec<KeyChord>| {
+            let (_, existing) = bindings.iter_mut().find(|(bound, _)| *bound == action).expect("every action is bound");
+            match index {
+                Some(index) => existing.splice(index..index + 1, chords).for_each(drop),
+                None => *existing = chords,
+            }
+        };
+
+        set(Action::ZoomIn, Some(0), vec![controls.zoom_in_key.parse()?]);
+        set(Action::ZoomOut, Some(0), vec![controls.zoom_out_key.parse()?]);
+        for (name, keys) in &controls.keys {
+            let action = Action::from_name(name).ok_or_else(|| format!("unknown action '{}' in key bindings", name))?;
+            let chords = keys.iter().map(|key| key.parse()).collect::<Result<Vec<KeyChord>, String>>()?;
+            set(action, None, chords);
+        }
+        Self::from_bindings(bindings)
+    }
+
+    fn from_bindings(mut bindings: Vec<(Action, Vec<KeyChord>)>) -> Result<Self, String> {
+        let mut actions = HashMap::new();
+        for (action, chords) in &mut bindings {
+            let mut seen = Vec::new();
+            chords.retain(|chord| {
+                let first = !seen.contains(chord);
+                seen.push(*chord);
+                first
+            });
+            for chord in chords.iter() {
+                if let Some(other) = actions.insert(*chord, *action) {
+                    return Err(format!("key '{}' is bound to both {} and {}", chord, other.name(), action.name()));
+                }
+            }
+        }
+        Ok(Self { bindings, actions })
+    }
+
+    /// The action a key press triggers, if any
+    pub fn action_for(&self, key: KeyEvent) -> Option<Action> {
+        self.actions.get(&KeyChord::from(key)).copied()
+    }
+
+    pub fn chords(&self, action: Action) -> &[KeyChord] {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| *bound == action)
+            .map_or(&[], |(_, chords)| chords.as_slice())
+    }
+
+    /// The chords for an action as shown to the user, e.g. "h/F1"
+    pub fn keys_label(&self, action: Action) -> String {
+        let chords: Vec<String> = self.chords(action).iter().map(|chord| chord.to_string()).collect();
+        if chords.is_empty() {
+            "(unbound)".to_string()
+        } else {
+            chords.join("/")
+        }
+    }
+
+    /// The key list for the controls panel, one action per line
+    pub fn controls_text(&self) -> String {
+        ACTIONS
+            .iter()
+            .filter_map(|info| info.label.map(|label| (info.action, label)))
+            .filter(|(action, _)| !self.chords(*action).is_empty())
+            .map(|(action, label)| format!("{:<4}: {}", self.keys_label(action), label))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// The key sections of the help popup, headed as in `ACTIONS`
+    pub fn help_text(&self) -> String {
+        let mut text = String::new();
+        let mut section = "";
+        for info in ACTIONS {
+            if info.section != section {
+                if !section.is_empty() {
+                    text.push('\n');
+                }
+                section = info.section;
+                text.push_str(&format!("{}:\n", section));
+            }
+            text.push_str(&format!("{} - {}\n", self.keys_label(info.action), info.description));
+        }
+        text
+    }
+}
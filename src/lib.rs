@@ -61,12 +61,57 @@ pub mod ui;
 pub mod renderer;
 /// Configuration management and serialization
 pub mod config;
+/// Formula tokenizer, parser and evaluator for custom fractals
+pub mod expression;
+/// Arbitrary-precision floating point numbers
+pub mod bigfloat;
+/// Perturbation-theory deep zoom engine
+pub mod perturbation;
+/// Double-double arithmetic and kernel float precision
+pub mod precision;
+/// Complex polynomials and their roots for the Newton fractal
+pub mod polynomial;
+/// Buddhabrot and Nebulabrot orbit density renderer
+pub mod buddhabrot;
+/// Orbit trap shapes and distance tracking
+pub mod orbit_trap;
+/// The `Fractal` trait and the registry of fractals the generator can render
+pub mod registry;
+/// Built-in fractals and their iteration kernels
+pub mod builtin;
+/// Undo/redo history of the views visited in the TUI
+pub mod history;
+/// Named views saved to disk
+pub mod bookmark;
+/// Command line parsing for the TUI's `:` mode
+pub mod command;
+/// Writing rendered fractals to text, ANSI and PNG files
+pub mod export;
+/// Configurable key chords for the TUI's actions
+pub mod keymap;
+/// Command-line arguments and config loading
+pub mod cli;
+/// Truecolor gradient palettes
+pub mod palette;
 
 pub use app::App;
-pub use fractal::{FractalType, FractalParams, FractalGenerator};
+pub use fractal::{FractalType, FractalParams, FractalGenerator, FractalSample};
 pub use ui::UI;
 pub use renderer::TerminalRenderer;
 pub use config::Config;
+pub use bigfloat::BigFloat;
+pub use precision::{DoubleDouble, Precision};
+pub use polynomial::Polynomial;
+pub use buddhabrot::DensityAccumulator;
+pub use orbit_trap::{OrbitTrap, TrapShape};
+pub use registry::{Fractal, FractalParameter, FractalRegistry, PlanePoint, Viewport};
+pub use history::{NavigationHistory, ViewState};
+pub use bookmark::{Bookmark, Bookmarks};
+pub use command::Command;
+pub use export::ExportFormat;
+pub use keymap::{Action, KeyChord, Keymap};
+pub use cli::Cli;
+pub use palette::Palette;
 
 #[cfg(test)]
 mod tests {
@@ -91,6 +136,7 @@ mod tests {
             center_x: -0.5,
             center_y: 0.0,
             max_iterations: 100,
+            ..Default::default()
         };
 
         let result = generator.generate(&params);
@@ -118,6 +164,7 @@ mod tests {
             center_x: 0.0,
             center_y: 0.0,
             max_iterations: 50,
+            ..Default::default()
         };
 
         let result = generator.generate(&params);
@@ -136,6 +183,7 @@ mod tests {
             center_x: -0.5,
             center_y: 0.0,
             max_iterations: 50,
+            ..Default::default()
         };
 
         let result = generator.generate(&params);
@@ -204,6 +252,1336 @@ mod tests {
         let mut bad_config = Config::default();
         bad_config.display.default_width = 0;
         assert!(bad_config.validate().is_err());
+
+        let mut bad_config = Config::default();
+        bad_config.controls.iteration_step = command::MAX_ITERATIONS + 1;
+        assert!(bad_config.validate().is_err());
+    }
+
+    #[test]
+    fn test_expression_evaluation() {
+        let z = Complex::new(0.5, -0.25);
+        let c = Complex::new(-0.4, 0.6);
+
+        let quadratic = expression::parse("z^2 + c").unwrap();
+        assert_eq!(quadratic.eval(z, c), z * z + c);
+
+        let implicit = expression::parse("2z - 3i").unwrap();
+        assert_eq!(implicit.eval(z, c), z * 2.0 - Complex::new(0.0, 3.0));
+
+        let functions = expression::parse("sin(z) * conj(c) + re(z)").unwrap();
+        let expected = z.sin() * c.conj() + Complex::new(z.re, 0.0);
+        assert!((functions.eval(z, c) - expected).norm() < 1e-12);
+
+        // Constant exponents are classified when parsing
+        assert!(matches!(expression::parse("z^3").unwrap(), expression::Expr::PowInt(_, 3)));
+        assert!(matches!(expression::parse("z^-0.5").unwrap(), expression::Expr::PowReal(_, _)));
+        assert!(matches!(expression::parse("z^(1+i)").unwrap(), expression::Expr::Pow(_, _)));
+    }
+
+    #[test]
+    fn test_expression_parse_errors() {
+        let error = expression::parse("z^2 + * c").unwrap_err();
+        assert_eq!(error.position, 6);
+
+        let error = expression::parse("sin(z + c").unwrap_err();
+        assert_eq!(error.position, 9);
+
+        let error = expression::parse("z^2 + q").unwrap_err();
+        assert_eq!(error.position, 6);
+        assert!(error.message.contains("unknown identifier"));
+
+        assert!(expression::parse("").is_err());
+        assert!(expression::parse("z $ c").is_err());
+    }
+
+    #[test]
+    fn test_advertised_custom_examples_render() {
+        let generator = FractalGenerator::new();
+        for equation in ["sin(z) * c", "exp(z) + c", "conj(z)^2 + c", "z^2.5 + c", "z^(1+i) + c", "z^3 - 0.5*z + c"] {
+            let params = FractalParams {
+                fractal_type: FractalType::Custom { equation: equation.to_string() },
+                width: 40,
+                height: 20,
+                max_iterations: 100,
+                ..Default::default()
+            };
+            let samples = generator.generate_samples(&params);
+            let counts: Vec<u32> = samples.iter().flatten().map(|sample| sample.iterations).collect();
+            let interior = counts.iter().filter(|&&count| count >= 100).count();
+            let distinct = counts.iter().collect::<std::collections::HashSet<_>>().len();
+            assert!(interior < counts.len() && distinct > 5, "{} renders a near-uniform image", equation);
+        }
+    }
+
+    #[test]
+    fn test_custom_formula_matches_builtin() {
+        let mut generator = FractalGenerator::new();
+        generator.set_quality_mode(false);
+
+        let params = FractalParams {
+            fractal_type: FractalType::Mandelbrot,
+            width: 12,
+            height: 12,
+            zoom: 1.0,
+            center_x: -0.5,
+            center_y: 0.0,
+            max_iterations: 60,
+            ..Default::default()
+        };
+        let custom_params = FractalParams {
+            fractal_type: FractalType::Custom { equation: "z*z + c".to_string() },
+            ..params.clone()
+        };
+
+        assert_eq!(generator.generate(&params), generator.generate(&custom_params));
+    }
+
+    #[test]
+    fn test_smooth_iteration_counts() {
+        let mut generator = FractalGenerator::new();
+        generator.set_quality_mode(false);
+        let params = FractalParams {
+            fractal_type: FractalType::Mandelbrot,
+            width: 16,
+            height: 16,
+            zoom: 1.0,
+            center_x: -0.5,
+            center_y: 0.0,
+            max_iterations: 80,
+            ..Default::default()
+        };
+
+        generator.set_smooth_coloring(true);
+        let samples = generator.generate_samples(&params);
+        for sample in samples.iter().flatten() {
+            if sample.iterations >= 80 {
+                assert_eq!(sample.smooth, None);
+            } else {
+                // The fractional count stays within a couple of iterations of the integer one
+                let smooth = sample.smooth.expect("escaped points carry a smooth value");
+                assert!((smooth - sample.iterations as f64).abs() < 3.0);
+            }
+        }
+
+        // generate() still reports the integer counts
+        let counts = generator.generate(&params);
+        assert_eq!(counts[3][5], samples[3][5].iterations);
+
+        generator.set_smooth_coloring(false);
+        let plain = generator.generate_samples(&params);
+        assert!(plain.iter().flatten().all(|sample| sample.smooth.is_none()));
+    }
+
+    #[test]
+    fn test_super_sampling_averages_smooth_values() {
+        let mut generator = FractalGenerator::new();
+        generator.set_super_sampling(true);
+        let params = FractalParams {
+            fractal_type: FractalType::Julia { c: Complex::new(-0.7, 0.27) },
+            width: 8,
+            height: 8,
+            zoom: 1.0,
+            center_x: 0.0,
+            center_y: 0.0,
+            max_iterations: 50,
+            ..Default::default()
+        };
+
+        let samples = generator.generate_samples(&params);
+        assert_eq!(samples.len(), 8);
+        assert_eq!(samples[0].len(), 8);
+        assert!(samples.iter().flatten().any(|sample| sample.smooth.is_some_and(|value| value.fract() != 0.0)));
+    }
+
+    #[test]
+    fn test_bigfloat_arithmetic() {
+        use crate::bigfloat::BigFloat;
+
+        let a = BigFloat::from_f64(1.5, 128);
+        let b = BigFloat::from_f64(-0.25, 128);
+        assert_eq!((&a + &b).to_f64(), 1.25);
+        assert_eq!((&a - &b).to_f64(), 1.75);
+        assert_eq!((&a * &b).to_f64(), -0.375);
+        assert_eq!(a.square().to_f64(), 2.25);
+        assert_eq!(a.div_u32(3).to_f64(), 0.5);
+
+        // Digits far below f64 precision survive addition
+        let tiny = BigFloat::from_f64(1e-30, 192);
+        let sum = &BigFloat::from_f64(1.0, 192) + &tiny;
+        assert_eq!((&sum - &BigFloat::from_f64(1.0, 192)).to_f64(), 1e-30);
+    }
+
+    #[test]
+    fn test_perturbation_matches_direct_iteration() {
+        let mut generator = FractalGenerator::new();
+        generator.set_quality_mode(false);
+        generator.set_adaptive_sampling(false);
+        generator.set_smooth_coloring(false);
+        let params = FractalParams {
+            fractal_type: FractalType::Mandelbrot,
+            width: 24,
+            height: 12,
+            zoom: 1e4,
+            center_x: -0.743643887,
+            center_y: 0.131825904,
+            max_iterations: 400,
+            ..Default::default()
+        };
+
+        let kind = perturbation::PerturbationKind::for_fractal(&generator.registry, &params.fractal_type).unwrap();
+        let deep = perturbation::generate(&generator, &params, kind, params.max_iterations);
+        generator.set_deep_zoom(false);
+        let direct = generator.generate_samples(&params);
+
+        let mismatches = deep.iter().flatten().zip(direct.iter().flatten())
+            .filter(|(a, b)| a.iterations.abs_diff(b.iterations) > 1)
+            .count();
+        assert!(mismatches <= 3, "{} pixels differ", mismatches);
+
+        // Higher powers step the delta through the binomial expansion of (Z + d)^p - Z^p
+        let params = FractalParams { fractal_type: FractalType::Multibrot { power: 4.0 }, center_x: 0.35, center_y: 0.65, zoom: 20.0, ..params };
+        let kind = perturbation::PerturbationKind::for_fractal(&generator.registry, &params.fractal_type).unwrap();
+        let deep = perturbation::generate(&generator, &params, kind, params.max_iterations);
+        let direct = generator.generate_samples(&params);
+        assert!(direct.iter().flatten().any(|sample| sample.iterations != direct[0][0].iterations));
+        let mismatches = deep.iter().flatten().zip(direct.iter().flatten())
+            .filter(|(a, b)| a.iterations.abs_diff(b.iterations) > 1)
+            .count();
+        assert!(mismatches <= 3, "{} pixels differ", mismatches);
+    }
+
+    #[test]
+    fn test_deep_zoom_engine_selection() {
+        let mut generator = FractalGenerator::new();
+        let mut params = FractalParams {
+            fractal_type: FractalType::Mandelbrot,
+            width: 80,
+            height: 40,
+            zoom: 1.0,
+            center_x: -0.5,
+            center_y: 0.0,
+            max_iterations: 100,
+            ..Default::default()
+        };
+        assert!(!generator.uses_perturbation(&params));
+        assert_eq!(generator.effective_precision(&params), Precision::F64);
+
+        // Double-double covers the range between f64 and perturbation
+        params.zoom = 1e15;
+        assert!(!generator.uses_perturbation(&params));
+        assert_eq!(generator.effective_precision(&params), Precision::DoubleDouble);
+
+        params.zoom = 1e30;
+        assert!(generator.uses_perturbation(&params));
+
+        generator.set_deep_zoom(false);
+        assert!(!generator.uses_perturbation(&params));
+    }
+
+    #[test]
+    fn test_precision_widens_with_zoom() {
+        let mut generator = FractalGenerator::new();
+        generator.set_precision(Precision::F32);
+        let mut params = FractalParams {
+            zoom: 1.0,
+            ..Default::default()
+        };
+        assert_eq!(generator.effective_precision(&params), Precision::F32);
+
+        params.zoom = 1e6;
+        assert_eq!(generator.effective_precision(&params), Precision::F64);
+
+        // Custom formulas only evaluate in f64
+        params.zoom = 1.0;
+        params.fractal_type = FractalType::Custom { equation: "z^2 + c".to_string() };
+        assert_eq!(generator.effective_precision(&params), Precision::F64);
+    }
+
+    #[test]
+    fn test_double_double_arithmetic() {
+        let one = DoubleDouble::from(1.0);
+        let tiny = DoubleDouble::from(1e-20);
+        assert_eq!((one + tiny - one).to_f64(), 1e-20);
+
+        // (1 + 2^-60)^2 keeps the 2^-59 cross term that f64 drops
+        let a = one + DoubleDouble::from(2f64.powi(-60));
+        let square = a * a;
+        assert_eq!(square.hi, 1.0);
+        assert_eq!(square.lo, 2f64.powi(-59));
+        assert_eq!((-a).abs(), a);
+
+        let center: BigFloat = "-1.74972197899999951234567".parse().unwrap();
+        let converted = DoubleDouble::from_bigfloat(&center);
+        assert_eq!(converted.hi, center.to_f64());
+        assert!(converted.lo != 0.0);
+    }
+
+    #[test]
+    fn test_double_double_matches_perturbation() {
+        let mut generator = FractalGenerator::new();
+        generator.set_quality_mode(false);
+        generator.set_adaptive_sampling(false);
+        generator.set_smooth_coloring(false);
+        let center_x: BigFloat = "-0.743643887037158704752191506114774".parse().unwrap();
+        let center_y: BigFloat = "0.131825904205311970493132056385139".parse().unwrap();
+        let params = FractalParams {
+            width: 24,
+            height: 12,
+            zoom: 1e14,
+            center_x: center_x.to_f64(),
+            center_y: center_y.to_f64(),
+            max_iterations: 6000,
+            precise_center: Some((center_x, center_y)),
+            ..Default::default()
+        };
+        assert_eq!(generator.effective_precision(&params), Precision::DoubleDouble);
+
+        let kind = perturbation::PerturbationKind::for_fractal(&generator.registry, &params.fractal_type).unwrap();
+        let deep = perturbation::generate(&generator, &params, kind, params.max_iterations);
+        let direct = generator.generate_samples(&params);
+        assert!(direct.iter().flatten().any(|sample| sample.iterations != direct[0][0].iterations));
+
+        let mismatches = deep.iter().flatten().zip(direct.iter().flatten())
+            .filter(|(a, b)| a.iterations.abs_diff(b.iterations) > 1)
+            .count();
+        assert!(mismatches <= 3, "{} pixels differ", mismatches);
+    }
+
+    #[test]
+    fn test_bigfloat_decimal_round_trip() {
+        use crate::bigfloat::BigFloat;
+
+        let text = "-0.743643887037158704752191506114774";
+        let value: BigFloat = text.parse().unwrap();
+        assert_eq!(value.to_string(), text);
+        assert_eq!(format!("{:.3}", value), "-0.744");
+        assert_eq!(BigFloat::from(0.1).to_string(), "0.1");
+        assert_eq!(BigFloat::from(2.5e30).to_string(), "2.5e30");
+        assert!("1.2.3".parse::<BigFloat>().is_err());
+
+        // Exponents past what any view can use are rejected instead of expanded
+        assert!("1e4000".parse::<BigFloat>().is_ok());
+        assert!("1e5000000000".parse::<BigFloat>().is_err());
+        assert!("1e-3000000".parse::<BigFloat>().is_err());
+        assert!("1e99999999999999999999".parse::<BigFloat>().is_err());
+
+        // Zooming in and back out returns exactly to the start
+        let step = BigFloat::from(1.5);
+        let zoom = &(&BigFloat::from(1.0) * &step) / &step;
+        assert_eq!(zoom, 1.0);
+
+        // Offsets far below f64 resolution still move the center
+        let center: BigFloat = "-0.5".parse().unwrap();
+        let panned = &center.with_precision(256) + &(&BigFloat::from(0.1) / &BigFloat::from(1e40));
+        assert!(panned > center);
+        assert_eq!(panned.to_f64(), -0.5);
+    }
+
+    #[test]
+    fn test_config_keeps_precise_coordinates() {
+        let json = serde_json::to_string(&Config::default()).unwrap();
+        let deep = "-0.743643887037158704752191506114774";
+        let json = json.replace("\"default_center_x\":\"-0.5\"", &format!("\"default_center_x\":\"{}\"", deep));
+        let config: Config = serde_json::from_str(&json).unwrap();
+        assert_eq!(config.fractal.default_center_x.to_string(), deep);
+
+        // Plain numbers are still accepted
+        let json = json.replace("\"default_zoom\":\"1\"", "\"default_zoom\":2.5");
+        let config: Config = serde_json::from_str(&json).unwrap();
+        assert_eq!(config.fractal.default_zoom, 2.5);
+    }
+
+    #[test]
+    fn test_perturbation_uses_precise_center() {
+        use crate::bigfloat::BigFloat;
+
+        let generator = FractalGenerator::new();
+        let center_x: BigFloat = "-1.74972197899999951234567".parse().unwrap();
+        let center_y = BigFloat::from(0.0);
+        let params = FractalParams {
+            width: 16,
+            height: 8,
+            zoom: 1e30,
+            center_x: center_x.to_f64(),
+            center_y: center_y.to_f64(),
+            max_iterations: 2000,
+            precise_center: Some((center_x, center_y)),
+            ..Default::default()
+        };
+        assert!(generator.uses_perturbation(&params));
+
+        // The f64 center is many view widths away from the exact one
+        let rounded = FractalParams { precise_center: None, ..params.clone() };
+        assert_ne!(generator.generate(&params), generator.generate(&rounded));
+    }
+
+    #[test]
+    fn test_polynomial_parsing_and_roots() {
+        let polynomial = Polynomial::parse("z^3 - 1").unwrap();
+        assert_eq!(polynomial.degree(), 3);
+        assert_eq!(polynomial.to_string(), "z^3 - 1");
+        for root in polynomial.roots() {
+            assert!((root.norm() - 1.0).abs() < 1e-9);
+            assert!(polynomial.eval_with_derivative(*root).0.norm() < 1e-9);
+        }
+
+        let expanded = Polynomial::parse("(z - 1)(z + 2i)").unwrap();
+        assert_eq!(expanded.coefficients(), &[Complex::new(0.0, -2.0), Complex::new(-1.0, 2.0), Complex::new(1.0, 0.0)]);
+        assert_eq!(Polynomial::from_roots(&[Complex::new(1.0, 0.0), Complex::new(0.0, -2.0)]).coefficients(), expanded.coefficients());
+
+        assert!(Polynomial::parse("z^2 + c").is_err());
+        assert!(Polynomial::parse("sin(z)").is_err());
+        assert!(Polynomial::parse("3").is_err());
+    }
+
+    #[test]
+    fn test_newton_basins() {
+        let generator = FractalGenerator::new();
+        let polynomial = Polynomial::parse("z^3 - 1").unwrap();
+        let params = FractalParams {
+            fractal_type: FractalType::Newton { polynomial: polynomial.clone() },
+            width: 30,
+            height: 30,
+            zoom: 1.0,
+            center_x: 0.0,
+            center_y: 0.0,
+            max_iterations: 100,
+            ..Default::default()
+        };
+
+        let samples = generator.generate_samples(&params);
+        let basins: std::collections::HashSet<usize> = samples.iter().flatten().filter_map(|sample| sample.root).collect();
+        assert_eq!(basins.len(), 3);
+
+        // Points on the positive real axis converge to the root at 1
+        let root_one = polynomial.roots().iter().position(|root| (root - Complex::new(1.0, 0.0)).norm() < 1e-9).unwrap();
+        let sample = samples[15][25];
+        assert_eq!(sample.root, Some(root_one));
+        assert!(sample.iterations < 10);
+
+        // Each basin gets its own colour
+        let mut renderer = TerminalRenderer::new();
+        let row = vec![(0..3).map(|root| FractalSample { root: Some(root), ..sample }).collect::<Vec<_>>()];
+        let lines = renderer.render_samples_to_text(&row, 3, 1);
+        let colours: std::collections::HashSet<_> = lines[0].spans.iter().map(|span| span.style.fg).collect();
+        assert_eq!(colours.len(), 3);
+    }
+
+    #[test]
+    fn test_buddhabrot_density_accumulates() {
+        let params = FractalParams {
+            fractal_type: FractalType::Buddhabrot,
+            width: 20,
+            height: 20,
+            zoom: 1.0,
+            center_x: -0.5,
+            center_y: 0.0,
+            max_iterations: 50,
+            ..Default::default()
+        };
+        assert!(DensityAccumulator::new(&FractalParams::default()).is_none());
+
+        let mut density = DensityAccumulator::new(&params).unwrap();
+        density.accumulate(20_000);
+        assert_eq!(density.samples(), 20_000);
+        density.accumulate(5_000);
+        assert_eq!(density.samples(), 25_000);
+
+        let samples = density.to_samples();
+        assert_eq!(samples.len(), 20);
+        assert_eq!(samples[0].len(), 20);
+        let colors: Vec<(u8, u8, u8)> = samples.iter().flatten().map(|sample| sample.color.unwrap()).collect();
+        assert!(colors.iter().all(|&(r, g, b)| r == g && g == b));
+        assert!(colors.contains(&(255, 255, 255)));
+
+        // Orbits are symmetric about the real axis, which runs between rows 9 and 10
+        let upper: u32 = samples[..10].iter().flatten().map(|sample| sample.iterations).sum();
+        let lower: u32 = samples[10..].iter().flatten().map(|sample| sample.iterations).sum();
+        assert!((upper as f64 / lower as f64 - 1.0).abs() < 0.2);
+    }
+
+    #[test]
+    fn test_nebulabrot_channels() {
+        let generator = FractalGenerator::new();
+        let params = FractalParams {
+            fractal_type: FractalType::Nebulabrot { limits: [200, 50, 10] },
+            width: 12,
+            height: 12,
+            zoom: 1.0,
+            center_x: -0.5,
+            center_y: 0.0,
+            ..Default::default()
+        };
+
+        let samples = generator.generate_samples(&params);
+        assert_eq!(samples.len(), 12);
+        assert!(samples.iter().flatten().all(|sample| sample.color.is_some()));
+        assert!(samples.iter().flatten().any(|sample| {
+            let (r, g, b) = sample.color.unwrap();
+            r != g || g != b
+        }));
+    }
+
+    #[test]
+    fn test_orbit_trap_distances() {
+        let z = Complex::new(3.0, 4.0);
+        assert_eq!(OrbitTrap::new(TrapShape::Point, 0.0, 0.0).distance(z), 5.0);
+        assert_eq!(OrbitTrap::new(TrapShape::Cross, 1.0, 1.0).distance(z), 2.0);
+        assert_eq!(OrbitTrap::new(TrapShape::Circle { radius: 2.0 }, 0.0, 0.0).distance(z), 3.0);
+        assert!((OrbitTrap::new(TrapShape::Line { angle: 0.0 }, 0.0, 1.0).distance(z) - 3.0).abs() < 1e-12);
+        let diagonal = OrbitTrap::new(TrapShape::Line { angle: std::f64::consts::FRAC_PI_4 }, 0.0, 0.0);
+        assert!(diagonal.distance(Complex::new(2.0, 2.0)) < 1e-12);
+
+        assert_eq!(TrapShape::Circle { radius: 0.5 }.adjust(1), TrapShape::Circle { radius: 0.625 });
+        assert_eq!(TrapShape::Point.next().next().next().next(), TrapShape::Point);
+    }
+
+    #[test]
+    fn test_orbit_trap_coloring() {
+        let mut generator = FractalGenerator::new();
+        let params = FractalParams {
+            width: 16,
+            height: 8,
+            max_iterations: 50,
+            ..Default::default()
+        };
+        assert!(generator.generate_samples(&params).iter().flatten().all(|sample| sample.trap_distance.is_none()));
+
+        generator.set_orbit_trap(Some(OrbitTrap::new(TrapShape::Circle { radius: 0.5 }, 0.0, 0.0)));
+        let samples = generator.generate_samples(&params);
+        assert!(samples.iter().flatten().all(|sample| sample.trap_distance.is_some_and(|distance| distance >= 0.0)));
+
+        // The trap replaces iteration bands in the rendered output
+        let mut renderer = TerminalRenderer::new();
+        let plain = FractalSample::new(40);
+        let trapped = FractalSample { trap_distance: Some(0.0), ..plain };
+        let lines = renderer.render_samples_to_text(&[vec![plain, trapped]], 2, 1);
+        assert_ne!(lines[0].spans[0].style.fg, lines[0].spans[1].style.fg);
+
+        // Deep zooms stay on the direct kernels while a trap is set
+        let deep = FractalParams { zoom: 1e30, ..params };
+        assert!(!generator.uses_perturbation(&deep));
+    }
+
+    #[test]
+    fn test_config_orbit_trap_defaults() {
+        let json = serde_json::to_string(&Config::default()).unwrap();
+        let config: Config = serde_json::from_str(&json).unwrap();
+        assert_eq!(config.fractal.orbit_trap.active_trap(), None);
+
+        // Configs written before orbit traps existed still load
+        let old = json.replace(&format!(",\"orbit_trap\":{}", serde_json::to_string(&config.fractal.orbit_trap).unwrap()), "");
+        assert!(!old.contains("orbit_trap"));
+        assert!(serde_json::from_str::<Config>(&old).is_ok());
+
+        let example: Config = serde_json::from_str(include_str!("../config.example.json")).unwrap();
+        assert_eq!(example.fractal.orbit_trap.trap.shape, TrapShape::Circle { radius: 0.5 });
+    }
+
+    #[test]
+    fn test_distance_estimation() {
+        let mut generator = FractalGenerator::new();
+        // Row 1 of a two-row image is the real axis, sampled every 0.125 from -2
+        let params = FractalParams {
+            width: 32,
+            height: 2,
+            center_x: 0.0,
+            max_iterations: 200,
+            ..Default::default()
+        };
+        assert!(generator.generate_samples(&params).iter().flatten().all(|sample| sample.distance.is_none()));
+
+        generator.set_distance_estimation(true);
+        let axis = generator.generate_samples(&params)[1].clone();
+        // Inside the set there is no exterior distance
+        assert!(axis[16].distance.is_none());
+
+        // Right of the cusp at 0.25 the estimate grows with the true distance,
+        // and is within the usual factor of it (1.625 = 13 pixels at x = 1.875)
+        let distances: Vec<f64> = axis[19..].iter().map(|sample| sample.distance.unwrap()).collect();
+        assert!(distances.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(distances[distances.len() - 1] > 13.0 / 4.0 && distances[distances.len() - 1] < 26.0);
+
+        for fractal_type in [FractalType::Julia { c: Complex::new(-0.7, 0.27) }, FractalType::Multibrot { power: 3.0 }] {
+            let samples = generator.generate_samples(&FractalParams { fractal_type, ..params.clone() });
+            assert!(samples.iter().flatten().any(|sample| sample.distance.is_some_and(|distance| distance > 0.0)));
+        }
+
+        // The perturbation engine tracks the derivative against the reference orbit too
+        generator.set_deep_zoom(true);
+        let deep = FractalParams { zoom: 1e30, center_x: -0.75, center_y: 0.1, ..params.clone() };
+        assert!(generator.uses_perturbation(&deep));
+        assert!(generator.generate_samples(&deep).iter().flatten().any(|sample| sample.distance.is_some()));
+
+        // DE mode highlights pixels within the threshold of the boundary
+        let mut renderer = TerminalRenderer::new();
+        renderer.set_distance_mode(true);
+        let near = FractalSample { distance: Some(0.5), ..FractalSample::new(5) };
+        let far = FractalSample { distance: Some(20.0), ..FractalSample::new(5) };
+        let lines = renderer.render_samples_to_text(&[vec![near, far]], 2, 1);
+        assert_eq!(lines[0].spans[0].content, "█");
+        assert_eq!(lines[0].spans[1].content, " ");
+    }
+
+    #[test]
+    fn test_interior_detection() {
+        let mut generator = FractalGenerator::new();
+        // A one-pixel view samples the top-left corner, 2 / zoom from the center
+        let pixel = |generator: &FractalGenerator, fractal_type: FractalType, re: f64, im: f64| {
+            let params = FractalParams {
+                fractal_type,
+                width: 1,
+                height: 1,
+                center_x: re + 2.0,
+                center_y: im + 2.0,
+                max_iterations: 500,
+                ..Default::default()
+            };
+            generator.generate_samples(&params)[0][0]
+        };
+
+        assert_eq!(pixel(&generator, FractalType::Mandelbrot, 0.0, 0.0).period, Some(1));
+        assert_eq!(pixel(&generator, FractalType::Mandelbrot, -1.0, 0.0).period, Some(2));
+        // Center of the period-3 bulb is only found by periodicity checking
+        generator.set_cardioid_check(false);
+        assert_eq!(pixel(&generator, FractalType::Mandelbrot, -0.1226, 0.7449).period, Some(3));
+        // Julia orbit of 0 under z^2 - 1 is the 2-cycle 0, -1
+        let julia = FractalType::Julia { c: Complex::new(-1.0, 0.0) };
+        assert_eq!(pixel(&generator, julia, 0.0, 0.0).period, Some(2));
+
+        generator.set_periodicity_check(false);
+        assert_eq!(pixel(&generator, FractalType::Mandelbrot, -0.1226, 0.7449).period, None);
+
+        // The shortcuts must not change which pixels escape, or when
+        let params = FractalParams { max_iterations: 300, ..Default::default() };
+        for fractal_type in [FractalType::Mandelbrot, FractalType::Tricorn, FractalType::BurningShip, FractalType::Multibrot { power: 3.0 }] {
+            generator.set_periodicity_check(false);
+            generator.set_cardioid_check(false);
+            let plain = generator.generate(&FractalParams { fractal_type: fractal_type.clone(), ..params.clone() });
+            generator.set_periodicity_check(true);
+            generator.set_cardioid_check(true);
+            assert_eq!(generator.generate(&FractalParams { fractal_type, ..params.clone() }), plain);
+        }
+
+        // A trap sees the whole interior orbit, with or without the shortcuts
+        generator.set_orbit_trap(Some(OrbitTrap::new(TrapShape::Circle { radius: 0.5 }, 0.0, 0.0)));
+        generator.set_periodicity_check(false);
+        let full = generator.generate_samples(&params);
+        generator.set_periodicity_check(true);
+        assert_eq!(generator.generate_samples(&params), full);
+        generator.set_orbit_trap(None);
+
+        // Period colouring gives each cycle length its own colour
+        let mut renderer = TerminalRenderer::new();
+        renderer.set_period_coloring(true);
+        let one = FractalSample { period: Some(1), ..FractalSample::new(500) };
+        let two = FractalSample { period: Some(2), ..FractalSample::new(500) };
+        let lines = renderer.render_samples_to_text(&[vec![one, two]], 2, 1);
+        assert_ne!(lines[0].spans[0].style.fg, lines[0].spans[1].style.fg);
+    }
+
+    #[test]
+    fn test_julia_mode() {
+        let generator = FractalGenerator::new();
+        let c = Complex::new(-0.8, 0.156);
+        let julia_of = |fractal_type: FractalType| FractalType::JuliaOf { parameter_plane: Box::new(fractal_type), c };
+        let render = |fractal_type: FractalType| {
+            generator.generate(&FractalParams { fractal_type, center_x: 0.0, max_iterations: 200, ..Default::default() })
+        };
+
+        // Every quadratic variant agrees with the dedicated Julia kernel
+        let julia = render(FractalType::Julia { c });
+        assert_eq!(render(julia_of(FractalType::Mandelbrot)), julia);
+        assert_eq!(render(julia_of(FractalType::Multibrot { power: 2.0 })), julia);
+        assert_eq!(render(julia_of(FractalType::Custom { equation: "z^2 + c".to_string() })), julia);
+
+        for fractal_type in [FractalType::BurningShip, FractalType::Tricorn, FractalType::Multibrot { power: 3.0 }] {
+            let fractal = generator.resolve(&julia_of(fractal_type.clone())).unwrap();
+            assert!(fractal.name().ends_with("Julia"));
+            assert_eq!(fractal.fractal_type(), julia_of(fractal_type.clone()));
+            assert!(fractal.julia(c).is_none());
+            assert_ne!(render(julia_of(fractal_type.clone())), render(fractal_type));
+        }
+
+        // Newton and the density renderers have no parameter c to fix
+        assert!(generator.resolve(&julia_of(FractalType::Buddhabrot)).is_none());
+        assert!(generator.resolve(&julia_of(FractalType::Julia { c })).is_none());
+    }
+
+    #[test]
+    fn test_split_view_julia_pane() {
+        let mut app = App::new();
+        app.regenerate_fractal();
+        assert!(app.julia_data.is_empty());
+
+        // With the crosshair in the middle, the pane is the Julia set for the view center
+        app.split_view = true;
+        app.regenerate_fractal();
+        let params = FractalParams {
+            fractal_type: FractalType::Julia { c: Complex::new(-0.5, 0.0) },
+            width: 80,
+            height: 24,
+            center_x: 0.0,
+            ..Default::default()
+        };
+        assert_eq!(app.julia_data, app.fractal_generator.generate_samples(&params));
+
+        app.current_fractal_type = FractalType::Buddhabrot;
+        app.regenerate_fractal();
+        assert!(app.julia_data.is_empty());
+    }
+
+    #[test]
+    fn test_mouse_navigation() {
+        use crossterm::event::{KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+        use ratatui::layout::Rect;
+
+        let mouse = |kind, column, row| MouseEvent { kind, column, row, modifiers: KeyModifiers::NONE };
+        let mut app = App::new();
+        // An 80x24 image inside the border, so cell (61, 13) is pixel (60, 12)
+        app.fractal_display_area = Some(Rect { x: 0, y: 0, width: 82, height: 26 });
+        app.regenerate_fractal();
+
+        // Pixel 60 of 80 is a quarter of the 4-unit span right of the center
+        app.handle_mouse_event(mouse(MouseEventKind::Down(MouseButton::Left), 61, 13));
+        app.handle_mouse_event(mouse(MouseEventKind::Up(MouseButton::Left), 61, 13));
+        assert!((app.center_x.to_f64() - 0.5).abs() < 1e-12);
+        assert!(app.center_y.to_f64().abs() < 1e-12);
+
+        // Scrolling keeps the point under the cursor in place
+        app.handle_mouse_event(mouse(MouseEventKind::ScrollUp, 21, 7));
+        assert!((app.zoom_factor.to_f64() - 1.5).abs() < 1e-12);
+        assert!((app.center_x.to_f64() - (-0.5 + 1.0 / 1.5)).abs() < 1e-12);
+        assert!((app.center_y.to_f64() - (-1.0 + 1.0 / 1.5)).abs() < 1e-12);
+
+        // Clicks on the border do nothing
+        let center_x = app.center_x.to_f64();
+        app.handle_mouse_event(mouse(MouseEventKind::Down(MouseButton::Left), 0, 13));
+        app.handle_mouse_event(mouse(MouseEventKind::Up(MouseButton::Left), 0, 13));
+        assert_eq!(app.center_x.to_f64(), center_x);
+
+        // Right-dragging by 8 cells moves the image 8 pixels with the pointer
+        app.handle_mouse_event(mouse(MouseEventKind::Down(MouseButton::Right), 41, 13));
+        app.handle_mouse_event(mouse(MouseEventKind::Drag(MouseButton::Right), 49, 13));
+        app.handle_mouse_event(mouse(MouseEventKind::Up(MouseButton::Right), 49, 13));
+        assert!((app.center_x.to_f64() - (center_x - 0.4 / 1.5)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_box_zoom() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+        use ratatui::layout::Rect;
+
+        let mouse = |kind, column, row| MouseEvent { kind, column, row, modifiers: KeyModifiers::NONE };
+        let mut app = App::new();
+        app.fractal_display_area = Some(Rect { x: 0, y: 0, width: 82, height: 26 });
+        app.regenerate_fractal();
+
+        // Pixels 20..=59 by 9..=14: half the width but a quarter of the height,
+        // so the height is widened and the zoom only doubles
+        app.handle_mouse_event(mouse(MouseEventKind::Down(MouseButton::Left), 21, 10));
+        app.handle_mouse_event(mouse(MouseEventKind::Drag(MouseButton::Left), 60, 15));
+        assert_eq!(app.box_selection.unwrap().cursor, (59, 14));
+        app.handle_mouse_event(mouse(MouseEventKind::Up(MouseButton::Left), 60, 15));
+        assert!(app.box_selection.is_none());
+        assert!((app.zoom_factor.to_f64() - 2.0).abs() < 1e-12);
+        assert!((app.center_x.to_f64() - (-0.5 - 0.025)).abs() < 1e-12);
+        assert!((app.center_y.to_f64() - (-1.0 / 12.0)).abs() < 1e-12);
+
+        // The same box marked from the keyboard, starting at pixel (40, 12)
+        let mut app = App::new();
+        app.fractal_display_area = Some(Rect { x: 0, y: 0, width: 82, height: 26 });
+        app.regenerate_fractal();
+        let keys = [
+            vec![KeyCode::Char('b')],
+            vec![KeyCode::Left; 20],
+            vec![KeyCode::Up; 3],
+            vec![KeyCode::Enter],
+            vec![KeyCode::Right; 39],
+            vec![KeyCode::Down; 5],
+            vec![KeyCode::Enter],
+        ];
+        for code in keys.concat() {
+            app.handle_key_event(KeyEvent::new(code, KeyModifiers::NONE));
+        }
+        assert!(app.box_selection.is_none());
+        assert!((app.zoom_factor.to_f64() - 2.0).abs() < 1e-12);
+        assert!((app.center_x.to_f64() - (-0.5 - 0.025)).abs() < 1e-12);
+        assert!(!app.should_quit);
+    }
+
+    #[test]
+    fn test_navigation_history() {
+        let view = |zoom: f64| ViewState {
+            fractal_type: FractalType::Mandelbrot,
+            equation: "z^2 + c".to_string(),
+            center_x: BigFloat::from(-0.5),
+            center_y: BigFloat::from(0.0),
+            zoom_factor: BigFloat::from(zoom),
+            max_iterations: 256,
+        };
+
+        let mut history = NavigationHistory::new(3);
+        assert!(history.undo().is_none());
+        for zoom in [1.0, 1.0, 2.0, 3.0] {
+            history.record(view(zoom));
+        }
+        // Repeats are not new steps
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.undo(), Some(&view(2.0)));
+        assert_eq!(history.undo(), Some(&view(1.0)));
+        assert!(history.undo().is_none());
+        assert_eq!(history.redo(), Some(&view(2.0)));
+
+        // A new view after an undo drops the redo branch
+        history.record(view(4.0));
+        assert!(history.redo().is_none());
+        assert_eq!(history.entries().cloned().collect::<Vec<_>>(), vec![view(1.0), view(2.0), view(4.0)]);
+
+        // Full history forgets the oldest view
+        history.record(view(5.0));
+        assert_eq!(history.entries().next(), Some(&view(2.0)));
+        assert_eq!(history.jump(0), Some(&view(2.0)));
+        assert_eq!(history.position(), 0);
+        assert!(history.jump(3).is_none());
+
+        // The app records each view change and undo/redo restore them
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        let mut app = App::new();
+        app.regenerate_fractal();
+        let press = |app: &mut App, code| app.handle_key_event(KeyEvent::new(code, KeyModifiers::NONE));
+        press(&mut app, KeyCode::Char('+'));
+        press(&mut app, KeyCode::Char('i'));
+        assert_eq!(app.history.len(), 3);
+        press(&mut app, KeyCode::Char('u'));
+        press(&mut app, KeyCode::Char('u'));
+        assert_eq!(app.zoom_factor, 1.0);
+        assert_eq!(app.max_iterations, 256);
+        press(&mut app, KeyCode::Char('U'));
+        assert_eq!(app.zoom_factor, 1.5);
+        assert_eq!(app.max_iterations, 256);
+        assert_eq!(app.history.len(), 3);
+    }
+
+    #[test]
+    fn test_bookmarks() {
+        use crate::bigfloat::BigFloat;
+        use crate::polynomial::Polynomial;
+
+        let mut bookmarks = Bookmarks::default();
+        assert_eq!(bookmarks.len(), 5);
+        assert_eq!(bookmarks.get("seahorse valley").unwrap().view.center_x, -0.8);
+
+        let view = |fractal_type: FractalType| ViewState {
+            fractal_type,
+            equation: String::new(),
+            center_x: "-0.743643887037158704752191506114774".parse().unwrap(),
+            center_y: BigFloat::from(0.131825904),
+            zoom_factor: BigFloat::from(1e6),
+            max_iterations: 5000,
+        };
+        let deep = Bookmark { name: " Deep ".to_string(), view: view(FractalType::Mandelbrot), palette: "fire".to_string() };
+        bookmarks.add(deep).unwrap();
+        assert_eq!(bookmarks.get("deep").unwrap().name, "Deep");
+        assert!(bookmarks.add(Bookmark { name: "  ".to_string(), ..bookmarks.bookmarks[0].clone() }).is_err());
+        assert!(bookmarks.rename(0, "deep").is_err());
+        bookmarks.rename(0, "Spiral Arm").unwrap();
+        assert_eq!(bookmarks.remove(1).unwrap().name, "Seahorse Valley");
+        assert!(bookmarks.remove(10).is_none());
+
+        // Every kind of fractal survives the trip through the file, digits and all
+        for fractal_type in [
+            FractalType::Newton { polynomial: Polynomial::parse("(z - 1)(z + i)(z^2 + 2.5)").unwrap() },
+            FractalType::JuliaOf { parameter_plane: Box::new(FractalType::Multibrot { power: 3.0 }), c: Complex::new(-0.8, 0.156) },
+            FractalType::Nebulabrot { limits: [5000, 500, 50] },
+        ] {
+            let name = format!("{:?}", fractal_type);
+            bookmarks.add(Bookmark { name, view: view(fractal_type), palette: "default".to_string() }).unwrap();
+        }
+        let path = std::env::temp_dir().join(format!("fractal-bookmarks-{}.json", std::process::id()));
+        bookmarks.save_to_file(&path).unwrap();
+        let loaded = Bookmarks::load_from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, bookmarks);
+        assert_eq!(loaded.get("deep").unwrap().view.center_x.to_string(), "-0.743643887037158704752191506114774");
+
+        // A missing file gives the shipped set
+        assert_eq!(Bookmarks::load(&path), Ok(Bookmarks::default()));
+
+        // A broken file is reported and never overwritten
+        let directory = std::env::temp_dir().join(format!("fractal-broken-bookmarks-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let broken = directory.join(bookmark::BOOKMARKS_FILE_NAME);
+        std::fs::write(&broken, "{ \"bookmarks\": [ oops").unwrap();
+        assert!(Bookmarks::load(&broken).is_err());
+        let mut app = App::with_config(Config::default(), directory.join("config.json"));
+        assert!(app.bookmarks_error.is_some());
+        assert!(app.status_message.contains("will not be saved"));
+        app.execute_command(Command::Bookmark("Here".to_string()));
+        assert!(app.status_message.contains("Not saved"));
+        assert_eq!(std::fs::read_to_string(&broken).unwrap(), "{ \"bookmarks\": [ oops");
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(bookmark::bookmarks_path_for("conf/config.json"), std::path::Path::new("conf/bookmarks.json"));
+    }
+
+    #[test]
+    fn test_command_parsing() {
+        use crate::bigfloat::BigFloat;
+
+        assert_eq!(
+            command::parse(":center -0.743643887 0.131825904").unwrap(),
+            Command::Center { x: "-0.743643887".parse().unwrap(), y: "0.131825904".parse().unwrap() }
+        );
+        assert_eq!(command::parse("zoom 1e6").unwrap(), Command::Zoom(BigFloat::from(1e6)));
+        assert_eq!(command::parse("iter 5000").unwrap(), Command::Iterations(5000));
+        assert_eq!(command::parse("export out.png 200x60").unwrap(),
+            Command::Export { path: "out.png".into(), size: Some((200, 60)) });
+        assert_eq!(command::parse("bookmark Deep Spiral").unwrap(), Command::Bookmark("Deep Spiral".to_string()));
+        assert!(command::parse("zoom -2").is_err());
+        assert!(command::parse("iter lots").is_err());
+        assert!(command::parse("export out.png 200by60").is_err());
+        assert!(command::parse("iter 4000000000").is_err());
+        assert!(command::parse("iter 1000001").is_err());
+        assert_eq!(command::parse("iter 1000000").unwrap(), Command::Iterations(command::MAX_ITERATIONS));
+        assert!(command::parse("export out.png 100000x100000").is_err());
+        assert!(command::parse_size("8192x8192").is_err());
+        assert_eq!(command::parse_size("8192x2048"), Ok((8192, 2048)));
+        assert!(command::parse_size("9000x10").is_err());
+        assert!(command::parse("warp 9").is_err());
+
+        let arguments = |text: &str| text.split_whitespace().map(String::from).collect::<Vec<_>>();
+        assert_eq!(command::fractal_type("julia", &arguments("-0.8 0.156")).unwrap(),
+            Some(FractalType::Julia { c: Complex::new(-0.8, 0.156) }));
+        assert!(command::fractal_type("julia", &arguments("-0.8")).is_err());
+        assert_eq!(command::fractal_type("burning-ship", &[]).unwrap(), None);
+
+        // One match completes with a trailing space; several complete their common prefix
+        let fractals = vec!["Burning Ship".to_string(), "Buddhabrot".to_string()];
+        assert_eq!(command::complete("ce", &fractals, &[]), ("center ".to_string(), Vec::new()));
+        assert_eq!(command::complete("fractal bu", &fractals, &[]),
+            ("fractal bu".to_string(), vec!["buddhabrot".to_string(), "burning-ship".to_string()]));
+        assert_eq!(command::complete("fractal bur", &fractals, &[]).0, "fractal burning-ship ");
+        let palettes = vec!["fire".to_string(), "forest".to_string()];
+        assert_eq!(command::complete("palette f", &fractals, &palettes).0, "palette f");
+        assert_eq!(command::complete("palette fi", &fractals, &palettes).0, "palette fire ");
+    }
+
+    #[test]
+    fn test_command_mode() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+        let mut app = App::new();
+        let type_line = |app: &mut App, line: &str| {
+            app.handle_key_event(KeyEvent::new(KeyCode::Char(':'), KeyModifiers::NONE));
+            for c in line.chars() {
+                app.handle_key_event(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+            }
+            app.handle_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        };
+        type_line(&mut app, "zoom 1e6");
+        assert_eq!(app.input_mode, app::InputMode::Normal);
+        assert_eq!(app.zoom_factor.to_f64(), 1e6);
+        type_line(&mut app, "iter 300");
+        assert_eq!(app.max_iterations, 300);
+        type_line(&mut app, "fractal julia -0.8 0.156");
+        assert_eq!(app.current_fractal_type, FractalType::Julia { c: Complex::new(-0.8, 0.156) });
+
+        type_line(&mut app, "fractal burning-ship");
+        assert_eq!(app.current_fractal_type, FractalType::BurningShip);
+        type_line(&mut app, "zoom nothing");
+        assert!(app.status_message.starts_with("Command failed"));
+
+        // Up recalls earlier lines, newest first
+        app.handle_key_event(KeyEvent::new(KeyCode::Char(':'), KeyModifiers::NONE));
+        app.handle_key_event(KeyEvent::new(KeyCode::Up, KeyModifiers::NONE));
+        assert_eq!(app.command_line, "zoom nothing");
+        app.handle_key_event(KeyEvent::new(KeyCode::Up, KeyModifiers::NONE));
+        app.handle_key_event(KeyEvent::new(KeyCode::Up, KeyModifiers::NONE));
+        assert_eq!(app.command_line, "fractal julia -0.8 0.156");
+        app.handle_key_event(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+        assert_eq!(app.input_mode, app::InputMode::Normal);
+        assert!(app.command_line.is_empty());
+
+        // The increase key never undoes a count typed above its old ceiling. Zoomed
+        // far out, every Mandelbrot pixel escapes or is caught early, so the renders stay quick.
+        type_line(&mut app, "fractal mandelbrot");
+        type_line(&mut app, "zoom 0.001");
+        type_line(&mut app, "iter 100000");
+        app.handle_key_event(KeyEvent::new(KeyCode::Char('i'), KeyModifiers::NONE));
+        assert_eq!(app.max_iterations, 100_010);
+        type_line(&mut app, "iter 1000000");
+        app.handle_key_event(KeyEvent::new(KeyCode::Char('i'), KeyModifiers::NONE));
+        assert_eq!(app.max_iterations, command::MAX_ITERATIONS);
+    }
+
+    #[test]
+    fn test_export_formats() {
+        let generator = FractalGenerator::new();
+        let params = FractalParams { width: 12, height: 5, ..Default::default() };
+        let samples = generator.generate_samples(&params);
+        let renderer = TerminalRenderer::new();
+
+        assert_eq!(ExportFormat::from_path("out.PNG"), ExportFormat::Png);
+        assert_eq!(ExportFormat::from_path("out.ans"), ExportFormat::Ansi);
+        assert_eq!(ExportFormat::from_path("out"), ExportFormat::Text);
+        assert_eq!("ansi".parse::<ExportFormat>().unwrap(), ExportFormat::Ansi);
+
+        let text = String::from_utf8(export::encode(&renderer, &samples, ExportFormat::Text).unwrap()).unwrap();
+        assert_eq!(text.lines().count(), 5);
+        assert!(!text.contains('\x1b'));
+        let ansi = String::from_utf8(export::encode(&renderer, &samples, ExportFormat::Ansi).unwrap()).unwrap();
+        assert!(ansi.contains("\x1b[38;2;"));
+        assert_eq!(ansi.lines().count(), 5);
+        let png = export::encode(&renderer, &samples, ExportFormat::Png).unwrap();
+        assert_eq!(&png[..4], b"\x89PNG");
+    }
+
+    #[test]
+    fn test_keymap() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        let keymap = Keymap::default();
+        assert_eq!(keymap.action_for(key(KeyCode::Char('+'))), Some(Action::ZoomIn));
+        // Terminals may or may not report Shift with a typed capital
+        assert_eq!(keymap.action_for(KeyEvent::new(KeyCode::Char('U'), KeyModifiers::SHIFT)), Some(Action::Redo));
+        assert_eq!(keymap.action_for(KeyEvent::new(KeyCode::Up, KeyModifiers::SHIFT)), Some(Action::CrosshairUp));
+        assert_eq!(keymap.action_for(key(KeyCode::Up)), Some(Action::PanUp));
+        assert_eq!(keymap.action_for(key(KeyCode::Char('x'))), None);
+        assert_eq!(keymap.keys_label(Action::ToggleHelp), "h/F1");
+        assert!(Action::all().all(|action| Action::from_name(action.name()) == Some(action)));
+
+        assert_eq!("Ctrl+s".parse::<KeyChord>().unwrap(), KeyChord::new(KeyCode::Char('s'), KeyModifiers::CONTROL));
+        assert_eq!("Shift+a".parse::<KeyChord>().unwrap(), KeyChord::new(KeyCode::Char('A'), KeyModifiers::NONE));
+        assert_eq!("Alt++".parse::<KeyChord>().unwrap(), KeyChord::new(KeyCode::Char('+'), KeyModifiers::ALT));
+        assert_eq!("f12".parse::<KeyChord>().unwrap().to_string(), "F12");
+        assert!("Hyper+x".parse::<KeyChord>().is_err());
+        assert!("F13".parse::<KeyChord>().is_err());
+
+        // Remapped keys, with the legacy zoom keys replacing the first zoom chord
+        let mut config = Config::default();
+        config.controls.zoom_in_key = "z".to_string();
+        config.controls.keys.insert("quit".to_string(), vec!["Ctrl+q".to_string()]);
+        config.controls.keys.insert("pan_left".to_string(), vec!["a".to_string(), "Left".to_string()]);
+        let keymap = Keymap::from_config(&config.controls).unwrap();
+        assert_eq!(keymap.keys_label(Action::ZoomIn), "z/=");
+        assert_eq!(keymap.action_for(key(KeyCode::Char('q'))), None);
+        assert_eq!(keymap.action_for(KeyEvent::new(KeyCode::Char('q'), KeyModifiers::CONTROL)), Some(Action::Quit));
+        assert!(keymap.controls_text().contains("a/← : Pan Left"));
+        assert!(keymap.help_text().contains("Ctrl+q - Quit application"));
+        assert!(config.validate().is_ok());
+
+        config.controls.keys.insert("history".to_string(), vec!["a".to_string()]);
+        assert!(config.validate().unwrap_err().contains("bound to both"));
+        config.controls.keys.clear();
+        config.controls.keys.insert("warp".to_string(), vec!["w".to_string()]);
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_configured_steps() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+        let mut app = App::new();
+        app.config.fractal.zoom_step = 2.0;
+        app.config.controls.zoom_speed = 2.0;
+        app.config.fractal.pan_step = 0.25;
+        app.config.controls.pan_speed = 2.0;
+        app.config.controls.iteration_step = 100;
+        app.config.controls.keys.insert("zoom_in".to_string(), vec!["z".to_string()]);
+        app.keymap = Keymap::from_config(&app.config.controls).unwrap();
+
+        app.handle_key_event(KeyEvent::new(KeyCode::Char('z'), KeyModifiers::NONE));
+        assert_eq!(app.zoom_factor.to_f64(), 4.0);
+        app.handle_key_event(KeyEvent::new(KeyCode::Char('+'), KeyModifiers::NONE));
+        assert_eq!(app.zoom_factor.to_f64(), 4.0);
+        app.handle_key_event(KeyEvent::new(KeyCode::Right, KeyModifiers::NONE));
+        assert!((app.center_x.to_f64() - (-0.5 + 0.5 / 4.0)).abs() < 1e-12);
+        app.handle_key_event(KeyEvent::new(KeyCode::Char('i'), KeyModifiers::NONE));
+        assert_eq!(app.max_iterations, 356);
+
+        // A step that skipped validation saturates at the limit instead of overflowing
+        app.config.controls.iteration_step = u32::MAX;
+        app.zoom_factor = BigFloat::from(0.001);
+        app.handle_key_event(KeyEvent::new(KeyCode::Char('i'), KeyModifiers::NONE));
+        assert_eq!(app.max_iterations, command::MAX_ITERATIONS);
+    }
+
+    #[test]
+    fn test_cli_arguments() {
+        use crate::bigfloat::BigFloat;
+        use clap::Parser;
+
+        let cli = Cli::try_parse_from([
+            "fractal-generator", "--fractal", "julia -0.8 0.156", "--center", "-0.743643887,0.131825904",
+            "--zoom", "1e6", "--iterations", "5000", "--palette", "fire", "--mode", "auto", "--no-color", "--ascii",
+        ]).unwrap();
+        assert_eq!(cli.view.center, Some(("-0.743643887".parse().unwrap(), "0.131825904".parse().unwrap())));
+        assert_eq!(cli.mode, cli::StartMode::Auto);
+        assert!(Cli::try_parse_from(["fractal-generator", "--zoom", "-2"]).is_err());
+        assert!(Cli::try_parse_from(["fractal-generator", "--center", "0.5"]).is_err());
+        assert!(Cli::try_parse_from(["fractal-generator", "--iterations", "0"]).is_err());
+        assert!(Cli::try_parse_from(["fractal-generator", "--iterations", "4000000000"]).is_err());
+
+        let mut config = Config::default();
+        cli.view.apply_to_config(&mut config);
+        assert_eq!(config.display.color_scheme, "fire");
+        assert!(!config.display.use_colors && !config.display.use_unicode);
+
+        // The config drives the app, the generator and the renderer
+        config.fractal.default_max_iterations = 700;
+        config.display.super_sampling = true;
+        let mut app = App::with_config(config, "some/dir/config.json");
+        assert_eq!(app.max_iterations, 700);
+        assert!(app.fractal_generator.super_sampling && app.renderer.super_sampling);
+        assert!(!app.renderer.use_colors);
+        assert_eq!(app.bookmarks_path, std::path::Path::new("some/dir/bookmarks.json"));
+
+        // The fractal brings its framing; the center and zoom given override it
+        cli.view.apply_to_app(&mut app).unwrap();
+        assert_eq!(app.current_fractal_type, FractalType::Julia { c: Complex::new(-0.8, 0.156) });
+        assert_eq!(app.zoom_factor, BigFloat::from(1e6));
+        assert_eq!(app.max_iterations, 5000);
+
+        // The shipped example loads; a missing or invalid file is an error
+        let example = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("config.example.json");
+        let cli = Cli::try_parse_from(["fractal-generator", "--config", example.to_str().unwrap()]).unwrap();
+        assert!(cli.build_app().is_ok());
+        assert!(cli::load_config(Some(std::path::Path::new("no/such/config.json"))).is_err());
+        let invalid = std::env::temp_dir().join(format!("fractal-config-{}.json", std::process::id()));
+        let mut config = Config::default();
+        config.fractal.zoom_step = 0.5;
+        config.save_to_file(&invalid).unwrap();
+        let cli = Cli::try_parse_from(["fractal-generator", "--config", invalid.to_str().unwrap()]).unwrap();
+        let error = cli.build_app().err().unwrap();
+        std::fs::remove_file(&invalid).unwrap();
+        assert!(error.contains("Zoom step"));
+    }
+
+    #[test]
+    fn test_render_subcommand() {
+        use clap::Parser;
+
+        let cli = Cli::try_parse_from([
+            "fractal-generator", "render", "--fractal", "mandelbrot", "--center", "-0.5,0", "--zoom", "1",
+            "--size", "40x12", "--ascii",
+        ]).unwrap();
+        let Some(cli::CliCommand::Render(render)) = &cli.command else { panic!("expected the render subcommand") };
+        assert_eq!(render.size, Some((40, 12)));
+        assert_eq!(render.format(), ExportFormat::Text);
+
+        let (config, _) = cli.load_config_with(&render.view).unwrap();
+        let text = String::from_utf8(render.render(&config).unwrap()).unwrap();
+        assert_eq!(text.lines().count(), 12);
+        assert!(text.lines().all(|line| line.chars().count() == 40));
+        assert!(text.is_ascii());
+
+        // The format follows the output file unless given
+        let cli = Cli::try_parse_from(["fractal-generator", "render", "--size", "16x8", "-o", "banner.png"]).unwrap();
+        let Some(cli::CliCommand::Render(render)) = &cli.command else { panic!("expected the render subcommand") };
+        assert_eq!(render.format(), ExportFormat::Png);
+        assert_eq!(&render.render(&Config::default()).unwrap()[..4], b"\x89PNG");
+        let cli = Cli::try_parse_from(["fractal-generator", "render", "--format", "ansi", "-o", "banner.txt"]).unwrap();
+        let Some(cli::CliCommand::Render(render)) = &cli.command else { panic!("expected the render subcommand") };
+        assert_eq!(render.format(), ExportFormat::Ansi);
+
+        assert!(Cli::try_parse_from(["fractal-generator", "render", "--size", "40"]).is_err());
+        assert!(Cli::try_parse_from(["fractal-generator", "render", "--size", "100000x100000"]).is_err());
+        assert!(Cli::try_parse_from(["fractal-generator", "render", "--format", "gif"]).is_err());
+
+        // Options belong after `render`; before it they would be silently ignored
+        assert!(Cli::try_parse_from(["fractal-generator", "--zoom", "1e6", "render", "-o", "x.txt"]).is_err());
+        assert!(Cli::try_parse_from(["fractal-generator", "--mode", "auto", "render"]).is_err());
+        let cli = Cli::try_parse_from(["fractal-generator", "render", "--config", "view.json", "--zoom", "1e6"]).unwrap();
+        assert_eq!(cli.config, Some(std::path::PathBuf::from("view.json")));
+        let Some(cli::CliCommand::Render(render)) = &cli.command else { panic!("expected the render subcommand") };
+        assert_eq!(render.view.zoom, Some(BigFloat::from(1e6)));
+    }
+
+    #[test]
+    fn test_truecolor_palettes() {
+        use ratatui::style::Color;
+
+        let grayscale = Palette::new("grayscale", &[(1.0, (255, 255, 255)), (0.0, (0, 0, 0))], (0, 0, 0));
+        assert_eq!(grayscale.color_at(0.0), (0, 0, 0));
+        assert_eq!(grayscale.color_at(0.5), (128, 128, 128));
+        assert_eq!(grayscale.color_at(2.0), (255, 255, 255));
+
+        let mut renderer = TerminalRenderer::new();
+        renderer.set_max_iterations(100);
+        assert!(renderer.set_palette("sunset").unwrap_err().contains("twilight"));
+        renderer.set_palette("Fire").unwrap();
+        assert_eq!(renderer.palette_name(), "fire");
+        let escaped = FractalSample { smooth: Some(10.5), ..FractalSample::new(10) };
+        assert!(matches!(renderer.sample_to_char_and_color(&escaped), ('█', Color::Rgb(..))));
+        assert_eq!(renderer.sample_to_char_and_color(&FractalSample::new(100)).1, Color::Rgb(0, 0, 0));
+        renderer.set_palette("default").unwrap();
+        assert!(!matches!(renderer.sample_to_char_and_color(&escaped).1, Color::Rgb(..)));
+
+        let mut app = App::new();
+        app.execute_command(Command::Palette("ocean".to_string()));
+        assert_eq!(app.config.display.color_scheme, "ocean");
+        app.execute_command(Command::Palette("sunset".to_string()));
+        assert_eq!(app.renderer.palette_name(), "ocean");
+
+        let mut config = Config::default();
+        config.display.color_scheme = "sunset".to_string();
+        assert!(config.validate().is_err());
+        config.display.color_scheme = "twilight".to_string();
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_config_palettes() {
+        use crate::config::{ColorStopConfig, PaletteConfig};
+        use crate::palette::Interpolation;
+
+        let stop = |position: f64, color: &str| ColorStopConfig { position, color: color.to_string() };
+        let definition = PaletteConfig {
+            stops: vec![stop(0.0, "#ff0000"), stop(1.0, "0000FF")],
+            interpolation: Interpolation::Hsv,
+            cycle_length: Some(16.0),
+            interior: Some("#102030".to_string()),
+            exterior: Some("#ffffff".to_string()),
+        };
+        let house = Palette::from_definition("house", &definition).unwrap();
+        assert_eq!(house.interior, (16, 32, 48));
+        // Red to blue the short way round the hue circle passes magenta
+        assert_eq!(house.color_at(0.5), (255, 0, 255));
+        assert_eq!(house.position(24.0, 1000), 0.5);
+
+        let grey = Palette { interpolation: Interpolation::Oklab, ..Palette::new("grey", &[(0.0, (0, 0, 0)), (1.0, (255, 255, 255))], (0, 0, 0)) };
+        assert_eq!(grey.color_at(0.0), (0, 0, 0));
+        assert_eq!(grey.color_at(1.0), (255, 255, 255));
+        assert_eq!(grey.color_at(0.5), (99, 99, 99));
+
+        let mut config = Config::default();
+        config.display.palettes.insert("house".to_string(), definition.clone());
+        config.display.color_scheme = "house".to_string();
+        assert!(config.validate().is_ok());
+        let renderer = TerminalRenderer::from_config(&config.display);
+        assert_eq!(renderer.palette_name(), "house");
+        assert_eq!(renderer.palette_names().len(), Palette::builtins().len() + 2);
+        let first_escape = renderer.sample_to_char_and_color(&FractalSample::new(1)).1;
+        assert_eq!(first_escape, ratatui::style::Color::Rgb(255, 255, 255));
+
+        // A config palette named like a built-in replaces it
+        config.display.palettes.insert("fire".to_string(), definition.clone());
+        assert_eq!(Palette::from_config(&config.display).unwrap().len(), Palette::builtins().len() + 1);
+
+        let invalid = [
+            PaletteConfig { stops: Vec::new(), ..definition.clone() },
+            PaletteConfig { stops: vec![stop(1.5, "#000000")], ..definition.clone() },
+            PaletteConfig { stops: vec![stop(0.0, "#00000g")], ..definition.clone() },
+            PaletteConfig { cycle_length: Some(0.0), ..definition.clone() },
+            PaletteConfig { interior: Some("black".to_string()), ..definition.clone() },
+        ];
+        for palette in invalid {
+            config.display.palettes.insert("house".to_string(), palette);
+            assert!(config.validate().unwrap_err().contains("house"));
+        }
+    }
+
+    #[test]
+    fn test_braille_cells() {
+        use crate::renderer::{Cell, CellMode};
+        use clap::Parser;
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        use ratatui::style::Color;
+
+        let mut renderer = TerminalRenderer::new();
+        renderer.set_cell_mode(CellMode::Braille);
+        assert_eq!(renderer.sample_size((10, 5)), (20, 20));
+
+        // Interior samples light every dot, quick escapes none, and a half shade dithers to half
+        let block = |iterations: u32| vec![vec![FractalSample::new(iterations); 2]; 4];
+        assert_eq!(renderer.cell(&block(1000), 0, 0), Cell { character: '⣿', fg: Color::LightMagenta, bg: None });
+        assert_eq!(renderer.cell(&block(2), 0, 0).character, ' ');
+        assert_eq!(renderer.cell(&block(6), 0, 0), Cell { character: '⢕', fg: Color::Gray, bg: None });
+        let mut top = block(1000);
+        top[2] = vec![FractalSample::new(0); 2];
+        top[3] = vec![FractalSample::new(0); 2];
+        assert_eq!(renderer.cell(&top, 0, 0).character, '⠛');
+
+        let samples = vec![vec![FractalSample::new(1000); 6]; 8];
+        assert_eq!(renderer.cell_size(&samples), (3, 2));
+        assert_eq!(renderer.render_samples_to_string(&samples), "⣿⣿⣿\n⣿⣿⣿\n");
+        renderer.set_use_unicode(false);
+        assert_eq!(renderer.cell_size(&samples), (6, 8));
+
+        // The app generates 2x4 samples per cell and cycles modes with m
+        let mut app = App::new();
+        app.regenerate_fractal();
+        let cells = (app.fractal_data[0].len(), app.fractal_data.len());
+        app.handle_key_event(KeyEvent::new(KeyCode::Char('m'), KeyModifiers::NONE));
+        assert_eq!(app.config.display.cell_mode, CellMode::Braille);
+        assert_eq!((app.fractal_data[0].len(), app.fractal_data.len()), (cells.0 * 2, cells.1 * 4));
+
+        let cli = Cli::try_parse_from(["fractal-generator", "render", "--cells", "braille", "--size", "10x5"]).unwrap();
+        let Some(cli::CliCommand::Render(render)) = &cli.command else { panic!("expected the render subcommand") };
+        let (config, _) = cli.load_config_with(&render.view).unwrap();
+        let text = String::from_utf8(render.render(&config).unwrap()).unwrap();
+        assert_eq!(text.lines().count(), 5);
+        assert!(text.lines().all(|line| line.chars().count() == 10));
+        assert!(Cli::try_parse_from(["fractal-generator", "--cells", "sixel"]).is_err());
+    }
+
+    #[test]
+    fn test_half_block_cells() {
+        use crate::renderer::{Cell, CellMode};
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        use ratatui::style::Color;
+
+        let mut renderer = TerminalRenderer::new();
+        renderer.set_cell_mode(CellMode::HalfBlock);
+        assert_eq!(renderer.sample_size((10, 5)), (10, 10));
+        assert_eq!("half-block".parse::<CellMode>(), Ok(CellMode::HalfBlock));
+
+        // Top sample in the foreground, bottom one in the background; blank halves show the terminal
+        let column = |top: u32, bottom: u32| vec![vec![FractalSample::new(top)], vec![FractalSample::new(bottom)]];
+        assert_eq!(renderer.cell(&column(1000, 14), 0, 0), Cell { character: '▀', fg: Color::LightMagenta, bg: Some(Color::Blue) });
+        assert_eq!(renderer.cell(&column(0, 14), 0, 0), Cell { character: '▄', fg: Color::Blue, bg: None });
+        assert_eq!(renderer.cell(&column(0, 0), 0, 0).character, ' ');
+
+        // Both colours reach the TUI and ANSI output
+        let lines = renderer.render_samples_to_text_with_bounds(&column(1000, 14), 0, 0, 1, 1, 1, 1);
+        assert_eq!(lines[0].spans[0].style.fg, Some(Color::LightMagenta));
+        assert_eq!(lines[0].spans[0].style.bg, Some(Color::Blue));
+        assert!(renderer.render_samples_to_ansi(&column(1000, 14)).contains("\x1b[48;2;0;0;238m▀"));
+
+        // Without colours both halves are solid
+        renderer.set_use_colors(false);
+        assert_eq!(renderer.cell(&column(1000, 14), 0, 0).character, '█');
+
+        let mut app = App::new();
+        app.regenerate_fractal();
+        let cells = (app.fractal_data[0].len(), app.fractal_data.len());
+        app.handle_key_event(KeyEvent::new(KeyCode::Char('m'), KeyModifiers::NONE));
+        app.handle_key_event(KeyEvent::new(KeyCode::Char('m'), KeyModifiers::NONE));
+        assert_eq!(app.config.display.cell_mode, CellMode::HalfBlock);
+        assert_eq!((app.fractal_data[0].len(), app.fractal_data.len()), (cells.0, cells.1 * 2));
+    }
+
+    #[test]
+    fn test_quality_mode_keeps_requested_iterations() {
+        let mut generator = FractalGenerator::new();
+        assert!(generator.quality_mode);
+        let params = FractalParams { width: 9, height: 9, center_x: 0.0, max_iterations: 2000, ..Default::default() };
+        // The middle pixel is inside the main cardioid, with and without the shortcut past it
+        assert_eq!(generator.generate(&params)[4][4], 2000);
+        generator.set_cardioid_check(false);
+        assert_eq!(generator.generate(&params)[4][4], 2000);
+    }
+
+    #[test]
+    fn test_deep_zoom_keeps_requested_iterations() {
+        let mut generator = FractalGenerator::new();
+        generator.set_performance_mode(true);
+        // Deep inside the main cardioid every pixel runs to the limit
+        let params = FractalParams { width: 8, height: 8, center_x: -0.1, zoom: 1e40, max_iterations: 2000, ..Default::default() };
+        assert!(generator.uses_perturbation(&params));
+        assert!(generator.generate(&params).iter().flatten().all(|&iterations| iterations == 2000));
     }
 
     #[test]
@@ -1,13 +1,42 @@
+use clap::Parser;
 use crossterm::{
     event::{DisableMouseCapture, EnableMouseCapture},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use fractal_generator::App;
+use fractal_generator::cli::{self, Cli, CliCommand};
 use ratatui::{backend::CrosstermBackend, Terminal};
-use std::{error::Error, io};
+use std::{error::Error, io, process};
 
 fn main() -> Result<(), Box<dyn Error>> {
+    let cli = Cli::parse();
+
+    // Headless rendering never touches the terminal, so it works in pipes
+    if let Some(CliCommand::Render(render)) = &cli.command {
+        let rendered = cli.load_config_with(&render.view).and_then(|(config, _)| {
+            cli::configure_thread_pool(&config.performance)?;
+            render.run(&config)
+        });
+        if let Err(error) = rendered {
+            eprintln!("fractal-generator: {}", error);
+            process::exit(2);
+        }
+        return Ok(());
+    }
+
+    // Report bad arguments and configs before the terminal is taken over
+    let built = cli.build_app().and_then(|app| {
+        cli::configure_thread_pool(&app.config.performance)?;
+        Ok(app)
+    });
+    let mut app = match built {
+        Ok(app) => app,
+        Err(error) => {
+            eprintln!("fractal-generator: {}", error);
+            process::exit(2);
+        }
+    };
+
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -15,8 +44,7 @@ fn main() -> Result<(), Box<dyn Error>> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    // Create app and run it
-    let mut app = App::new();
+    // Run the app
     let res = app.run(&mut terminal);
 
     // Restore terminal
@@ -0,0 +1,105 @@
+use num_complex::Complex;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// Orbit trap colouring: instead of how long an orbit took to escape, each pixel
+// records how close its orbit came to a shape placed in the complex plane.
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrapShape {
+    Point,
+    /// Infinite line through the trap center at `angle` radians from the real axis
+    Line { angle: f64 },
+    /// The horizontal and vertical lines through the trap center
+    Cross,
+    Circle { radius: f64 },
+}
+
+impl TrapShape {
+    /// Cycles point -> line -> cross -> circle -> point with default parameters
+    pub fn next(&self) -> TrapShape {
+        match self {
+            TrapShape::Point => TrapShape::Line { angle: 0.0 },
+            TrapShape::Line { .. } => TrapShape::Cross,
+            TrapShape::Cross => TrapShape::Circle { radius: 0.5 },
+            TrapShape::Circle { .. } => TrapShape::Point,
+        }
+    }
+
+    /// Grows or shrinks the shape's parameter one step: circle radius or line angle
+    pub fn adjust(&self, steps: i32) -> TrapShape {
+        match self {
+            TrapShape::Line { angle } => TrapShape::Line {
+                angle: (angle + steps as f64 * std::f64::consts::PI / 12.0).rem_euclid(std::f64::consts::PI),
+            },
+            TrapShape::Circle { radius } => TrapShape::Circle { radius: radius * 1.25f64.powi(steps) },
+            other => *other,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct OrbitTrap {
+    pub shape: TrapShape,
+    pub center_x: f64,
+    pub center_y: f64,
+}
+
+impl OrbitTrap {
+    pub fn new(shape: TrapShape, center_x: f64, center_y: f64) -> Self {
+        Self { shape, center_x, center_y }
+    }
+
+    /// Distance from `z` to the trap shape
+    pub fn distance(&self, z: Complex<f64>) -> f64 {
+        let dx = z.re - self.center_x;
+        let dy = z.im - self.center_y;
+        match self.shape {
+            TrapShape::Point => dx.hypot(dy),
+            TrapShape::Line { angle } => (dy * angle.cos() - dx * angle.sin()).abs(),
+            TrapShape::Cross => dx.abs().min(dy.abs()),
+            TrapShape::Circle { radius } => (dx.hypot(dy) - radius).abs(),
+        }
+    }
+}
+
+impl Default for OrbitTrap {
+    fn default() -> Self {
+        Self::new(TrapShape::Point, 0.0, 0.0)
+    }
+}
+
+impl fmt::Display for OrbitTrap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.shape {
+            TrapShape::Point => write!(f, "point")?,
+            TrapShape::Line { angle } => write!(f, "line at {:.0}°", angle.to_degrees())?,
+            TrapShape::Cross => write!(f, "cross")?,
+            TrapShape::Circle { radius } => write!(f, "circle r={:.3}", radius)?,
+        }
+        write!(f, " at ({:.3}, {:.3})", self.center_x, self.center_y)
+    }
+}
+
+// Running minimum of the trap distance over one orbit; does nothing without a trap
+pub(crate) struct TrapTracker {
+    trap: Option<OrbitTrap>,
+    min_distance: f64,
+}
+
+impl TrapTracker {
+    pub(crate) fn new(trap: Option<OrbitTrap>) -> Self {
+        Self { trap, min_distance: f64::INFINITY }
+    }
+
+    pub(crate) fn visit(&mut self, z: Complex<f64>) {
+        if let Some(trap) = &self.trap {
+            self.min_distance = self.min_distance.min(trap.distance(z));
+        }
+    }
+
+    pub(crate) fn distance(&self) -> Option<f64> {
+        self.trap.map(|_| self.min_distance)
+    }
+}
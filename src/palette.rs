@@ -0,0 +1,290 @@
+// Truecolor gradients. A palette maps a position in [0, 1] to an RGB colour by
+// interpolating between colour stops; the renderer picks the position from a
+// pixel's (smooth) iteration count. Besides the built-ins, the config can
+// define palettes of its own under `display.palettes`.
+
+use crate::config::{DisplayConfig, PaletteConfig};
+use serde::{Deserialize, Serialize};
+
+pub type Rgb = (u8, u8, u8);
+
+/// Name of the banded 16-colour scheme the renderer uses when no gradient is selected
+pub const DEFAULT_PALETTE: &str = "default";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorStop {
+    /// Where the colour sits on the gradient, from 0 to 1
+    pub position: f64,
+    pub color: Rgb,
+}
+
+/// Colour space neighbouring stops are blended in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Interpolation {
+    #[default]
+    Rgb,
+    /// Hue, saturation and value, with the hue going the short way round
+    Hsv,
+    /// Perceptually uniform, so lightness changes evenly along the gradient
+    Oklab,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Palette {
+    pub name: String,
+    /// Stops sorted by position
+    pub stops: Vec<ColorStop>,
+    pub interpolation: Interpolation,
+    /// Iterations one pass through the gradient spans before it repeats;
+    /// `None` stretches the gradient once over the iteration limit
+    pub cycle_length: Option<f64>,
+    /// Colour of points that never escaped
+    pub interior: Rgb,
+    /// Colour of points that escape on the first iteration, the background
+    /// around the set; `None` colours them from the gradient
+    pub exterior: Option<Rgb>,
+}
+
+impl Palette {
+    pub fn new(name: &str, stops: &[(f64, Rgb)], interior: Rgb) -> Self {
+        let mut stops: Vec<ColorStop> = stops.iter().map(|&(position, color)| ColorStop { position, color }).collect();
+        stops.sort_by(|a, b| a.position.total_cmp(&b.position));
+        Self { name: name.to_string(), stops, interpolation: Interpolation::Rgb, cycle_length: None, interior, exterior: None }
+    }
+
+    /// A palette from its config definition
+    pub fn from_definition(name: &str, definition: &PaletteConfig) -> Result<Self, String> {
+        if name.trim().is_empty() || name.eq_ignore_ascii_case(DEFAULT_PALETTE) {
+            return Err(format!("'{}' cannot be used as a palette name", name));
+        }
+        if definition.stops.is_empty() {
+            return Err("needs at least one colour stop".to_string());
+        }
+        let mut stops = Vec::with_capacity(definition.stops.len());
+        for stop in &definition.stops {
+            if !(0.0..=1.0).contains(&stop.position) {
+                return Err(format!("stop position {} is outside 0 to 1", stop.position));
+            }
+            stops.push((stop.position, parse_hex(&stop.color)?));
+        }
+        if let Some(cycle_length) = definition.cycle_length {
+            if !(cycle_length.is_finite() && cycle_length > 0.0) {
+                return Err("cycle length must be positive".to_string());
+            }
+        }
+        let interior = definition.interior.as_deref().map(parse_hex).transpose()?.unwrap_or((0, 0, 0));
+        let mut palette = Self::new(name, &stops, interior);
+        palette.interpolation = definition.interpolation;
+        palette.cycle_length = definition.cycle_length;
+        palette.exterior = definition.exterior.as_deref().map(parse_hex).transpose()?;
+        Ok(palette)
+    }
+
+    /// The built-ins plus the config's palettes; a config palette named like
+    /// a built-in replaces it
+    pub fn from_config(display: &DisplayConfig) -> Result<Vec<Self>, String> {
+        let mut palettes = Self::builtins();
+        for (name, definition) in &display.palettes {
+            let palette = Self::from_definition(name, definition).map_err(|error| format!("palette '{}': {}", name, error))?;
+            match palettes.iter_mut().find(|existing| existing.name.eq_ignore_ascii_case(name)) {
+                Some(existing) => *existing = palette,
+                None => palettes.push(palette),
+            }
+        }
+        Ok(palettes)
+    }
+
+    /// The shipped gradients: classic, fire, ocean, grayscale and twilight
+    pub fn builtins() -> Vec<Palette> {
+        vec![
+            // The blue-white-orange of Ultra Fractal's default gradient
+            Palette::new("classic", &[
+                (0.0, (0, 7, 100)),
+                (0.16, (32, 107, 203)),
+                (0.42, (237, 255, 255)),
+                (0.6425, (255, 170, 0)),
+                (0.8575, (0, 2, 0)),
+                (1.0, (0, 7, 100)),
+            ], (0, 0, 0)),
+            Palette::new("fire", &[
+                (0.0, (0, 0, 0)),
+                (0.25, (128, 0, 0)),
+                (0.5, (255, 64, 0)),
+                (0.7, (255, 160, 0)),
+                (0.9, (255, 255, 64)),
+                (1.0, (255, 255, 255)),
+            ], (0, 0, 0)),
+            Palette::new("ocean", &[
+                (0.0, (0, 0, 32)),
+                (0.3, (0, 40, 100)),
+                (0.55, (0, 110, 170)),
+                (0.8, (40, 190, 210)),
+                (0.95, (180, 240, 240)),
+                (1.0, (255, 255, 255)),
+            ], (0, 0, 16)),
+            Palette::new("grayscale", &[(0.0, (0, 0, 0)), (1.0, (255, 255, 255))], (0, 0, 0)),
+            // Light at both ends so the cycle wraps without a seam
+            Palette::new("twilight", &[
+                (0.0, (226, 217, 226)),
+                (0.25, (94, 130, 187)),
+                (0.5, (47, 20, 67)),
+                (0.75, (174, 85, 70)),
+                (1.0, (226, 217, 226)),
+            ], (0, 0, 0)),
+        ]
+    }
+
+    /// The colour at `position`, clamped to the ends of the gradient
+    pub fn color_at(&self, position: f64) -> Rgb {
+        let position = if position.is_nan() { 0.0 } else { position.clamp(0.0, 1.0) };
+        let Some(first) = self.stops.first() else { return self.interior };
+        if position <= first.position {
+            return first.color;
+        }
+        for pair in self.stops.windows(2) {
+            let (from, to) = (pair[0], pair[1]);
+            if position <= to.position {
+                let span = to.position - from.position;
+                let fraction = if span > 0.0 { (position - from.position) / span } else { 1.0 };
+                return self.interpolation.mix(from.color, to.color, fraction);
+            }
+        }
+        self.stops[self.stops.len() - 1].color
+    }
+
+    /// Where an escape time of `value` falls on the gradient
+    pub fn position(&self, value: f64, max_iterations: u32) -> f64 {
+        let value = value.max(0.0);
+        match self.cycle_length {
+            Some(cycle_length) => (value / cycle_length).fract(),
+            // A log scale spreads the many quick escapes across the gradient
+            None => value.ln_1p() / (max_iterations.max(1) as f64).ln_1p(),
+        }
+    }
+}
+
+impl Interpolation {
+    fn mix(self, from: Rgb, to: Rgb, fraction: f64) -> Rgb {
+        match self {
+            Interpolation::Rgb => lerp_rgb(from, to, fraction),
+            Interpolation::Hsv => {
+                let (from_hue, from_saturation, from_value) = rgb_to_hsv(from);
+                let (to_hue, to_saturation, to_value) = rgb_to_hsv(to);
+                let mut delta = to_hue - from_hue;
+                if delta > 180.0 {
+                    delta -= 360.0;
+                } else if delta < -180.0 {
+                    delta += 360.0;
+                }
+                hsv_to_rgb(
+                    (from_hue + delta * fraction).rem_euclid(360.0),
+                    lerp(from_saturation, to_saturation, fraction),
+                    lerp(from_value, to_value, fraction),
+                )
+            },
+            Interpolation::Oklab => {
+                let from = rgb_to_oklab(from);
+                let to = rgb_to_oklab(to);
+                oklab_to_rgb([
+                    lerp(from[0], to[0], fraction),
+                    lerp(from[1], to[1], fraction),
+                    lerp(from[2], to[2], fraction),
+                ])
+            },
+        }
+    }
+}
+
+/// Parses a `#rrggbb` colour; the `#` is optional
+pub fn parse_hex(text: &str) -> Result<Rgb, String> {
+    let digits = text.trim().strip_prefix('#').unwrap_or(text.trim());
+    let invalid = || format!("'{}' is not a #rrggbb colour", text);
+    if digits.len() != 6 || !digits.chars().all(|digit| digit.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+    let channel = |index: usize| u8::from_str_radix(&digits[index..index + 2], 16).map_err(|_| invalid());
+    Ok((channel(0)?, channel(2)?, channel(4)?))
+}
+
+fn lerp(from: f64, to: f64, fraction: f64) -> f64 {
+    from + (to - from) * fraction
+}
+
+fn lerp_rgb(from: Rgb, to: Rgb, fraction: f64) -> Rgb {
+    let channel = |a: u8, b: u8| lerp(a as f64, b as f64, fraction).round() as u8;
+    (channel(from.0, to.0), channel(from.1, to.1), channel(from.2, to.2))
+}
+
+fn to_byte(channel: f64) -> u8 {
+    (channel.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+// Hue in degrees, saturation and value in [0, 1]
+fn rgb_to_hsv((red, green, blue): Rgb) -> (f64, f64, f64) {
+    let (red, green, blue) = (red as f64 / 255.0, green as f64 / 255.0, blue as f64 / 255.0);
+    let max = red.max(green).max(blue);
+    let min = red.min(green).min(blue);
+    let chroma = max - min;
+    let hue = if chroma == 0.0 {
+        0.0
+    } else if max == red {
+        60.0 * ((green - blue) / chroma).rem_euclid(6.0)
+    } else if max == green {
+        60.0 * ((blue - red) / chroma + 2.0)
+    } else {
+        60.0 * ((red - green) / chroma + 4.0)
+    };
+    let saturation = if max == 0.0 { 0.0 } else { chroma / max };
+    (hue, saturation, max)
+}
+
+fn hsv_to_rgb(hue: f64, saturation: f64, value: f64) -> Rgb {
+    let chroma = value * saturation;
+    let sector = hue / 60.0;
+    let x = chroma * (1.0 - (sector.rem_euclid(2.0) - 1.0).abs());
+    let (red, green, blue) = match sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let offset = value - chroma;
+    (to_byte(red + offset), to_byte(green + offset), to_byte(blue + offset))
+}
+
+// OKLab works on linear light, so sRGB's transfer curve is undone first
+fn srgb_to_linear(channel: u8) -> f64 {
+    let channel = channel as f64 / 255.0;
+    if channel <= 0.04045 { channel / 12.92 } else { ((channel + 0.055) / 1.055).powf(2.4) }
+}
+
+fn linear_to_srgb(channel: f64) -> u8 {
+    let channel = if channel <= 0.0031308 { channel * 12.92 } else { 1.055 * channel.max(0.0).powf(1.0 / 2.4) - 0.055 };
+    to_byte(channel)
+}
+
+fn rgb_to_oklab((red, green, blue): Rgb) -> [f64; 3] {
+    let (red, green, blue) = (srgb_to_linear(red), srgb_to_linear(green), srgb_to_linear(blue));
+    let l = (0.4122214708 * red + 0.5363325363 * green + 0.0514459929 * blue).cbrt();
+    let m = (0.2119034982 * red + 0.6806995451 * green + 0.1073969566 * blue).cbrt();
+    let s = (0.0883024619 * red + 0.2817188376 * green + 0.6299787005 * blue).cbrt();
+    [
+        0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+        1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+        0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+    ]
+}
+
+fn oklab_to_rgb([lightness, a, b]: [f64; 3]) -> Rgb {
+    let l = (lightness + 0.3963377774 * a + 0.2158037573 * b).powi(3);
+    let m = (lightness - 0.1055613458 * a - 0.0638541728 * b).powi(3);
+    let s = (lightness - 0.0894841775 * a - 1.2914855480 * b).powi(3);
+    (
+        linear_to_srgb(4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s),
+        linear_to_srgb(-1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s),
+        linear_to_srgb(-0.0041960863 * l - 0.7034186147 * m + 1.7076147010 * s),
+    )
+}
@@ -0,0 +1,367 @@
+use crate::bigfloat::BigFloat;
+use crate::fractal::{FractalGenerator, FractalParams, FractalSample, FractalType};
+use crate::registry::FractalRegistry;
+use num_complex::Complex;
+use rayon::prelude::*;
+
+// Deep zoom by perturbation: one reference orbit Z_n is iterated at high precision
+// and every pixel only tracks its f64 offset delta_n = z_n - Z_n from it.
+
+// Pauldelbrot's criterion: once |z| is this much smaller than |Z| the f64 delta has
+// lost its significant bits and the pixel needs a closer reference
+const GLITCH_TOLERANCE: f64 = 1e-6;
+// Upper bound on how many extra references are tried for glitched pixels
+const MAX_REFERENCES: usize = 16;
+// The series is used while its truncation error stays below this relative size
+const SERIES_TOLERANCE: f64 = 1e-12;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PerturbationKind {
+    Mandelbrot,
+    Multibrot { power: u32 },
+    Tricorn,
+    BurningShip,
+}
+
+impl PerturbationKind {
+    /// The kind for the fractal a registry resolves `fractal_type` to, if it has one
+    pub fn for_fractal(registry: &FractalRegistry, fractal_type: &FractalType) -> Option<Self> {
+        registry.resolve(fractal_type)?.perturbation_kind()
+    }
+
+    fn power(&self) -> f64 {
+        match self {
+            PerturbationKind::Multibrot { power } => *power as f64,
+            _ => 2.0,
+        }
+    }
+
+    // Whether dz/dc exists for this iteration; the folded variants are not holomorphic
+    fn has_derivative(&self) -> bool {
+        matches!(self, PerturbationKind::Mandelbrot | PerturbationKind::Multibrot { .. })
+    }
+
+    // dz/dc at step n+1 from the full orbit point z_n and dz/dc at step n
+    fn step_derivative(&self, z: Complex<f64>, derivative: Complex<f64>) -> Complex<f64> {
+        match self {
+            PerturbationKind::Multibrot { power } => derivative * z.powu(power - 1) * *power as f64 + 1.0,
+            _ => derivative * z * 2.0 + 1.0,
+        }
+    }
+
+    // One step of the high-precision reference iteration
+    fn step_reference(&self, x: &BigFloat, y: &BigFloat, cx: &BigFloat, cy: &BigFloat) -> (BigFloat, BigFloat) {
+        match self {
+            PerturbationKind::Mandelbrot | PerturbationKind::Tricorn | PerturbationKind::BurningShip => {
+                let real = &(&x.square() - &y.square()) + cx;
+                let cross = (x * y).mul_pow2(1);
+                let imag = match self {
+                    PerturbationKind::Tricorn => cy - &cross,
+                    PerturbationKind::BurningShip => &cross.abs() + cy,
+                    _ => &cross + cy,
+                };
+                (real, imag)
+            }
+            PerturbationKind::Multibrot { power } => {
+                let mut real = x.clone();
+                let mut imag = y.clone();
+                for _ in 1..*power {
+                    let next_real = &(&real * x) - &(&imag * y);
+                    let next_imag = &(&real * y) + &(&imag * x);
+                    real = next_real;
+                    imag = next_imag;
+                }
+                (&real + cx, &imag + cy)
+            }
+        }
+    }
+}
+
+// A kind's delta step with everything that doesn't depend on the pixel worked out
+// once per render; for Multibrot that is the binomial coefficients binom(p, k)
+struct DeltaStep {
+    kind: PerturbationKind,
+    binomials: Vec<f64>,
+}
+
+impl DeltaStep {
+    fn new(kind: PerturbationKind) -> Self {
+        let binomials = match kind {
+            PerturbationKind::Multibrot { power } => {
+                let power = power as usize;
+                let mut binomials = vec![1.0; power + 1];
+                for k in 1..=power {
+                    binomials[k] = binomials[k - 1] * (power - k + 1) as f64 / k as f64;
+                }
+                binomials
+            }
+            _ => Vec::new(),
+        };
+        Self { kind, binomials }
+    }
+
+    // delta_{n+1} given the reference point Z_n, the current delta_n and the pixel offset delta_c
+    fn apply(&self, reference: Complex<f64>, delta: Complex<f64>, delta_c: Complex<f64>) -> Complex<f64> {
+        match self.kind {
+            PerturbationKind::Mandelbrot => delta * (reference * 2.0 + delta) + delta_c,
+            PerturbationKind::Tricorn => (delta * (reference * 2.0 + delta)).conj() + delta_c,
+            PerturbationKind::BurningShip => {
+                let (x, y) = (reference.re, reference.im);
+                let (dx, dy) = (delta.re, delta.im);
+                let real = (2.0 * x + dx) * dx - (2.0 * y + dy) * dy + delta_c.re;
+                let imag = 2.0 * ((x + dx).abs() * diff_abs(y, dy) + y.abs() * diff_abs(x, dx)) + delta_c.im;
+                Complex::new(real, imag)
+            }
+            PerturbationKind::Multibrot { power } => {
+                // (Z + d)^p - Z^p = sum_{k=1..p} binom(p, k) Z^(p-k) d^k, evaluated with Horner
+                // in d from k = p down, building up Z^(p-k) as it goes
+                let mut sum = Complex::new(1.0, 0.0);
+                let mut reference_power = Complex::new(1.0, 0.0);
+                for k in (1..power as usize).rev() {
+                    reference_power *= reference;
+                    sum = sum * delta + reference_power * self.binomials[k];
+                }
+                sum * delta + delta_c
+            }
+        }
+    }
+}
+
+// |c + d| - |c| without cancellation when d is tiny compared to c
+fn diff_abs(c: f64, d: f64) -> f64 {
+    let cd = c + d;
+    if c >= 0.0 {
+        if cd >= 0.0 { d } else { -(2.0 * c + d) }
+    } else if cd > 0.0 {
+        2.0 * c + d
+    } else {
+        -d
+    }
+}
+
+pub struct ReferenceOrbit {
+    pub center: (BigFloat, BigFloat),
+    /// Z_0 ..= Z_n rounded to f64, ending at the first escaped point or at the iteration limit
+    pub points: Vec<Complex<f64>>,
+}
+
+impl ReferenceOrbit {
+    pub fn compute(kind: PerturbationKind, center: (BigFloat, BigFloat), max_iterations: u32, bailout_sqr: f64) -> Self {
+        let precision = center.0.precision().max(center.1.precision());
+        let mut x = BigFloat::zero(precision);
+        let mut y = BigFloat::zero(precision);
+        let mut points = Vec::with_capacity(max_iterations as usize + 1);
+        points.push(Complex::new(0.0, 0.0));
+
+        for _ in 0..max_iterations {
+            let (next_x, next_y) = kind.step_reference(&x, &y, &center.0, &center.1);
+            x = next_x;
+            y = next_y;
+
+            let point = Complex::new(x.to_f64(), y.to_f64());
+            points.push(point);
+            if point.norm_sqr() > bailout_sqr {
+                break;
+            }
+        }
+
+        Self { center, points }
+    }
+}
+
+// Cubic series delta_n ~ A_n dc + B_n dc^2 + C_n dc^3 for the Mandelbrot iteration,
+// letting every pixel start at iteration `skip` instead of zero
+struct SeriesApproximation {
+    skip: usize,
+    a: Complex<f64>,
+    b: Complex<f64>,
+    c: Complex<f64>,
+}
+
+impl SeriesApproximation {
+    fn compute(orbit: &ReferenceOrbit, max_delta: f64) -> Self {
+        let zero = Complex::new(0.0, 0.0);
+        let mut series = Self { skip: 0, a: zero, b: zero, c: zero };
+        let (d1, d2, d3) = (max_delta, max_delta * max_delta, max_delta * max_delta * max_delta);
+
+        // Stop one short of the end so the pixel loop always has a reference point to continue from
+        for n in 0..orbit.points.len().saturating_sub(2) {
+            let z = orbit.points[n] * 2.0;
+            let a = z * series.a + 1.0;
+            let b = z * series.b + series.a * series.a;
+            let c = z * series.c + series.a * series.b * 2.0;
+
+            let truncation = c.norm() * d3;
+            let leading = a.norm() * d1;
+            let estimate = leading + b.norm() * d2 + truncation;
+            if !estimate.is_finite() || truncation > SERIES_TOLERANCE * leading || estimate > 1e-3 {
+                break;
+            }
+
+            series = Self { skip: n + 1, a, b, c };
+        }
+
+        series
+    }
+
+    fn delta(&self, delta_c: Complex<f64>) -> Complex<f64> {
+        let delta_c2 = delta_c * delta_c;
+        self.a * delta_c + self.b * delta_c2 + self.c * delta_c2 * delta_c
+    }
+}
+
+enum PixelResult {
+    Done { iterations: u32, z: Complex<f64>, derivative: Complex<f64> },
+    Glitch { iterations: u32, z: Complex<f64> },
+}
+
+fn iterate_pixel(
+    step: &DeltaStep,
+    orbit: &ReferenceOrbit,
+    delta_c: Complex<f64>,
+    start: (usize, Complex<f64>),
+    max_iterations: u32,
+    bailout_sqr: f64,
+    track_derivative: bool,
+) -> PixelResult {
+    let (mut n, mut delta) = start;
+    let mut derivative = Complex::new(0.0, 0.0);
+    let points = &orbit.points;
+
+    while (n as u32) < max_iterations {
+        let reference = points[n];
+        let z = reference + delta;
+        let norm_sqr = z.norm_sqr();
+
+        if norm_sqr > bailout_sqr {
+            return PixelResult::Done { iterations: n as u32, z, derivative };
+        }
+        if norm_sqr < GLITCH_TOLERANCE * reference.norm_sqr() || !norm_sqr.is_finite() {
+            return PixelResult::Glitch { iterations: n as u32, z };
+        }
+        // The reference escaped before this pixel did
+        if n + 1 >= points.len() && ((n + 1) as u32) < max_iterations {
+            return PixelResult::Glitch { iterations: n as u32, z };
+        }
+
+        if track_derivative {
+            derivative = step.kind.step_derivative(z, derivative);
+        }
+        delta = step.apply(reference, delta, delta_c);
+        n += 1;
+    }
+
+    let z = points.get(n).copied().unwrap_or(points[points.len() - 1]) + delta;
+    PixelResult::Done { iterations: max_iterations, z, derivative }
+}
+
+// Bits needed so the reference center resolves individual pixels with room to spare
+pub fn required_precision(params: &FractalParams) -> u32 {
+    let spacing = 4.0 / params.zoom / params.width.max(params.height).max(1) as f64;
+    let bits = (-spacing.log2()).max(0.0) as u32;
+    bits + 64
+}
+
+pub fn generate(generator: &FractalGenerator, params: &FractalParams, kind: PerturbationKind, max_iterations: u32) -> Vec<Vec<FractalSample>> {
+    let width = params.width;
+    let height = params.height;
+    let bailout_sqr = generator.bailout_sqr();
+    let power = kind.power();
+    let track_derivative = generator.distance_estimation && kind.has_derivative();
+    let step = DeltaStep::new(kind);
+
+    // Pixel offsets from the center stay small and exact in f64
+    let half_extent = 2.0 / params.zoom;
+    let x_scale = 2.0 * half_extent / width as f64;
+    let y_scale = 2.0 * half_extent / height as f64;
+    let delta_at = |index: usize| {
+        Complex::new(
+            -half_extent + (index % width) as f64 * x_scale,
+            -half_extent + (index / width) as f64 * y_scale,
+        )
+    };
+
+    let precision = required_precision(params);
+    let center = params.center_with_precision(precision);
+
+    let orbit = ReferenceOrbit::compute(kind, center, max_iterations, bailout_sqr);
+    // The series skips the early iterations the derivative has to be accumulated over
+    let series = if kind == PerturbationKind::Mandelbrot && !track_derivative {
+        Some(SeriesApproximation::compute(&orbit, half_extent * std::f64::consts::SQRT_2))
+    } else {
+        None
+    };
+
+    let mut results: Vec<PixelResult> = (0..width * height)
+        .into_par_iter()
+        .map(|index| {
+            let delta_c = delta_at(index);
+            let start = match &series {
+                Some(series) => (series.skip, series.delta(delta_c)),
+                None => (0, Complex::new(0.0, 0.0)),
+            };
+            iterate_pixel(&step, &orbit, delta_c, start, max_iterations, bailout_sqr, track_derivative)
+        })
+        .collect();
+
+    // Re-reference: pick a glitched pixel as the new reference and redo the glitched pixels against it
+    for _ in 0..MAX_REFERENCES {
+        let glitched: Vec<usize> = results
+            .iter()
+            .enumerate()
+            .filter(|(_, result)| matches!(result, PixelResult::Glitch { .. }))
+            .map(|(index, _)| index)
+            .collect();
+        if glitched.is_empty() {
+            break;
+        }
+
+        // The glitched pixel whose orbit came closest to zero is nearest the feature causing the glitch
+        let reference_index = glitched
+            .iter()
+            .copied()
+            .min_by(|&a, &b| {
+                let norm = |index: usize| match &results[index] {
+                    PixelResult::Glitch { z, .. } => z.norm_sqr(),
+                    PixelResult::Done { .. } => f64::INFINITY,
+                };
+                norm(a).total_cmp(&norm(b))
+            })
+            .unwrap_or(glitched[0]);
+
+        let reference_delta = delta_at(reference_index);
+        let reference_center = (
+            &orbit.center.0 + &BigFloat::from_f64(reference_delta.re, precision),
+            &orbit.center.1 + &BigFloat::from_f64(reference_delta.im, precision),
+        );
+        let new_orbit = ReferenceOrbit::compute(kind, reference_center, max_iterations, bailout_sqr);
+
+        let updates: Vec<(usize, PixelResult)> = glitched
+            .par_iter()
+            .map(|&index| {
+                let delta_c = delta_at(index) - reference_delta;
+                let start = (0, Complex::new(0.0, 0.0));
+                let result = iterate_pixel(&step, &new_orbit, delta_c, start, max_iterations, bailout_sqr, track_derivative);
+                (index, result)
+            })
+            .collect();
+        for (index, result) in updates {
+            results[index] = result;
+        }
+    }
+
+    let samples: Vec<FractalSample> = results
+        .into_iter()
+        .map(|result| match result {
+            PixelResult::Done { iterations, z, derivative } => FractalSample {
+                distance: track_derivative
+                    .then(|| generator.distance_estimate(iterations, max_iterations, z, derivative))
+                    .flatten(),
+                ..generator.finish_sample(iterations, max_iterations, z, power)
+            },
+            PixelResult::Glitch { iterations, z } => generator.finish_sample(iterations, max_iterations, z, power),
+        })
+        .collect();
+
+    samples.chunks(width.max(1)).map(|row| row.to_vec()).collect()
+}
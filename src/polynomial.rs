@@ -0,0 +1,239 @@
+use crate::expression::{self, Expr, ParseError};
+use num_complex::Complex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+// Newton iteration gets slow and the root finder unreliable beyond this
+const MAX_DEGREE: usize = 32;
+// Durand-Kerner stops once no root estimate moves by more than this
+const ROOT_TOLERANCE: f64 = 1e-14;
+const ROOT_MAX_ITERATIONS: usize = 500;
+
+// Complex polynomial in z for the Newton fractal, with its roots found once up front
+// so every pixel only has to compare against them
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polynomial {
+    /// Coefficients in ascending order: coefficients[k] multiplies z^k
+    coefficients: Vec<Complex<f64>>,
+    roots: Vec<Complex<f64>>,
+}
+
+impl Polynomial {
+    pub fn from_coefficients(mut coefficients: Vec<Complex<f64>>) -> Self {
+        while coefficients.len() > 1 && coefficients.last() == Some(&Complex::new(0.0, 0.0)) {
+            coefficients.pop();
+        }
+        if coefficients.is_empty() {
+            coefficients.push(Complex::new(0.0, 0.0));
+        }
+
+        let roots = find_roots(&coefficients);
+        Self { coefficients, roots }
+    }
+
+    /// The monic polynomial (z - r_0)(z - r_1)... with exactly these roots
+    pub fn from_roots(roots: &[Complex<f64>]) -> Self {
+        let mut coefficients = vec![Complex::new(1.0, 0.0)];
+        for &root in roots {
+            coefficients = multiply(&coefficients, &[-root, Complex::new(1.0, 0.0)]);
+        }
+        Self { coefficients, roots: roots.to_vec() }
+    }
+
+    /// Parses a formula in z such as "z^3 - 1" or "(z - 1)(z + i)" and multiplies it out
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let expr = expression::parse(text)?;
+        let coefficients = expand(&expr).map_err(|message| ParseError { message, position: 0 })?;
+        let polynomial = Self::from_coefficients(coefficients);
+        if polynomial.degree() == 0 {
+            return Err(ParseError {
+                message: "polynomial must depend on z".to_string(),
+                position: 0,
+            });
+        }
+        Ok(polynomial)
+    }
+
+    pub fn coefficients(&self) -> &[Complex<f64>] {
+        &self.coefficients
+    }
+
+    pub fn roots(&self) -> &[Complex<f64>] {
+        &self.roots
+    }
+
+    pub fn degree(&self) -> usize {
+        self.coefficients.len() - 1
+    }
+
+    /// p(z) and p'(z) by Horner's scheme
+    pub fn eval_with_derivative(&self, z: Complex<f64>) -> (Complex<f64>, Complex<f64>) {
+        let mut value = Complex::new(0.0, 0.0);
+        let mut derivative = Complex::new(0.0, 0.0);
+        for &coefficient in self.coefficients.iter().rev() {
+            derivative = derivative * z + value;
+            value = value * z + coefficient;
+        }
+        (value, derivative)
+    }
+}
+
+impl fmt::Display for Polynomial {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut first = true;
+        for (power, coefficient) in self.coefficients.iter().enumerate().rev() {
+            if *coefficient == Complex::new(0.0, 0.0) && !(first && power == 0) {
+                continue;
+            }
+
+            // Pull a real coefficient's sign out into the joining operator
+            let (negative, magnitude) = if coefficient.im == 0.0 && coefficient.re < 0.0 {
+                (true, -*coefficient)
+            } else {
+                (false, *coefficient)
+            };
+            if first {
+                if negative {
+                    write!(f, "-")?;
+                }
+            } else {
+                write!(f, " {} ", if negative { "-" } else { "+" })?;
+            }
+            first = false;
+
+            let coefficient_text = if magnitude.im == 0.0 {
+                format!("{}", magnitude.re)
+            } else if magnitude.re == 0.0 {
+                format!("{}i", magnitude.im)
+            } else {
+                format!("({} {} {}i)", magnitude.re, if magnitude.im < 0.0 { "-" } else { "+" }, magnitude.im.abs())
+            };
+            match power {
+                0 => write!(f, "{}", coefficient_text)?,
+                _ => {
+                    if magnitude != Complex::new(1.0, 0.0) {
+                        write!(f, "{}", coefficient_text)?;
+                    }
+                    if power == 1 {
+                        write!(f, "z")?;
+                    } else {
+                        write!(f, "z^{}", power)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// Stored as the formula text, which parses back to the same coefficients
+impl Serialize for Polynomial {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Polynomial {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        Polynomial::parse(&text).map_err(serde::de::Error::custom)
+    }
+}
+
+// Multiplies an expression out into ascending coefficients, rejecting anything
+// that is not a polynomial in z
+fn expand(expr: &Expr) -> Result<Vec<Complex<f64>>, String> {
+    let coefficients = match expr {
+        Expr::Constant(value) => vec![*value],
+        Expr::Z => vec![Complex::new(0.0, 0.0), Complex::new(1.0, 0.0)],
+        Expr::C => return Err("'c' is not allowed in a Newton polynomial".to_string()),
+        Expr::Neg(inner) => expand(inner)?.into_iter().map(|coefficient| -coefficient).collect(),
+        Expr::Add(a, b) => add(&expand(a)?, &expand(b)?, 1.0),
+        Expr::Sub(a, b) => add(&expand(a)?, &expand(b)?, -1.0),
+        Expr::Mul(a, b) => multiply(&expand(a)?, &expand(b)?),
+        Expr::Div(a, b) => {
+            let divisor = expand(b)?;
+            if divisor.iter().skip(1).any(|coefficient| *coefficient != Complex::new(0.0, 0.0)) {
+                return Err("can only divide a polynomial by a constant".to_string());
+            }
+            expand(a)?.into_iter().map(|coefficient| coefficient / divisor[0]).collect()
+        }
+        Expr::PowInt(base, exponent) if *exponent >= 0 => {
+            let base = expand(base)?;
+            let mut result = vec![Complex::new(1.0, 0.0)];
+            for _ in 0..*exponent {
+                result = multiply(&result, &base);
+                if result.len() > MAX_DEGREE + 1 {
+                    break;
+                }
+            }
+            result
+        }
+        Expr::PowInt(..) | Expr::PowReal(..) | Expr::Pow(..) => {
+            return Err("exponents in a polynomial must be non-negative integers".to_string())
+        }
+        Expr::Call(..) => return Err("functions are not allowed in a Newton polynomial".to_string()),
+    };
+
+    if coefficients.len() > MAX_DEGREE + 1 {
+        return Err(format!("polynomial degree is limited to {}", MAX_DEGREE));
+    }
+    Ok(coefficients)
+}
+
+fn add(a: &[Complex<f64>], b: &[Complex<f64>], sign: f64) -> Vec<Complex<f64>> {
+    let mut sum = vec![Complex::new(0.0, 0.0); a.len().max(b.len())];
+    for (k, coefficient) in a.iter().enumerate() {
+        sum[k] += coefficient;
+    }
+    for (k, coefficient) in b.iter().enumerate() {
+        sum[k] += coefficient * sign;
+    }
+    sum
+}
+
+fn multiply(a: &[Complex<f64>], b: &[Complex<f64>]) -> Vec<Complex<f64>> {
+    let mut product = vec![Complex::new(0.0, 0.0); a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            product[i + j] += x * y;
+        }
+    }
+    product
+}
+
+// All roots at once by Durand-Kerner (Weierstrass) iteration on the monic polynomial
+fn find_roots(coefficients: &[Complex<f64>]) -> Vec<Complex<f64>> {
+    let degree = coefficients.len() - 1;
+    if degree == 0 {
+        return Vec::new();
+    }
+
+    let leading = coefficients[degree];
+    let monic: Vec<Complex<f64>> = coefficients.iter().map(|coefficient| coefficient / leading).collect();
+    let eval = |z: Complex<f64>| monic.iter().rev().fold(Complex::new(0.0, 0.0), |value, coefficient| value * z + coefficient);
+
+    // Powers of a point that is neither real nor a root of unity keep the starting guesses apart
+    let seed = Complex::new(0.4, 0.9);
+    let mut roots: Vec<Complex<f64>> = (0..degree).map(|k| seed.powu(k as u32)).collect();
+
+    for _ in 0..ROOT_MAX_ITERATIONS {
+        let mut largest_step: f64 = 0.0;
+        for i in 0..degree {
+            let denominator = (0..degree)
+                .filter(|&j| j != i)
+                .fold(Complex::new(1.0, 0.0), |product, j| product * (roots[i] - roots[j]));
+            if denominator == Complex::new(0.0, 0.0) {
+                continue;
+            }
+            let step = eval(roots[i]) / denominator;
+            roots[i] -= step;
+            largest_step = largest_step.max(step.norm());
+        }
+        if largest_step < ROOT_TOLERANCE {
+            break;
+        }
+    }
+
+    roots
+}
@@ -0,0 +1,302 @@
+use crate::bigfloat::BigFloat;
+use num_complex::Complex;
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Precision {
+    F32,
+    #[default]
+    F64,
+    DoubleDouble,
+}
+
+impl Precision {
+    /// Relative spacing of representable numbers (one ulp at 1.0)
+    pub fn epsilon(&self) -> f64 {
+        match self {
+            Precision::F32 => f32::EPSILON as f64,
+            Precision::F64 => f64::EPSILON,
+            Precision::DoubleDouble => DoubleDouble::EPSILON,
+        }
+    }
+
+    /// The next wider precision, if any
+    pub fn wider(&self) -> Option<Precision> {
+        match self {
+            Precision::F32 => Some(Precision::F64),
+            Precision::F64 => Some(Precision::DoubleDouble),
+            Precision::DoubleDouble => None,
+        }
+    }
+
+    /// Cycles F32 -> F64 -> DoubleDouble -> F32
+    pub fn next(&self) -> Precision {
+        self.wider().unwrap_or(Precision::F32)
+    }
+}
+
+impl fmt::Display for Precision {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Precision::F32 => "f32",
+            Precision::F64 => "f64",
+            Precision::DoubleDouble => "double-double",
+        };
+        f.write_str(name)
+    }
+}
+
+// Unevaluated sum hi + lo of two f64s with |lo| <= ulp(hi) / 2, giving about
+// 106 significant bits. Cheap enough to run every pixel directly, so it covers
+// the zoom range between plain f64 and the perturbation engine.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+pub struct DoubleDouble {
+    pub hi: f64,
+    pub lo: f64,
+}
+
+impl DoubleDouble {
+    pub const EPSILON: f64 = f64::EPSILON * f64::EPSILON;
+
+    pub fn new(hi: f64, lo: f64) -> Self {
+        let (hi, lo) = quick_two_sum(hi, lo);
+        Self { hi, lo }
+    }
+
+    pub fn from_f64(value: f64) -> Self {
+        Self { hi: value, lo: 0.0 }
+    }
+
+    pub fn from_bigfloat(value: &BigFloat) -> Self {
+        let hi = value.to_f64();
+        let lo = (value - &BigFloat::from_f64(hi, value.precision())).to_f64();
+        Self::new(hi, lo)
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.hi + self.lo
+    }
+
+    pub fn abs(self) -> Self {
+        if self.hi < 0.0 { -self } else { self }
+    }
+}
+
+impl From<f64> for DoubleDouble {
+    fn from(value: f64) -> Self {
+        Self::from_f64(value)
+    }
+}
+
+impl Add for DoubleDouble {
+    type Output = DoubleDouble;
+    fn add(self, other: DoubleDouble) -> DoubleDouble {
+        let (sum, error) = two_sum(self.hi, other.hi);
+        let (low_sum, low_error) = two_sum(self.lo, other.lo);
+        let (sum, error) = quick_two_sum(sum, error + low_sum);
+        let (hi, lo) = quick_two_sum(sum, error + low_error);
+        DoubleDouble { hi, lo }
+    }
+}
+
+impl Sub for DoubleDouble {
+    type Output = DoubleDouble;
+    fn sub(self, other: DoubleDouble) -> DoubleDouble {
+        self + -other
+    }
+}
+
+impl Mul for DoubleDouble {
+    type Output = DoubleDouble;
+    fn mul(self, other: DoubleDouble) -> DoubleDouble {
+        let (product, error) = two_prod(self.hi, other.hi);
+        let error = error + (self.hi * other.lo + self.lo * other.hi);
+        let (hi, lo) = quick_two_sum(product, error);
+        DoubleDouble { hi, lo }
+    }
+}
+
+impl Neg for DoubleDouble {
+    type Output = DoubleDouble;
+    fn neg(self) -> DoubleDouble {
+        DoubleDouble { hi: -self.hi, lo: -self.lo }
+    }
+}
+
+// Exact a + b as a rounded sum and its error (Knuth)
+fn two_sum(a: f64, b: f64) -> (f64, f64) {
+    let sum = a + b;
+    let b_virtual = sum - a;
+    let error = (a - (sum - b_virtual)) + (b - b_virtual);
+    (sum, error)
+}
+
+// two_sum for |a| >= |b|
+fn quick_two_sum(a: f64, b: f64) -> (f64, f64) {
+    let sum = a + b;
+    (sum, b - (sum - a))
+}
+
+// Exact a * b as a rounded product and its error. Uses Dekker's splitting
+// rather than mul_add, which is a slow libm call on targets without FMA.
+fn two_prod(a: f64, b: f64) -> (f64, f64) {
+    let product = a * b;
+    let (a_hi, a_lo) = split(a);
+    let (b_hi, b_lo) = split(b);
+    let error = ((a_hi * b_hi - product) + a_hi * b_lo + a_lo * b_hi) + a_lo * b_lo;
+    (product, error)
+}
+
+fn split(value: f64) -> (f64, f64) {
+    const SPLITTER: f64 = 134217729.0; // 2^27 + 1
+    let scaled = SPLITTER * value;
+    let hi = scaled - (scaled - value);
+    (hi, value - hi)
+}
+
+/// Scalar types the escape-time kernels can iterate in
+pub trait KernelFloat:
+    Copy + Send + Sync + PartialOrd + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + Neg<Output = Self>
+{
+    /// Relative spacing of representable numbers (one ulp at 1.0)
+    const EPSILON: f64;
+
+    fn from_f64(value: f64) -> Self;
+    fn from_bigfloat(value: &BigFloat) -> Self;
+    fn to_f64(self) -> f64;
+    fn abs(self) -> Self;
+}
+
+impl KernelFloat for f32 {
+    const EPSILON: f64 = f32::EPSILON as f64;
+
+    fn from_f64(value: f64) -> Self {
+        value as f32
+    }
+
+    fn from_bigfloat(value: &BigFloat) -> Self {
+        value.to_f64() as f32
+    }
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+
+    fn abs(self) -> Self {
+        f32::abs(self)
+    }
+}
+
+impl KernelFloat for f64 {
+    const EPSILON: f64 = f64::EPSILON;
+
+    fn from_f64(value: f64) -> Self {
+        value
+    }
+
+    fn from_bigfloat(value: &BigFloat) -> Self {
+        value.to_f64()
+    }
+
+    fn to_f64(self) -> f64 {
+        self
+    }
+
+    fn abs(self) -> Self {
+        f64::abs(self)
+    }
+}
+
+impl KernelFloat for DoubleDouble {
+    const EPSILON: f64 = DoubleDouble::EPSILON;
+
+    fn from_f64(value: f64) -> Self {
+        DoubleDouble::from_f64(value)
+    }
+
+    fn from_bigfloat(value: &BigFloat) -> Self {
+        DoubleDouble::from_bigfloat(value)
+    }
+
+    fn to_f64(self) -> f64 {
+        DoubleDouble::to_f64(self)
+    }
+
+    fn abs(self) -> Self {
+        DoubleDouble::abs(self)
+    }
+}
+
+/// Complex number over any kernel float; only the operations the kernels need
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KernelComplex<T> {
+    pub re: T,
+    pub im: T,
+}
+
+impl<T: KernelFloat> KernelComplex<T> {
+    pub fn new(re: T, im: T) -> Self {
+        Self { re, im }
+    }
+
+    pub fn zero() -> Self {
+        Self::new(T::from_f64(0.0), T::from_f64(0.0))
+    }
+
+    pub fn from_f64(value: Complex<f64>) -> Self {
+        Self::new(T::from_f64(value.re), T::from_f64(value.im))
+    }
+
+    pub fn to_f64(self) -> Complex<f64> {
+        Complex::new(self.re.to_f64(), self.im.to_f64())
+    }
+
+    /// |z|^2 rounded to f64, which is all a bailout test needs
+    pub fn norm_sqr(self) -> f64 {
+        (self.re * self.re + self.im * self.im).to_f64()
+    }
+
+    pub fn conj(self) -> Self {
+        Self::new(self.re, -self.im)
+    }
+
+    pub fn square(self) -> Self {
+        let cross = self.re * self.im;
+        Self::new(self.re * self.re - self.im * self.im, cross + cross)
+    }
+
+    pub fn powi(self, power: u32) -> Self {
+        // Square-and-multiply
+        let mut result = Self::new(T::from_f64(1.0), T::from_f64(0.0));
+        let mut base = self;
+        let mut exponent = power;
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result * base;
+            }
+            exponent >>= 1;
+            if exponent > 0 {
+                base = base.square();
+            }
+        }
+        result
+    }
+}
+
+impl<T: KernelFloat> Add for KernelComplex<T> {
+    type Output = Self;
+    fn add(self, other: Self) -> Self {
+        Self::new(self.re + other.re, self.im + other.im)
+    }
+}
+
+impl<T: KernelFloat> Mul for KernelComplex<T> {
+    type Output = Self;
+    fn mul(self, other: Self) -> Self {
+        Self::new(
+            self.re * other.re - self.im * other.im,
+            self.re * other.im + self.im * other.re,
+        )
+    }
+}
@@ -0,0 +1,208 @@
+use crate::builtin;
+use crate::fractal::{FractalGenerator, FractalParams, FractalSample, FractalType};
+use crate::perturbation::PerturbationKind;
+use crate::precision::{DoubleDouble, KernelComplex};
+use num_complex::Complex;
+use std::sync::Arc;
+
+// Everything the generator needs to know about one kind of fractal. The built-in
+// fractals are implemented this way too, and a downstream crate can add its own
+// with `FractalGenerator::register` and select it with `FractalType::Registered`.
+
+/// View that frames the whole fractal
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    pub center_x: f64,
+    pub center_y: f64,
+    pub zoom: f64,
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self { center_x: 0.0, center_y: 0.0, zoom: 1.0 }
+    }
+}
+
+/// One entry in a fractal's parameter schema, with its current value
+#[derive(Debug, Clone, PartialEq)]
+pub struct FractalParameter {
+    pub name: &'static str,
+    pub value: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl FractalParameter {
+    pub fn new(name: &'static str, value: f64, min: f64, max: f64) -> Self {
+        Self { name, value, min, max }
+    }
+}
+
+/// A point of the plane in the float type the generator picked for this view
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlanePoint {
+    F32(KernelComplex<f32>),
+    F64(KernelComplex<f64>),
+    DoubleDouble(KernelComplex<DoubleDouble>),
+}
+
+impl PlanePoint {
+    pub fn to_f64(self) -> Complex<f64> {
+        match self {
+            PlanePoint::F32(point) => point.to_f64(),
+            PlanePoint::F64(point) => point.to_f64(),
+            PlanePoint::DoubleDouble(point) => point.to_f64(),
+        }
+    }
+}
+
+impl From<KernelComplex<f32>> for PlanePoint {
+    fn from(point: KernelComplex<f32>) -> Self {
+        PlanePoint::F32(point)
+    }
+}
+
+impl From<KernelComplex<f64>> for PlanePoint {
+    fn from(point: KernelComplex<f64>) -> Self {
+        PlanePoint::F64(point)
+    }
+}
+
+impl From<KernelComplex<DoubleDouble>> for PlanePoint {
+    fn from(point: KernelComplex<DoubleDouble>) -> Self {
+        PlanePoint::DoubleDouble(point)
+    }
+}
+
+pub trait Fractal: Send + Sync {
+    /// Display name, also the key the registry files the fractal under
+    fn name(&self) -> &str;
+
+    /// Iterates one pixel's point and reports how its orbit behaved. The generator's
+    /// settings (smooth colouring, orbit trap, ...) are there for the kernel to honour.
+    fn iterate(&self, generator: &FractalGenerator, point: PlanePoint, max_iterations: u32) -> FractalSample;
+
+    /// The view a fresh render of this fractal starts from
+    fn default_viewport(&self) -> Viewport {
+        Viewport::default()
+    }
+
+    /// The fractal's parameters and their current values
+    fn parameters(&self) -> Vec<FractalParameter> {
+        Vec::new()
+    }
+
+    /// What `FractalParams` carries to select this fractal
+    fn fractal_type(&self) -> FractalType {
+        FractalType::Registered { name: self.name().to_string() }
+    }
+
+    /// This kind of fractal with the parameters `fractal_type` carries, or `None`
+    /// if it describes another kind. The registry resolves the built-in
+    /// `FractalType` variants by asking each fractal in turn.
+    fn with_type(&self, _fractal_type: &FractalType) -> Option<Arc<dyn Fractal>> {
+        None
+    }
+
+    /// The Julia set of this fractal for `c`: the same iteration with `c` held
+    /// fixed and each pixel as the starting z. `None` if it has no such counterpart.
+    fn julia(&self, _c: Complex<f64>) -> Option<Arc<dyn Fractal>> {
+        None
+    }
+
+    /// Whether `iterate` handles f32 and double-double points itself; otherwise
+    /// it is always handed f64 points
+    fn supports_precision(&self) -> bool {
+        false
+    }
+
+    /// The perturbation engine's equivalent of this fractal, for deep zooms
+    fn perturbation_kind(&self) -> Option<PerturbationKind> {
+        None
+    }
+
+    /// Renders the whole image at once, for fractals that are not one orbit per
+    /// pixel or that sample the view themselves; `None` iterates every pixel
+    fn render(&self, _generator: &FractalGenerator, _params: &FractalParams) -> Option<Vec<Vec<FractalSample>>> {
+        None
+    }
+}
+
+/// The fractals a generator can render by name, in the order the TUI cycles through them
+#[derive(Clone)]
+pub struct FractalRegistry {
+    fractals: Vec<Arc<dyn Fractal>>,
+    // Kinds `resolve` can build that aren't offered by name, i.e. custom formulas
+    unlisted: Vec<Arc<dyn Fractal>>,
+}
+
+impl FractalRegistry {
+    pub fn new() -> Self {
+        Self { fractals: Vec::new(), unlisted: Vec::new() }
+    }
+
+    /// A registry holding every built-in fractal with its default parameters
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+        for fractal in builtin::defaults() {
+            registry.register(fractal);
+        }
+        registry.unlisted.push(builtin::custom_template());
+        registry
+    }
+
+    /// Adds a fractal, replacing any registered under the same name
+    pub fn register(&mut self, fractal: Arc<dyn Fractal>) {
+        match self.position(fractal.name()) {
+            Some(index) => self.fractals[index] = fractal,
+            None => self.fractals.push(fractal),
+        }
+    }
+
+    /// Looks a fractal up by name, ignoring case
+    pub fn get(&self, name: &str) -> Option<Arc<dyn Fractal>> {
+        self.position(name).map(|index| self.fractals[index].clone())
+    }
+
+    /// The fractal a `FractalType` describes, or `None` if nothing registered
+    /// builds it (or a custom formula no longer parses)
+    pub fn resolve(&self, fractal_type: &FractalType) -> Option<Arc<dyn Fractal>> {
+        match fractal_type {
+            FractalType::Registered { name } => self.get(name),
+            FractalType::JuliaOf { parameter_plane, c } => self.resolve(parameter_plane)?.julia(*c),
+            _ => self.fractals.iter().chain(&self.unlisted).find_map(|fractal| fractal.with_type(fractal_type)),
+        }
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.fractals.iter().map(|fractal| fractal.name()).collect()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<dyn Fractal>> {
+        self.fractals.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.fractals.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fractals.is_empty()
+    }
+
+    /// The fractal after the named one, wrapping around; the first if the name is unknown
+    pub fn next_after(&self, name: &str) -> Option<Arc<dyn Fractal>> {
+        let next = self.position(name).map_or(0, |index| (index + 1) % self.fractals.len());
+        self.fractals.get(next).cloned()
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.fractals.iter().position(|fractal| fractal.name().eq_ignore_ascii_case(name))
+    }
+}
+
+impl Default for FractalRegistry {
+    fn default() -> Self {
+        Self::with_builtins()
+    }
+}
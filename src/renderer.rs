@@ -1,7 +1,130 @@
+use crate::config::DisplayConfig;
+use crate::fractal::FractalSample;
+use crate::palette::{Palette, DEFAULT_PALETTE};
 use ratatui::{
     style::{Color, Style},
     text::{Line, Span},
 };
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+// Newton basins cycle through these (fast, slow) colour pairs by root index
+const BASIN_COLORS: [(Color, Color); 6] = [
+    (Color::LightRed, Color::Red),
+    (Color::LightGreen, Color::Green),
+    (Color::LightBlue, Color::Blue),
+    (Color::LightYellow, Color::Yellow),
+    (Color::LightMagenta, Color::Magenta),
+    (Color::LightCyan, Color::Cyan),
+];
+
+// Iterations per step from the densest to the lightest basin shade
+const BASIN_SHADE_STEP: f64 = 5.0;
+
+// How quickly orbit trap brightness falls off with distance in the complex plane
+const TRAP_SHARPNESS: f64 = 4.0;
+
+// Interior points are coloured by the length of the cycle their orbit settles into
+const PERIOD_COLORS: [Color; 8] = [
+    Color::Blue,
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Magenta,
+    Color::Cyan,
+    Color::LightRed,
+    Color::LightGreen,
+];
+
+// Default DE mode threshold: pixels closer than this to the boundary are highlighted
+pub const DEFAULT_DISTANCE_THRESHOLD: f64 = 1.0;
+
+// Bit of each dot in a braille cell, by row and column, from U+2800
+const BRAILLE_DOTS: [[u8; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+// Ordered dither thresholds for the dots of a braille cell: the first two
+// columns of the 4x4 Bayer matrix, ranked, so a brightness of n/8 lights n dots
+const BRAILLE_THRESHOLDS: [[f64; 2]; 4] = [[0.0625, 0.5625], [0.8125, 0.3125], [0.1875, 0.6875], [0.9375, 0.4375]];
+
+/// How samples are laid out in terminal cells
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CellMode {
+    /// One sample per cell, drawn as a shade character
+    #[default]
+    Text,
+    /// A 2x4 block of samples per cell, drawn as dithered braille dots
+    Braille,
+    /// Two samples stacked in each cell, drawn as a half block in the top
+    /// one's colour over the bottom one's
+    #[serde(rename = "half-block")]
+    HalfBlock,
+}
+
+/// What one terminal cell shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+    pub character: char,
+    pub fg: Color,
+    /// Background colour; `None` leaves the terminal's own
+    pub bg: Option<Color>,
+}
+
+impl Cell {
+    const BLANK: Cell = Cell { character: ' ', fg: Color::Black, bg: None };
+
+    fn new((character, fg): (char, Color)) -> Self {
+        Self { character, fg, bg: None }
+    }
+
+    pub fn style(&self) -> Style {
+        let style = Style::default().fg(self.fg);
+        match self.bg {
+            Some(bg) => style.bg(bg),
+            None => style,
+        }
+    }
+}
+
+impl CellMode {
+    pub const ALL: [CellMode; 3] = [CellMode::Text, CellMode::Braille, CellMode::HalfBlock];
+
+    /// Samples across and down that one cell shows
+    pub fn samples_per_cell(self) -> (usize, usize) {
+        match self {
+            CellMode::Text => (1, 1),
+            CellMode::Braille => (2, 4),
+            CellMode::HalfBlock => (1, 2),
+        }
+    }
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&mode| mode == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+impl FromStr for CellMode {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|mode| mode.to_string().eq_ignore_ascii_case(text))
+            .ok_or_else(|| format!("unknown cell mode '{}' (expected text, braille or half-block)", text))
+    }
+}
+
+impl fmt::Display for CellMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CellMode::Text => write!(f, "text"),
+            CellMode::Braille => write!(f, "braille"),
+            CellMode::HalfBlock => write!(f, "half-block"),
+        }
+    }
+}
 
 pub struct TerminalRenderer {
     // Configuration for rendering
@@ -10,7 +133,21 @@ pub struct TerminalRenderer {
     pub use_fast_rendering: bool,
     pub quality_mode: bool,
     pub super_sampling: bool,
-    pub last_rendered_data: Option<Vec<Vec<u32>>>,
+    /// Highlight pixels by their estimated distance to the boundary instead of escape time
+    pub distance_mode: bool,
+    /// DE mode highlight cut-off, in pixels
+    pub distance_threshold: f64,
+    /// Colour interior points by their detected period instead of the in-set colour
+    pub period_coloring: bool,
+    /// Truecolor gradient for escape times; `None` keeps the banded 16-colour scheme
+    pub palette: Option<Palette>,
+    /// Gradients `set_palette` can select by name
+    pub available_palettes: Vec<Palette>,
+    /// Iteration limit of the image being drawn, which the gradient spans
+    pub max_iterations: u32,
+    /// How many samples each cell shows
+    pub cell_mode: CellMode,
+    pub last_rendered_data: Option<Vec<Vec<FractalSample>>>,
 }
 
 impl TerminalRenderer {
@@ -21,10 +158,32 @@ impl TerminalRenderer {
             use_fast_rendering: false,
             quality_mode: true,
             super_sampling: false,
+            distance_mode: false,
+            distance_threshold: DEFAULT_DISTANCE_THRESHOLD,
+            period_coloring: false,
+            palette: None,
+            available_palettes: Palette::builtins(),
+            max_iterations: 256,
+            cell_mode: CellMode::Text,
             last_rendered_data: None,
         }
     }
 
+    /// A renderer with the colour, character set and quality settings from a config
+    pub fn from_config(display: &DisplayConfig) -> Self {
+        let mut renderer = Self::new();
+        renderer.set_use_colors(display.use_colors);
+        renderer.set_use_unicode(display.use_unicode);
+        renderer.set_quality_mode(display.quality_mode);
+        renderer.set_super_sampling(display.super_sampling);
+        renderer.set_cell_mode(display.cell_mode);
+        // Config::validate rejects bad palettes and unknown schemes; an
+        // unvalidated config falls back to the built-ins and default colours
+        renderer.available_palettes = Palette::from_config(display).unwrap_or_else(|_| Palette::builtins());
+        renderer.set_palette(&display.color_scheme).ok();
+        renderer
+    }
+
     pub fn set_fast_rendering(&mut self, enabled: bool) {
         self.use_fast_rendering = enabled;
     }
@@ -37,16 +196,85 @@ impl TerminalRenderer {
         self.super_sampling = enabled;
     }
 
-    pub fn render_to_text(&mut self, fractal_data: &[Vec<u32>], target_width: usize, target_height: usize) -> Vec<Line> {
+    pub fn set_distance_mode(&mut self, enabled: bool) {
+        self.distance_mode = enabled;
+    }
+
+    pub fn set_period_coloring(&mut self, enabled: bool) {
+        self.period_coloring = enabled;
+    }
+
+    pub fn set_cell_mode(&mut self, cell_mode: CellMode) {
+        self.cell_mode = cell_mode;
+    }
+
+    /// The cell mode drawn with; braille and half blocks need Unicode, so ASCII
+    /// output stays on text
+    pub fn effective_cell_mode(&self) -> CellMode {
+        if self.use_unicode { self.cell_mode } else { CellMode::Text }
+    }
+
+    /// Samples to generate to fill `width` x `height` cells
+    pub fn sample_size(&self, (width, height): (usize, usize)) -> (usize, usize) {
+        let (across, down) = self.effective_cell_mode().samples_per_cell();
+        (width * across, height * down)
+    }
+
+    /// Cells across and down a sample grid is drawn in
+    pub fn cell_size(&self, fractal_data: &[Vec<FractalSample>]) -> (usize, usize) {
+        let (across, down) = self.effective_cell_mode().samples_per_cell();
+        let height = fractal_data.len();
+        let width = fractal_data.first().map_or(0, |row| row.len());
+        (width.div_ceil(across), height.div_ceil(down))
+    }
+
+    pub fn set_max_iterations(&mut self, max_iterations: u32) {
+        self.max_iterations = max_iterations.max(1);
+    }
+
+    /// Selects a gradient by name, ignoring case, or the banded scheme for "default"
+    pub fn set_palette(&mut self, name: &str) -> Result<(), String> {
+        if name.eq_ignore_ascii_case(DEFAULT_PALETTE) {
+            self.palette = None;
+            return Ok(());
+        }
+        let palette = self.available_palettes
+            .iter()
+            .find(|palette| palette.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("unknown palette '{}' (available: {})", name, self.palette_names().join(", ")))?;
+        self.palette = Some(palette.clone());
+        Ok(())
+    }
+
+    pub fn palette_name(&self) -> &str {
+        self.palette.as_ref().map_or(DEFAULT_PALETTE, |palette| palette.name.as_str())
+    }
+
+    /// "default" followed by every selectable gradient
+    pub fn palette_names(&self) -> Vec<String> {
+        std::iter::once(DEFAULT_PALETTE.to_string())
+            .chain(self.available_palettes.iter().map(|palette| palette.name.clone()))
+            .collect()
+    }
+
+    pub fn render_to_text(&mut self, fractal_data: &[Vec<u32>], target_width: usize, target_height: usize) -> Vec<Line<'_>> {
+        let samples = Self::to_samples(fractal_data);
+        self.render_samples_to_text(&samples, target_width, target_height)
+    }
+
+    pub fn render_samples_to_text(&mut self, fractal_data: &[Vec<FractalSample>], target_width: usize, target_height: usize) -> Vec<Line<'_>> {
         if fractal_data.is_empty() {
             return vec![Line::from("No fractal data")];
         }
 
         let data_height = fractal_data.len();
         let data_width = fractal_data[0].len();
+        let (cell_width, cell_height) = self.cell_size(fractal_data);
 
-        // Check if we can use differential rendering
+        // Check if we can use differential rendering; it compares single
+        // samples, so only text cells qualify
         let use_differential = self.use_fast_rendering &&
+            self.effective_cell_mode() == CellMode::Text &&
             self.last_rendered_data.as_ref()
                 .map(|last| last.len() == data_height && last[0].len() == data_width)
                 .unwrap_or(false);
@@ -54,16 +282,14 @@ impl TerminalRenderer {
         // Scale the fractal data to fit the target dimensions
         let mut lines = Vec::new();
 
-        for y in 0..target_height.min(data_height) {
+        for y in 0..target_height.min(cell_height) {
             let mut spans = Vec::new();
 
-            for x in 0..target_width.min(data_width) {
-                let iterations = fractal_data[y][x];
-
+            for x in 0..target_width.min(cell_width) {
                 // Skip rendering if pixel hasn't changed (differential rendering)
                 if use_differential {
                     if let Some(ref last_data) = self.last_rendered_data {
-                        if last_data[y][x] == iterations {
+                        if last_data[y][x] == fractal_data[y][x] {
                             // Use cached character for unchanged pixels
                             spans.push(Span::raw(" "));
                             continue;
@@ -71,12 +297,12 @@ impl TerminalRenderer {
                     }
                 }
 
-                let (character, color) = self.iterations_to_char_and_color(iterations);
+                let cell = self.cell(fractal_data, x, y);
 
                 let span = if self.use_colors {
-                    Span::styled(character.to_string(), Style::default().fg(color))
+                    Span::styled(cell.character.to_string(), cell.style())
                 } else {
-                    Span::raw(character.to_string())
+                    Span::raw(cell.character.to_string())
                 };
 
                 spans.push(span);
@@ -93,6 +319,7 @@ impl TerminalRenderer {
         lines
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn render_to_text_with_bounds(
         &mut self,
         fractal_data: &[Vec<u32>],
@@ -102,13 +329,32 @@ impl TerminalRenderer {
         display_height: usize,
         target_width: usize,
         target_height: usize
-    ) -> Vec<Line> {
+    ) -> Vec<Line<'_>> {
+        let samples = Self::to_samples(fractal_data);
+        self.render_samples_to_text_with_bounds(
+            &samples,
+            start_x, start_y,
+            display_width, display_height,
+            target_width, target_height
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_samples_to_text_with_bounds(
+        &mut self,
+        fractal_data: &[Vec<FractalSample>],
+        start_x: usize,
+        start_y: usize,
+        display_width: usize,
+        display_height: usize,
+        target_width: usize,
+        target_height: usize
+    ) -> Vec<Line<'_>> {
         if fractal_data.is_empty() {
             return vec![Line::from("No fractal data")];
         }
 
-        let data_height = fractal_data.len();
-        let data_width = if data_height > 0 { fractal_data[0].len() } else { 0 };
+        let (data_width, data_height) = self.cell_size(fractal_data);
 
         let mut lines = Vec::new();
 
@@ -120,7 +366,7 @@ impl TerminalRenderer {
             let mut spans = Vec::new();
 
             for target_x in 0..target_width {
-                let char_and_color = if target_y >= center_offset_y &&
+                let cell = if target_y >= center_offset_y &&
                                        target_y < center_offset_y + display_height &&
                                        target_x >= center_offset_x &&
                                        target_x < center_offset_x + display_width {
@@ -129,20 +375,19 @@ impl TerminalRenderer {
                     let fractal_y = start_y + (target_y - center_offset_y);
 
                     if fractal_y < data_height && fractal_x < data_width {
-                        let iterations = fractal_data[fractal_y][fractal_x];
-                        self.iterations_to_char_and_color(iterations)
+                        self.cell(fractal_data, fractal_x, fractal_y)
                     } else {
-                        (' ', Color::Black) // Outside fractal bounds
+                        Cell::BLANK // Outside fractal bounds
                     }
                 } else {
                     // We're in the padding area
-                    (' ', Color::Black)
+                    Cell::BLANK
                 };
 
                 let span = if self.use_colors {
-                    Span::styled(char_and_color.0.to_string(), Style::default().fg(char_and_color.1))
+                    Span::styled(cell.character.to_string(), cell.style())
                 } else {
-                    Span::raw(char_and_color.0.to_string())
+                    Span::raw(cell.character.to_string())
                 };
 
                 spans.push(span);
@@ -154,6 +399,158 @@ impl TerminalRenderer {
         lines
     }
 
+    fn to_samples(fractal_data: &[Vec<u32>]) -> Vec<Vec<FractalSample>> {
+        fractal_data
+            .iter()
+            .map(|row| row.iter().map(|&iterations| FractalSample::new(iterations)).collect())
+            .collect()
+    }
+
+    /// Cell (`x`, `y`) of the grid `cell_size` gives
+    pub fn cell(&self, fractal_data: &[Vec<FractalSample>], x: usize, y: usize) -> Cell {
+        match self.effective_cell_mode() {
+            CellMode::Text => Cell::new(self.sample_to_char_and_color(&fractal_data[y][x])),
+            CellMode::Braille => Cell::new(self.braille_to_char_and_color(fractal_data, x, y)),
+            CellMode::HalfBlock => self.half_block_cell(fractal_data, x, y),
+        }
+    }
+
+    fn half_block_cell(&self, fractal_data: &[Vec<FractalSample>], x: usize, y: usize) -> Cell {
+        // Blank samples show the terminal background, like blank text cells
+        let color = |row: usize| {
+            let sample = fractal_data.get(row)?.get(x)?;
+            let (character, color) = self.sample_to_char_and_color(sample);
+            (character != ' ').then_some(color)
+        };
+        match (color(y * 2), color(y * 2 + 1)) {
+            (Some(top), Some(bottom)) if self.use_colors => Cell { character: '▀', fg: top, bg: Some(bottom) },
+            (Some(top), Some(_)) => Cell { character: '█', fg: top, bg: None },
+            (Some(top), None) => Cell { character: '▀', fg: top, bg: None },
+            (None, Some(bottom)) => Cell { character: '▄', fg: bottom, bg: None },
+            (None, None) => Cell::BLANK,
+        }
+    }
+
+    fn braille_to_char_and_color(&self, fractal_data: &[Vec<FractalSample>], x: usize, y: usize) -> (char, Color) {
+        // Each sample's brightness is dithered into its dot, and the cell takes
+        // the average colour of the dots that are lit
+        let mut dots = 0;
+        let mut colors = Vec::with_capacity(8);
+        for (row, thresholds) in BRAILLE_THRESHOLDS.iter().enumerate() {
+            let Some(samples) = fractal_data.get(y * 4 + row) else { break };
+            for (column, &threshold) in thresholds.iter().enumerate() {
+                let Some(sample) = samples.get(x * 2 + column) else { continue };
+                let (character, color) = self.sample_to_char_and_color(sample);
+                let (red, green, blue) = color_to_rgb(color);
+                let brightness = glyph_coverage(character) * red.max(green).max(blue) as f64 / 255.0;
+                if brightness > threshold {
+                    dots |= BRAILLE_DOTS[row][column];
+                    colors.push(color);
+                }
+            }
+        }
+        let Some(&first) = colors.first() else { return (' ', Color::Black) };
+        let glyph = char::from_u32(0x2800 + dots as u32).unwrap_or(' ');
+        // A block in one named colour keeps it, so the terminal's theme still applies
+        if colors.iter().all(|&color| color == first) {
+            return (glyph, first);
+        }
+        let total = colors.iter().map(|&color| color_to_rgb(color)).fold((0, 0, 0), |(red, green, blue), (r, g, b)| {
+            (red + r as usize, green + g as usize, blue + b as usize)
+        });
+        let count = colors.len();
+        (glyph, Color::Rgb((total.0 / count) as u8, (total.1 / count) as u8, (total.2 / count) as u8))
+    }
+
+    /// The character and colour a sample is drawn with
+    pub fn sample_to_char_and_color(&self, sample: &FractalSample) -> (char, Color) {
+        if let Some(color) = sample.color {
+            return self.density_to_char_and_color(color);
+        }
+        if let Some(root) = sample.root {
+            return self.basin_to_char_and_color(root, sample.value());
+        }
+        if let (true, Some(period)) = (self.period_coloring, sample.period) {
+            return self.period_to_char_and_color(period);
+        }
+        if let (true, Some(distance)) = (self.distance_mode, sample.distance) {
+            return self.distance_to_char_and_color(distance);
+        }
+        if let Some(distance) = sample.trap_distance {
+            return self.trap_to_char_and_color(distance);
+        }
+
+        // Without colours a gradient would be solid blocks, so keep the bands
+        if let (true, Some(palette)) = (self.use_colors, &self.palette) {
+            return self.gradient_to_char_and_color(palette, sample);
+        }
+
+        // Smooth counts move the band edges onto the continuous potential
+        // instead of the integer escape-time contours
+        self.iterations_to_char_and_color(sample.value() as u32)
+    }
+
+    fn gradient_to_char_and_color(&self, palette: &Palette, sample: &FractalSample) -> (char, Color) {
+        // Escaped points carry a smooth count when smooth colouring is on, so
+        // only points with neither are taken to be inside
+        if sample.smooth.is_none() && sample.iterations >= self.max_iterations {
+            let (red, green, blue) = palette.interior;
+            return (if self.use_unicode { '█' } else { '#' }, Color::Rgb(red, green, blue));
+        }
+        let value = sample.value().max(0.0);
+        let (red, green, blue) = match palette.exterior {
+            Some(exterior) if sample.iterations <= 1 => exterior,
+            _ => palette.color_at(palette.position(value, self.max_iterations)),
+        };
+        // The colour carries the detail, so cells are solid; ASCII keeps its
+        // banded characters so plain text output still shows the shape
+        let character = if self.use_unicode { '█' } else { self.iterations_to_ascii_char_and_color(value as u32).0 };
+        (character, Color::Rgb(red, green, blue))
+    }
+
+    fn basin_to_char_and_color(&self, root: usize, iterations: f64) -> (char, Color) {
+        // Quickly converging points are dense and bright, slow ones fade towards the basin edges
+        let shades = if self.use_unicode { ['█', '▓', '▒', '░'] } else { ['#', '%', '+', '.'] };
+        let shade = ((iterations / BASIN_SHADE_STEP) as usize).min(shades.len() - 1);
+        let (fast, slow) = BASIN_COLORS[root % BASIN_COLORS.len()];
+        (shades[shade], if shade < shades.len() / 2 { fast } else { slow })
+    }
+
+    fn density_to_char_and_color(&self, (red, green, blue): (u8, u8, u8)) -> (char, Color) {
+        // The colour carries the density; the character only needs to follow the overall brightness
+        let shades = if self.use_unicode { [' ', '░', '▒', '▓', '█'] } else { [' ', '.', ':', '*', '#'] };
+        let brightness = red.max(green).max(blue) as usize;
+        let shade = (brightness * shades.len() / 256).min(shades.len() - 1);
+        (shades[shade], Color::Rgb(red, green, blue))
+    }
+
+    fn period_to_char_and_color(&self, period: u32) -> (char, Color) {
+        let solid = if self.use_unicode { '█' } else { '#' };
+        (solid, PERIOD_COLORS[(period.max(1) as usize - 1) % PERIOD_COLORS.len()])
+    }
+
+    fn distance_to_char_and_color(&self, distance: f64) -> (char, Color) {
+        // Pixels straddling the boundary are solid, a thin halo around them shows filaments
+        // too fine for escape-time banding, and everything farther out is blank
+        let (solid, halo) = if self.use_unicode { ('█', '▒') } else { ('#', ':') };
+        if distance < self.distance_threshold {
+            (solid, Color::White)
+        } else if distance < self.distance_threshold * 4.0 {
+            (halo, Color::Gray)
+        } else {
+            (' ', Color::Black)
+        }
+    }
+
+    fn trap_to_char_and_color(&self, distance: f64) -> (char, Color) {
+        // Orbits that pass right over the trap are solid and bright, distant ones fade to nothing
+        let shades = if self.use_unicode { [' ', '·', '░', '▒', '▓', '█'] } else { [' ', '.', ':', '+', '*', '#'] };
+        let colors = [Color::Black, Color::DarkGray, Color::Blue, Color::Cyan, Color::LightCyan, Color::White];
+        let closeness = (-distance.max(0.0) * TRAP_SHARPNESS).exp();
+        let level = ((closeness * shades.len() as f64) as usize).min(shades.len() - 1);
+        (shades[level], colors[level])
+    }
+
     fn iterations_to_char_and_color(&self, iterations: u32) -> (char, Color) {
         if self.use_unicode {
             self.iterations_to_unicode_char_and_color(iterations)
@@ -252,12 +649,16 @@ impl TerminalRenderer {
 
     // Method to render fractal data to a simple string (for debugging or text output)
     pub fn render_to_string(&self, fractal_data: &[Vec<u32>]) -> String {
+        self.render_samples_to_string(&Self::to_samples(fractal_data))
+    }
+
+    pub fn render_samples_to_string(&self, fractal_data: &[Vec<FractalSample>]) -> String {
         let mut result = String::new();
+        let (width, height) = self.cell_size(fractal_data);
         
-        for row in fractal_data {
-            for &iterations in row {
-                let (character, _) = self.iterations_to_char_and_color(iterations);
-                result.push(character);
+        for y in 0..height {
+            for x in 0..width {
+                result.push(self.cell(fractal_data, x, y).character);
             }
             result.push('\n');
         }
@@ -265,6 +666,41 @@ impl TerminalRenderer {
         result
     }
 
+    /// Text with 24-bit ANSI colour escapes, for terminals and files outside the TUI
+    pub fn render_samples_to_ansi(&self, fractal_data: &[Vec<FractalSample>]) -> String {
+        if !self.use_colors {
+            return self.render_samples_to_string(fractal_data);
+        }
+
+        let mut result = String::new();
+        let (width, height) = self.cell_size(fractal_data);
+        for y in 0..height {
+            let (mut current_fg, mut current_bg) = (None, None);
+            for x in 0..width {
+                let cell = self.cell(fractal_data, x, y);
+                // Only emit an escape when a colour changes
+                if current_fg != Some(cell.fg) {
+                    let (red, green, blue) = color_to_rgb(cell.fg);
+                    result.push_str(&format!("\x1b[38;2;{};{};{}m", red, green, blue));
+                    current_fg = Some(cell.fg);
+                }
+                if current_bg != cell.bg {
+                    match cell.bg {
+                        Some(bg) => {
+                            let (red, green, blue) = color_to_rgb(bg);
+                            result.push_str(&format!("\x1b[48;2;{};{};{}m", red, green, blue));
+                        },
+                        None => result.push_str("\x1b[49m"),
+                    }
+                    current_bg = cell.bg;
+                }
+                result.push(cell.character);
+            }
+            result.push_str("\x1b[0m\n");
+        }
+        result
+    }
+
     // Method to get color palette information
     pub fn get_color_info(&self) -> Vec<(String, Color)> {
         vec![
@@ -280,6 +716,63 @@ impl TerminalRenderer {
     }
 }
 
+// How much of its cell a shade character fills, so sparse shades dither to few dots
+fn glyph_coverage(character: char) -> f64 {
+    match character {
+        ' ' => 0.0,
+        '·' | '.' => 0.125,
+        '░' | ':' => 0.25,
+        '▒' => 0.5,
+        '▓' => 0.75,
+        _ => 1.0,
+    }
+}
+
+/// RGB value of a terminal colour, using the xterm defaults for the named ones
+pub fn color_to_rgb(color: Color) -> (u8, u8, u8) {
+    match color {
+        Color::Rgb(red, green, blue) => (red, green, blue),
+        Color::Reset | Color::Black => (0, 0, 0),
+        Color::Red => (205, 0, 0),
+        Color::Green => (0, 205, 0),
+        Color::Yellow => (205, 205, 0),
+        Color::Blue => (0, 0, 238),
+        Color::Magenta => (205, 0, 205),
+        Color::Cyan => (0, 205, 205),
+        Color::Gray => (229, 229, 229),
+        Color::DarkGray => (127, 127, 127),
+        Color::LightRed => (255, 0, 0),
+        Color::LightGreen => (0, 255, 0),
+        Color::LightYellow => (255, 255, 0),
+        Color::LightBlue => (92, 92, 255),
+        Color::LightMagenta => (255, 0, 255),
+        Color::LightCyan => (0, 255, 255),
+        Color::White => (255, 255, 255),
+        Color::Indexed(index) => indexed_to_rgb(index),
+    }
+}
+
+// The xterm 256-colour table: 16 system colours, a 6x6x6 cube, then a grey ramp
+fn indexed_to_rgb(index: u8) -> (u8, u8, u8) {
+    const SYSTEM: [Color; 16] = [
+        Color::Black, Color::Red, Color::Green, Color::Yellow, Color::Blue, Color::Magenta, Color::Cyan, Color::Gray,
+        Color::DarkGray, Color::LightRed, Color::LightGreen, Color::LightYellow, Color::LightBlue, Color::LightMagenta,
+        Color::LightCyan, Color::White,
+    ];
+    match index {
+        0..=15 => color_to_rgb(SYSTEM[index as usize]),
+        16..=231 => {
+            let level = |value: u8| if value == 0 { 0 } else { 55 + value * 40 };
+            let cube = index - 16;
+            (level(cube / 36), level(cube / 6 % 6), level(cube % 6))
+        },
+        _ => {
+            let grey = 8 + (index - 232) * 10;
+            (grey, grey, grey)
+        },
+    }
+}
+
 impl Default for TerminalRenderer {
     fn default() -> Self {
         Self::new()
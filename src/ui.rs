@@ -76,6 +76,7 @@ impl UI {
         f.render_widget(info_widget, popup_area);
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn render_parameter_panel(
         f: &mut Frame,
         area: Rect,
@@ -15,6 +15,7 @@ fn test_full_fractal_generation_pipeline() {
         center_x: -0.5,
         center_y: 0.0,
         max_iterations: 100,
+        ..Default::default()
     };
     
     let fractal_data = generator.generate(&params);
@@ -36,6 +37,7 @@ fn test_performance_mode_affects_generation() {
         center_x: -0.5,
         center_y: 0.0,
         max_iterations: 100,
+        ..Default::default()
     };
     
     // Generate without performance mode
@@ -68,6 +70,7 @@ fn test_adaptive_sampling_at_high_zoom() {
         center_x: -0.5,
         center_y: 0.0,
         max_iterations: 100,
+        ..Default::default()
     };
     
     // Test with adaptive sampling
@@ -97,12 +100,13 @@ fn test_different_fractal_types() {
         center_x: -0.5,
         center_y: 0.0,
         max_iterations: 50,
+        ..Default::default()
     };
     
     // Test Mandelbrot
     let mandelbrot_params = FractalParams {
         fractal_type: FractalType::Mandelbrot,
-        ..base_params
+        ..base_params.clone()
     };
     let mandelbrot_result = generator.generate(&mandelbrot_params);
     assert_eq!(mandelbrot_result.len(), 8);
@@ -110,7 +114,7 @@ fn test_different_fractal_types() {
     // Test Julia Set
     let julia_params = FractalParams {
         fractal_type: FractalType::Julia { c: Complex::new(-0.7, 0.27) },
-        ..base_params
+        ..base_params.clone()
     };
     let julia_result = generator.generate(&julia_params);
     assert_eq!(julia_result.len(), 8);
@@ -118,7 +122,7 @@ fn test_different_fractal_types() {
     // Test Burning Ship
     let burning_ship_params = FractalParams {
         fractal_type: FractalType::BurningShip,
-        ..base_params
+        ..base_params.clone()
     };
     let burning_ship_result = generator.generate(&burning_ship_params);
     assert_eq!(burning_ship_result.len(), 8);
@@ -126,7 +130,7 @@ fn test_different_fractal_types() {
     // Test Tricorn
     let tricorn_params = FractalParams {
         fractal_type: FractalType::Tricorn,
-        ..base_params
+        ..base_params.clone()
     };
     let tricorn_result = generator.generate(&tricorn_params);
     assert_eq!(tricorn_result.len(), 8);
@@ -134,7 +138,7 @@ fn test_different_fractal_types() {
     // Test Multibrot
     let multibrot_params = FractalParams {
         fractal_type: FractalType::Multibrot { power: 3.0 },
-        ..base_params
+        ..base_params.clone()
     };
     let multibrot_result = generator.generate(&multibrot_params);
     assert_eq!(multibrot_result.len(), 8);
@@ -217,6 +221,7 @@ fn test_zoom_and_pan_bounds() {
         center_x: -0.5,
         center_y: 0.0,
         max_iterations: 50,
+        ..Default::default()
     };
     
     let result = generator.generate(&high_zoom_params);
@@ -232,6 +237,7 @@ fn test_zoom_and_pan_bounds() {
         center_x: 100.0,
         center_y: 100.0,
         max_iterations: 50,
+        ..Default::default()
     };
     
     let result2 = generator.generate(&extreme_pan_params);
@@ -302,3 +308,97 @@ fn test_renderer_with_bounds() {
         assert_eq!(line.spans.len(), 6);
     }
 }
+
+// A fractal defined outside the crate: points escape once |z| passes the radius
+struct Disc {
+    radius: f64,
+}
+
+impl Fractal for Disc {
+    fn name(&self) -> &str {
+        "Disc"
+    }
+
+    fn iterate(&self, _generator: &FractalGenerator, point: PlanePoint, max_iterations: u32) -> FractalSample {
+        let inside = point.to_f64().norm() <= self.radius;
+        FractalSample::new(if inside { max_iterations } else { 0 })
+    }
+
+    fn default_viewport(&self) -> Viewport {
+        Viewport { zoom: 2.0, ..Viewport::default() }
+    }
+
+    fn parameters(&self) -> Vec<FractalParameter> {
+        vec![FractalParameter::new("radius", self.radius, 0.0, 2.0)]
+    }
+}
+
+#[test]
+fn test_registered_fractal() {
+    let mut generator = FractalGenerator::new();
+    let builtins = generator.registry.len();
+    generator.register(std::sync::Arc::new(Disc { radius: 1.0 }));
+    assert_eq!(generator.registry.len(), builtins + 1);
+    assert_eq!(generator.registry.names().last(), Some(&"Disc"));
+
+    let disc = generator.registry.get("disc").unwrap();
+    assert_eq!(disc.fractal_type(), FractalType::Registered { name: "Disc".to_string() });
+    assert_eq!(disc.parameters()[0].value, 1.0);
+
+    let params = FractalParams {
+        fractal_type: disc.fractal_type(),
+        width: 9,
+        height: 9,
+        center_x: 0.0,
+        max_iterations: 50,
+        ..Default::default()
+    };
+    // Plain f64 kernels only, however deep the zoom
+    assert_eq!(generator.effective_precision(&FractalParams { zoom: 1e20, ..params.clone() }), Precision::F64);
+    assert!(!generator.uses_perturbation(&FractalParams { zoom: 1e40, ..params.clone() }));
+
+    // Pixel (x, y) sits at -2 + 4x/9, so the middle pixels are inside the unit disc and the corners are not
+    let data = generator.generate(&params);
+    assert_eq!(data[4][4], 50);
+    assert_eq!(data[0][0], 0);
+
+    // Re-registering under the same name replaces the old entry
+    generator.register(std::sync::Arc::new(Disc { radius: 0.1 }));
+    assert_eq!(generator.registry.len(), builtins + 1);
+    assert_eq!(generator.generate(&params)[4][4], 0);
+
+    // Unknown names render as an empty image rather than failing
+    let missing = FractalParams { fractal_type: FractalType::Registered { name: "Nope".to_string() }, ..params };
+    assert!(generator.generate(&missing).iter().flatten().all(|&iterations| iterations == 0));
+}
+
+#[test]
+fn test_builtins_are_registered() {
+    let registry = FractalRegistry::with_builtins();
+    for name in ["Mandelbrot", "Julia Set", "Burning Ship", "Tricorn", "Multibrot", "Newton", "Buddhabrot", "Nebulabrot"] {
+        let fractal = registry.get(name).unwrap();
+        // Built-ins map back to their own FractalType variant
+        assert!(!matches!(fractal.fractal_type(), FractalType::Registered { .. }));
+    }
+    assert_eq!(registry.next_after("Mandelbrot").unwrap().name(), "Burning Ship");
+    assert_eq!(registry.next_after("Nebulabrot").unwrap().name(), "Mandelbrot");
+    assert_eq!(registry.get("Mandelbrot").unwrap().default_viewport().center_x, -0.5);
+
+    // Every built-in variant, parameters included, resolves through the registry
+    let newton = Polynomial::parse("z^4 - 1").unwrap();
+    let types = [
+        FractalType::Julia { c: Complex::new(0.3, 0.5) },
+        FractalType::Multibrot { power: 5.0 },
+        FractalType::Newton { polynomial: newton },
+        FractalType::Nebulabrot { limits: [10, 20, 30] },
+        FractalType::Custom { equation: "sin(z) * c".to_string() },
+        FractalType::JuliaOf { parameter_plane: Box::new(FractalType::Tricorn), c: Complex::new(-0.1, 0.6) },
+    ];
+    for fractal_type in types {
+        assert_eq!(registry.resolve(&fractal_type).unwrap().fractal_type(), fractal_type);
+    }
+    assert!(registry.resolve(&FractalType::Custom { equation: "z^".to_string() }).is_none());
+    // Custom formulas resolve without being offered by name
+    assert!(registry.get("Custom").is_none());
+    assert!(FractalRegistry::new().resolve(&FractalType::Mandelbrot).is_none());
+}